@@ -0,0 +1,114 @@
+use std::sync::atomic::Ordering;
+
+use tokio_util::sync::CancellationToken;
+
+use crate::RespVersion;
+
+use super::Backend;
+
+/// What CLIENT LIST reports about one connection
+#[derive(Debug, Clone)]
+pub struct ClientInfo {
+    pub id: u64,
+    /// `ip:port` for TCP, `path:0` for unix socket connections
+    pub addr: String,
+    pub name: String,
+    pub cmd: String,
+    pub resp: RespVersion,
+    /// cancelled by CLIENT KILL or shutdown, the connection's handler exits when it fires
+    pub kill: CancellationToken,
+}
+
+/// Keeps a connection listed in the client registry until it is dropped
+#[derive(Debug)]
+pub struct ClientHandle {
+    backend: Backend,
+    id: u64,
+}
+
+impl ClientInfo {
+    // one CLIENT LIST line, in the same `key=value` layout as redis
+    pub fn line(&self) -> String {
+        let resp = match self.resp {
+            RespVersion::Resp2 => 2,
+            RespVersion::Resp3 => 3,
+        };
+        format!(
+            "id={} addr={} name={} db=0 cmd={} resp={}",
+            self.id, self.addr, self.name, self.cmd, resp
+        )
+    }
+}
+
+impl ClientHandle {
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+
+    pub fn killed(&self) -> CancellationToken {
+        self.backend
+            .clients
+            .get(&self.id)
+            .map(|info| info.kill.clone())
+            .unwrap_or_default()
+    }
+
+    /// Update the registry entry after the connection ran a command
+    pub fn record(&self, cmd: &str, resp: RespVersion) {
+        if let Some(mut info) = self.backend.clients.get_mut(&self.id) {
+            info.cmd = cmd.to_string();
+            info.resp = resp;
+        }
+    }
+}
+
+impl Drop for ClientHandle {
+    fn drop(&mut self) {
+        self.backend.clients.remove(&self.id);
+        if let Some(metrics) = &self.backend.metrics {
+            metrics.on_connection(-1);
+        }
+    }
+}
+
+impl Backend {
+    /// Register a new connection, ids are never reused within a process
+    pub fn register_client(&self, addr: String) -> ClientHandle {
+        let id = self.next_client_id.fetch_add(1, Ordering::Relaxed) + 1;
+        self.clients.insert(
+            id,
+            ClientInfo {
+                id,
+                addr,
+                name: String::new(),
+                cmd: "NULL".to_string(),
+                resp: RespVersion::default(),
+                kill: self.shutdown.child_token(),
+            },
+        );
+        if let Some(metrics) = &self.metrics {
+            metrics.on_connection(1);
+        }
+        ClientHandle {
+            backend: self.clone(),
+            id,
+        }
+    }
+
+    /// Close every connection matching `filter`, returning how many were killed
+    pub fn kill_clients(&self, filter: impl Fn(&ClientInfo) -> bool) -> usize {
+        let mut killed = 0;
+        for info in self.clients.iter().filter(|info| filter(info)) {
+            info.kill.cancel();
+            killed += 1;
+        }
+        killed
+    }
+
+    /// Connected clients ordered by id
+    pub fn client_list(&self) -> Vec<ClientInfo> {
+        let mut clients: Vec<ClientInfo> = self.clients.iter().map(|c| c.clone()).collect();
+        clients.sort_by_key(|c| c.id);
+        clients
+    }
+}
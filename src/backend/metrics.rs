@@ -0,0 +1,11 @@
+use std::{fmt::Debug, time::Duration};
+
+/// Hooks for embedders to observe the server, e.g. to forward to StatsD or OpenTelemetry.
+/// Every method defaults to a no-op so implementations only override what they need.
+pub trait Metrics: Debug + Send + Sync {
+    /// A command finished executing, `name` is lowercase
+    fn on_command(&self, _name: &str, _dur: Duration) {}
+
+    /// A connection was opened (+1) or closed (-1)
+    fn on_connection(&self, _delta: i64) {}
+}
@@ -1,16 +1,83 @@
-use std::{ops::Deref, sync::Arc};
+mod client;
+mod metrics;
 
-use dashmap::DashMap;
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    ops::Deref,
+    sync::{atomic::AtomicU64, Arc},
+    time::{Duration, Instant},
+};
 
-use crate::RespFrame;
+use dashmap::{DashMap, DashSet};
+use tokio_util::{sync::CancellationToken, task::TaskTracker};
+
+use crate::{RespFrame, RespMap, ServerConfig};
+
+pub use client::{ClientHandle, ClientInfo};
+pub use metrics::Metrics;
 
 #[derive(Debug, Clone)]
 pub struct Backend(Arc<BackInner>);
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum KeyKind {
+    String,
+    Hash,
+    List,
+    Set,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct MemoryEstimate {
+    pub keys: usize,
+    pub strings: usize,
+    pub hashes: usize,
+    pub lists: usize,
+    pub sets: usize,
+    pub overhead: usize,
+}
+
 #[derive(Debug)]
 pub struct BackInner {
+    pub config: ServerConfig,
     pub map: DashMap<String, RespFrame>,
     pub hmap: DashMap<String, DashMap<String, RespFrame>>,
+    pub list: DashMap<String, VecDeque<RespFrame>>,
+    pub set: DashMap<String, HashSet<Vec<u8>>>,
+    /// strings modified in place (APPEND, BITFIELD), redis always reports those as `raw`
+    pub raw_strings: DashSet<String>,
+    /// hashes that outgrew the listpack limits, like redis they never convert back
+    pub hashtable_hashes: DashSet<String>,
+    /// lists longer than `list_max_listpack_size`
+    pub quicklist_lists: DashSet<String>,
+    /// sets holding a non-integer member, reported as `listpack` while they stay small
+    pub listpack_sets: DashSet<String>,
+    /// sets that outgrew the intset/listpack limits, they never convert back either
+    pub hashtable_sets: DashSet<String>,
+    /// deadlines of keys with a TTL, expired keys are removed lazily when touched
+    pub expires: DashMap<String, Instant>,
+    /// currently open connections by client id
+    pub clients: DashMap<u64, ClientInfo>,
+    /// cancelled on shutdown, every client's kill token is a child of it
+    pub shutdown: CancellationToken,
+    /// the connection handlers, so shutdown can wait for them to finish
+    pub connections: TaskTracker,
+    next_client_id: AtomicU64,
+    /// instrumentation hooks installed by the embedder
+    pub metrics: Option<Arc<dyn Metrics>>,
+    // only maintained when `config.type_index` is on, one set per kind so writes to different
+    // keys don't contend on a single lock
+    type_index: Option<HashMap<KeyKind, DashSet<String>>>,
+}
+
+impl MemoryEstimate {
+    pub fn dataset(&self) -> usize {
+        self.strings + self.hashes + self.lists + self.sets
+    }
+
+    pub fn total(&self) -> usize {
+        self.dataset() + self.overhead
+    }
 }
 
 impl Deref for Backend {
@@ -23,9 +90,34 @@ impl Deref for Backend {
 
 impl BackInner {
     pub fn new() -> Self {
+        Self::with_config(ServerConfig::default())
+    }
+
+    pub fn with_config(config: ServerConfig) -> Self {
+        let type_index = config.type_index.then(|| {
+            [KeyKind::String, KeyKind::Hash, KeyKind::List, KeyKind::Set]
+                .into_iter()
+                .map(|kind| (kind, DashSet::new()))
+                .collect()
+        });
         Self {
+            config,
             map: DashMap::new(),
             hmap: DashMap::new(),
+            list: DashMap::new(),
+            set: DashMap::new(),
+            raw_strings: DashSet::new(),
+            hashtable_hashes: DashSet::new(),
+            quicklist_lists: DashSet::new(),
+            listpack_sets: DashSet::new(),
+            hashtable_sets: DashSet::new(),
+            expires: DashMap::new(),
+            clients: DashMap::new(),
+            shutdown: CancellationToken::new(),
+            connections: TaskTracker::new(),
+            next_client_id: AtomicU64::new(0),
+            metrics: None,
+            type_index,
         }
     }
 }
@@ -47,26 +139,554 @@ impl Backend {
         Self::default()
     }
 
+    pub fn with_config(config: ServerConfig) -> Self {
+        Self(Arc::new(BackInner::with_config(config)))
+    }
+
+    /// Same as `with_config`, reporting to `metrics`
+    pub fn with_metrics(config: ServerConfig, metrics: Arc<dyn Metrics>) -> Self {
+        Self(Arc::new(BackInner {
+            metrics: Some(metrics),
+            ..BackInner::with_config(config)
+        }))
+    }
+
     pub fn get(&self, key: &str) -> Option<RespFrame> {
+        self.expire_if_needed(key);
         self.map.get(key).map(|r| r.value().clone())
     }
 
+    /// Overwriting a key also drops its TTL and whatever type it held before, like redis SET
     pub fn set(&self, key: String, value: RespFrame) {
+        if !matches!(self.key_kind(&key), None | Some(KeyKind::String)) {
+            self.del(&key);
+        }
+        self.index_key(&key, KeyKind::String);
+        self.raw_strings.remove(&key);
+        self.expires.remove(&key);
         self.map.insert(key, value);
     }
 
     pub fn hget(&self, key: &str, field: &str) -> Option<RespFrame> {
+        self.expire_if_needed(key);
         self.hmap
             .get(key)
             .and_then(|m| m.get(field).map(|r| r.value().clone()))
     }
 
     pub fn hgetall(&self, key: &str) -> Option<DashMap<String, RespFrame>> {
+        self.expire_if_needed(key);
         self.hmap.get(key).map(|m| m.clone())
     }
 
     pub fn hset(&self, key: String, field: String, value: RespFrame) {
-        let hmap = self.hmap.entry(key).or_default();
+        self.expire_if_needed(&key);
+        self.index_key(&key, KeyKind::Hash);
+        let too_long = field.len().max(value_len(&value)) > self.config.hash_max_listpack_value;
+        let hmap = self.hmap.entry(key.clone()).or_default();
         hmap.insert(field, value);
+        if too_long || hmap.len() > self.config.hash_max_listpack_entries {
+            self.hashtable_hashes.insert(key);
+        }
+    }
+
+    /// The encoding bookkeeping of `hset`, for writers that update a hash in place. `len` is the
+    /// number of fields after the write
+    pub fn track_hash_write(&self, key: &str, len: usize, field: &str, value: &RespFrame) {
+        let too_long = field.len().max(value_len(value)) > self.config.hash_max_listpack_value;
+        if too_long || len > self.config.hash_max_listpack_entries {
+            self.hashtable_hashes.insert(key.to_string());
+        }
+    }
+
+    /// Record the length of a list after a push or pop. Like redis 7.2 a quicklist only turns
+    /// back into a listpack once it shrinks to half the limit, so it doesn't flip on every write
+    pub fn track_list_len(&self, key: &str, len: usize) {
+        let limit = self.config.list_max_listpack_size;
+        if len > limit {
+            self.quicklist_lists.insert(key.to_string());
+        } else if len <= limit / 2 {
+            self.quicklist_lists.remove(key);
+        }
+    }
+
+    /// Same for sets, `added` are the members just inserted and `len` the size after the write.
+    /// A set starts as an intset, the first non-integer member turns it into a listpack
+    pub fn track_set_write(&self, key: &str, len: usize, added: &[Vec<u8>]) {
+        if self.hashtable_sets.contains(key) {
+            return;
+        }
+        let listpack = self.listpack_sets.contains(key) || !added.iter().all(|m| is_int_member(m));
+        let too_big = match listpack {
+            true => {
+                len > self.config.set_max_listpack_entries
+                    || added
+                        .iter()
+                        .any(|m| m.len() > self.config.set_max_listpack_value)
+            }
+            false => len > self.config.set_max_intset_entries,
+        };
+        if too_big {
+            self.listpack_sets.remove(key);
+            self.hashtable_sets.insert(key.to_string());
+        } else if listpack {
+            self.listpack_sets.insert(key.to_string());
+        }
+    }
+
+    /// Drop the encoding bookkeeping of a set that is gone
+    pub fn untrack_set(&self, key: &str) {
+        self.listpack_sets.remove(key);
+        self.hashtable_sets.remove(key);
+    }
+
+    /// The whole hash as a map frame, for moving hashes in and out programmatically
+    pub fn export_hash(&self, key: &str) -> Option<RespMap> {
+        self.expire_if_needed(key);
+        self.hmap.get(key).map(|hmap| {
+            let mut map = RespMap::new();
+            for field in hmap.iter() {
+                map.insert(field.key().clone(), field.value().clone());
+            }
+            map
+        })
+    }
+
+    /// Replace whatever `key` holds with the fields of `map`, an empty map just deletes the key
+    pub fn import_hash(&self, key: String, map: RespMap) {
+        self.del(&key);
+        for (field, value) in map.0 {
+            self.hset(key.clone(), field, value);
+        }
+    }
+
+    /// Remove one field, dropping the whole hash once its last field is gone like redis does
+    pub fn hdel(&self, key: &str, field: &str) -> bool {
+        self.expire_if_needed(key);
+        let removed = self
+            .hmap
+            .get(key)
+            .is_some_and(|hmap| hmap.remove(field).is_some());
+        if removed
+            && self
+                .hmap
+                .remove_if(key, |_, hmap| hmap.is_empty())
+                .is_some()
+        {
+            self.hashtable_hashes.remove(key);
+            self.expires.remove(key);
+            self.unindex_key(key, KeyKind::Hash);
+        }
+        removed
+    }
+
+    pub fn key_kind(&self, key: &str) -> Option<KeyKind> {
+        self.expire_if_needed(key);
+        if self.map.contains_key(key) {
+            Some(KeyKind::String)
+        } else if self.hmap.contains_key(key) {
+            Some(KeyKind::Hash)
+        } else if self.list.contains_key(key) {
+            Some(KeyKind::List)
+        } else if self.set.contains_key(key) {
+            Some(KeyKind::Set)
+        } else {
+            None
+        }
+    }
+
+    /// Record a key as holding `kind`, writers that bypass `set`/`hset` must call this themselves
+    pub fn index_key(&self, key: &str, kind: KeyKind) {
+        if let Some(index) = &self.type_index {
+            index[&kind].insert(key.to_string());
+        }
+    }
+
+    /// Drop a key from the type index once it no longer holds `kind`
+    pub fn unindex_key(&self, key: &str, kind: KeyKind) {
+        if let Some(index) = &self.type_index {
+            index[&kind].remove(key);
+        }
+    }
+
+    /// All keys holding `kind`, served from the type index when it is enabled
+    pub fn keys_of_kind(&self, kind: KeyKind) -> Vec<String> {
+        match &self.type_index {
+            Some(index) => index[&kind].iter().map(|key| key.clone()).collect(),
+            None => match kind {
+                KeyKind::String => self.map.iter().map(|e| e.key().clone()).collect(),
+                KeyKind::Hash => self.hmap.iter().map(|e| e.key().clone()).collect(),
+                KeyKind::List => self.list.iter().map(|e| e.key().clone()).collect(),
+                KeyKind::Set => self.set.iter().map(|e| e.key().clone()).collect(),
+            },
+        }
+    }
+
+    /// Set a key to expire `secs` seconds from now, false if the key doesn't exist
+    pub fn expire(&self, key: &str, secs: u64) -> bool {
+        self.expire_in(key, Duration::from_secs(secs))
+    }
+
+    /// Same as `expire` with any duration, also false if the deadline can't be represented.
+    /// Commands reject such TTLs up front with `checked_ttl`
+    pub fn expire_in(&self, key: &str, ttl: Duration) -> bool {
+        let Some(deadline) = Instant::now().checked_add(ttl) else {
+            return false;
+        };
+        if !self.exists(key) {
+            return false;
+        }
+        self.expires.insert(key.to_string(), deadline);
+        true
+    }
+
+    /// Seconds left to live, -2 for a missing key and -1 for a key without expiry
+    pub fn ttl(&self, key: &str) -> i64 {
+        if !self.exists(key) {
+            return -2;
+        }
+        match self.expires.get(key) {
+            // 四舍五入到秒, 和 redis 一致
+            Some(deadline) => {
+                let left = deadline.saturating_duration_since(Instant::now());
+                ((left.as_millis() + 500) / 1000) as i64
+            }
+            None => -1,
+        }
+    }
+
+    /// Same as `ttl`, in milliseconds
+    pub fn pttl(&self, key: &str) -> i64 {
+        if !self.exists(key) {
+            return -2;
+        }
+        match self.expires.get(key) {
+            Some(deadline) => deadline
+                .saturating_duration_since(Instant::now())
+                .as_millis() as i64,
+            None => -1,
+        }
+    }
+
+    fn is_expired(&self, key: &str) -> bool {
+        self.expires
+            .get(key)
+            .is_some_and(|deadline| *deadline <= Instant::now())
+    }
+
+    // lazy expiration: a key past its deadline is deleted the first time it is touched
+    fn expire_if_needed(&self, key: &str) {
+        if self.is_expired(key) {
+            self.del(key);
+        }
+    }
+
+    /// Remove a key from every store along with its TTL and bookkeeping, returns whether it
+    /// existed. Every delete path goes through here so nothing is left behind.
+    pub fn del(&self, key: &str) -> bool {
+        let removed = match (
+            self.map.remove(key),
+            self.hmap.remove(key),
+            self.list.remove(key),
+            self.set.remove(key),
+        ) {
+            (Some(_), _, _, _) => Some(KeyKind::String),
+            (_, Some(_), _, _) => Some(KeyKind::Hash),
+            (_, _, Some(_), _) => Some(KeyKind::List),
+            (_, _, _, Some(_)) => Some(KeyKind::Set),
+            (None, None, None, None) => None,
+        };
+        self.raw_strings.remove(key);
+        self.hashtable_hashes.remove(key);
+        self.quicklist_lists.remove(key);
+        self.untrack_set(key);
+        self.expires.remove(key);
+        match removed {
+            Some(kind) => {
+                self.unindex_key(key, kind);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Move the value at `from` to `to` along with its TTL, replacing whatever `to` held.
+    /// `ttl` replaces the moved TTL when given. False if `from` doesn't exist
+    pub fn rename(&self, from: &str, to: &str, ttl: Option<Duration>) -> bool {
+        let Some(kind) = self.key_kind(from) else {
+            return false;
+        };
+        let deadline = match ttl {
+            Some(ttl) => Instant::now().checked_add(ttl),
+            None => self.expires.get(from).map(|deadline| *deadline),
+        };
+        if from == to {
+            if let Some(deadline) = deadline {
+                self.expires.insert(to.to_string(), deadline);
+            }
+            return true;
+        }
+        self.del(to);
+        let to = to.to_string();
+        // ttl 先于值写到 to 上, 其他连接看到 to 时它已经带着 ttl
+        if let Some(deadline) = deadline {
+            self.expires.insert(to.clone(), deadline);
+        }
+        match kind {
+            KeyKind::String => {
+                if let Some((_, value)) = self.map.remove(from) {
+                    self.map.insert(to.clone(), value);
+                }
+            }
+            KeyKind::Hash => {
+                if let Some((_, hmap)) = self.hmap.remove(from) {
+                    self.hmap.insert(to.clone(), hmap);
+                }
+            }
+            KeyKind::List => {
+                if let Some((_, list)) = self.list.remove(from) {
+                    self.list.insert(to.clone(), list);
+                }
+            }
+            KeyKind::Set => {
+                if let Some((_, set)) = self.set.remove(from) {
+                    self.set.insert(to.clone(), set);
+                }
+            }
+        }
+        if self.raw_strings.remove(from).is_some() {
+            self.raw_strings.insert(to.clone());
+        }
+        for tracked in [
+            &self.hashtable_hashes,
+            &self.quicklist_lists,
+            &self.listpack_sets,
+            &self.hashtable_sets,
+        ] {
+            if tracked.remove(from).is_some() {
+                tracked.insert(to.clone());
+            }
+        }
+        self.expires.remove(from);
+        self.unindex_key(from, kind);
+        self.index_key(&to, kind);
+        true
+    }
+
+    /// The name TYPE reports for the value at `key`
+    pub fn type_of(&self, key: &str) -> &'static str {
+        match self.key_kind(key) {
+            Some(KeyKind::String) => "string",
+            Some(KeyKind::Hash) => "hash",
+            Some(KeyKind::List) => "list",
+            Some(KeyKind::Set) => "set",
+            None => "none",
+        }
+    }
+
+    pub fn exists(&self, key: &str) -> bool {
+        self.expire_if_needed(key);
+        self.map.contains_key(key)
+            || self.hmap.contains_key(key)
+            || self.list.contains_key(key)
+            || self.set.contains_key(key)
+    }
+
+    /// Delete every key past its deadline, not just the ones being touched
+    pub fn purge_expired(&self) {
+        let now = Instant::now();
+        let expired: Vec<String> = self
+            .expires
+            .iter()
+            .filter(|deadline| *deadline.value() <= now)
+            .map(|deadline| deadline.key().clone())
+            .collect();
+        // 重新检查一次, 期间可能被重新设置了
+        for key in expired {
+            self.expire_if_needed(&key);
+        }
+    }
+
+    /// Number of keys in the keyspace, a key only ever lives in one store so the sum counts
+    /// each key once. Expired keys nobody touched yet are purged first so they don't count
+    pub fn dbsize(&self) -> usize {
+        self.purge_expired();
+        self.map.len() + self.hmap.len() + self.list.len() + self.set.len()
+    }
+
+    /// Every key currently in the keyspace, whatever it holds
+    pub fn keys(&self) -> Vec<String> {
+        self.map
+            .iter()
+            .map(|e| e.key().clone())
+            .chain(self.hmap.iter().map(|e| e.key().clone()))
+            .chain(self.list.iter().map(|e| e.key().clone()))
+            .chain(self.set.iter().map(|e| e.key().clone()))
+            .filter(|key| !self.is_expired(key))
+            .collect()
+    }
+
+    /// Estimate the memory used by every store: payload bytes per store plus per-entry overhead
+    pub fn memory_estimate(&self) -> MemoryEstimate {
+        let entry_size = std::mem::size_of::<String>() + std::mem::size_of::<RespFrame>();
+        let mut estimate = MemoryEstimate::default();
+        for entry in self.map.iter() {
+            estimate.keys += 1;
+            estimate.strings += entry.key().len() + entry.value().memory_estimate();
+            estimate.overhead += entry_size;
+        }
+        for entry in self.hmap.iter() {
+            estimate.keys += 1;
+            estimate.hashes += entry.key().len();
+            estimate.overhead += entry_size;
+            for field in entry.value().iter() {
+                estimate.hashes += field.key().len() + field.value().memory_estimate();
+                estimate.overhead += entry_size;
+            }
+        }
+        for entry in self.list.iter() {
+            estimate.keys += 1;
+            estimate.lists += entry.key().len();
+            estimate.overhead += entry_size;
+            for item in entry.value().iter() {
+                estimate.lists += item.memory_estimate();
+                estimate.overhead += std::mem::size_of::<RespFrame>();
+            }
+        }
+        for entry in self.set.iter() {
+            estimate.keys += 1;
+            estimate.sets += entry.key().len();
+            estimate.overhead += entry_size;
+            for member in entry.value().iter() {
+                estimate.sets += member.len();
+                estimate.overhead += std::mem::size_of::<Vec<u8>>();
+            }
+        }
+        estimate
+    }
+
+    pub fn flush(&self) {
+        self.map.clear();
+        self.hmap.clear();
+        self.list.clear();
+        self.set.clear();
+        self.raw_strings.clear();
+        self.hashtable_hashes.clear();
+        self.quicklist_lists.clear();
+        self.listpack_sets.clear();
+        self.hashtable_sets.clear();
+        self.expires.clear();
+        if let Some(index) = &self.type_index {
+            index.values().for_each(DashSet::clear);
+        }
+    }
+}
+
+// redis keeps a set in an intset while every member is a canonical 64 bit integer
+fn is_int_member(member: &[u8]) -> bool {
+    std::str::from_utf8(member)
+        .ok()
+        .and_then(|s| s.parse::<i64>().ok().filter(|i| i.to_string() == s))
+        .is_some()
+}
+
+// the byte length redis would see for a hash value
+fn value_len(value: &RespFrame) -> usize {
+    match value {
+        RespFrame::BulkString(s) => s.0.as_ref().map_or(0, |s| s.len()),
+        RespFrame::SimpleString(s) => s.0.len(),
+        RespFrame::Integer(i) => i.to_string().len(),
+        frame => frame.memory_estimate(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::BulkString;
+
+    use super::*;
+
+    fn sorted(mut keys: Vec<String>) -> Vec<String> {
+        keys.sort();
+        keys
+    }
+
+    #[test]
+    fn test_type_index_matches_keyspace() {
+        let indexed = Backend::with_config(ServerConfig {
+            type_index: true,
+            ..Default::default()
+        });
+        let scanned = Backend::new();
+        for backend in [&indexed, &scanned] {
+            backend.set("a".to_string(), BulkString::new("1").into());
+            backend.set("b".to_string(), BulkString::new("2").into());
+            backend.set("a".to_string(), BulkString::new("3").into());
+            backend.hset("h1".to_string(), "f".to_string(), RespFrame::Integer(1));
+            backend.hset("h1".to_string(), "g".to_string(), RespFrame::Integer(2));
+            backend.hset("h2".to_string(), "f".to_string(), RespFrame::Integer(1));
+        }
+
+        for kind in [KeyKind::String, KeyKind::Hash] {
+            assert_eq!(
+                sorted(indexed.keys_of_kind(kind)),
+                sorted(scanned.keys_of_kind(kind))
+            );
+        }
+        assert_eq!(sorted(indexed.keys_of_kind(KeyKind::Hash)), ["h1", "h2"]);
+
+        indexed.map.remove("b");
+        indexed.unindex_key("b", KeyKind::String);
+        assert_eq!(indexed.keys_of_kind(KeyKind::String), ["a"]);
+
+        indexed.flush();
+        assert!(indexed.keys_of_kind(KeyKind::String).is_empty());
+        assert!(indexed.keys_of_kind(KeyKind::Hash).is_empty());
+    }
+
+    #[test]
+    fn test_lazy_expiration() {
+        let backend = Backend::new();
+        assert!(!backend.expire("key", 10));
+        assert_eq!(backend.ttl("key"), -2);
+
+        backend.set("key".to_string(), BulkString::new("value").into());
+        assert_eq!(backend.ttl("key"), -1);
+        assert!(backend.expire("key", 10));
+        assert_eq!(backend.ttl("key"), 10);
+
+        backend.set("key".to_string(), BulkString::new("value").into());
+        assert_eq!(backend.ttl("key"), -1);
+
+        assert!(backend.expire_in("key", Duration::from_millis(20)));
+        std::thread::sleep(Duration::from_millis(40));
+        assert!(backend.get("key").is_none());
+        assert!(!backend.map.contains_key("key"));
+        assert!(backend.expires.is_empty());
+    }
+
+    #[test]
+    fn test_export_import_hash() {
+        let backend = Backend::new();
+        assert!(backend.export_hash("h").is_none());
+        backend.hset(
+            "h".to_string(),
+            "f1".to_string(),
+            BulkString::new("v1").into(),
+        );
+        backend.hset("h".to_string(), "f2".to_string(), RespFrame::Integer(2));
+
+        let exported = backend.export_hash("h").unwrap();
+        assert_eq!(exported.len(), 2);
+
+        let other = Backend::new();
+        other.set("h".to_string(), BulkString::new("string").into());
+        other.import_hash("h".to_string(), exported.clone());
+        assert_eq!(other.type_of("h"), "hash");
+        assert_eq!(other.export_hash("h"), Some(exported));
+        assert_eq!(other.hget("h", "f2"), Some(RespFrame::Integer(2)));
+
+        other.import_hash("h".to_string(), RespMap::new());
+        assert!(!other.exists("h"));
     }
 }
@@ -0,0 +1,622 @@
+use std::time::Duration;
+
+use crate::{
+    BigNumber, BulkString, KeyKind, RespArray, RespFrame, RespMap, RespNull, RespSet, SimpleError,
+    SimpleString,
+};
+
+use super::{
+    check_key_limit, extract_args, subcommand, CommandError, CommandExecutor, DebugCommand,
+    MemoryCommand, ObjectCommand, RESP_OK,
+};
+
+// redis 中 embstr 编码的最大长度
+const EMBSTR_SIZE_LIMIT: usize = 44;
+
+const PROTOCOL_TYPES: [&str; 14] = [
+    "string", "integer", "double", "bignum", "null", "array", "set", "map", "attrib", "verbatim",
+    "true", "false", "push", "err",
+];
+
+impl CommandExecutor for DebugCommand {
+    fn execute(self, backend: &crate::Backend) -> RespFrame {
+        match self {
+            DebugCommand::Sleep(duration) => {
+                std::thread::sleep(duration);
+                RESP_OK.clone()
+            }
+            DebugCommand::Protocol(ty) => protocol_example(&ty),
+            // redis 的字段里这里只有这两个能如实给出, 不编造 lru 之类的值
+            DebugCommand::Object(key) => match encoding(backend, &key) {
+                Some(encoding) => {
+                    SimpleString::new(format!("refcount:1 encoding:{}", encoding)).into()
+                }
+                None => SimpleError::new("ERR no such key").into(),
+            },
+            DebugCommand::Populate {
+                count,
+                prefix,
+                size,
+            } => {
+                // the parser can't see the config, so the size is checked before allocating
+                if size.is_some_and(|size| size > backend.config.proto_max_bulk_len) {
+                    return SimpleError::new("ERR string exceeds maximum allowed size").into();
+                }
+                // straight into the backend, existing keys are left alone like redis does
+                for i in 0..count {
+                    let key = format!("{}:{}", prefix, i);
+                    if backend.key_kind(&key).is_some() {
+                        continue;
+                    }
+                    if let Err(e) = check_key_limit(backend, &[&key]) {
+                        return e.into();
+                    }
+                    let mut value = format!("value:{}", i).into_bytes();
+                    if let Some(size) = size {
+                        value.resize(size, 0);
+                    }
+                    backend.set(key, BulkString::new(value).into());
+                }
+                RESP_OK.clone()
+            }
+            #[cfg(test)]
+            DebugCommand::Panic => panic!("DEBUG PANIC"),
+        }
+    }
+}
+
+impl TryFrom<RespArray> for DebugCommand {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        let args = extract_args(value, 1)?;
+        match (subcommand(&args, 0).as_deref(), args.get(1), args.len()) {
+            (Some("sleep"), Some(RespFrame::BulkString(secs)), 2) => {
+                let secs = String::from_utf8_lossy(secs.as_ref());
+                // try_from_secs_f64 rejects negative, nan and too large values instead of panicking
+                match secs.parse::<f64>().map(Duration::try_from_secs_f64) {
+                    Ok(Ok(secs)) => Ok(DebugCommand::Sleep(secs)),
+                    _ => Err(CommandError::InvalidArgument(format!(
+                        "Invalid sleep time: {}",
+                        secs
+                    ))),
+                }
+            }
+            #[cfg(test)]
+            (Some("panic"), _, 1) => Ok(DebugCommand::Panic),
+            (Some("populate"), Some(RespFrame::BulkString(count)), 2..=4) => {
+                let invalid = || CommandError::InvalidArgument("value is out of range".to_string());
+                let count = String::from_utf8_lossy(count.as_ref())
+                    .parse()
+                    .map_err(|_| invalid())?;
+                let prefix = match args.get(2) {
+                    Some(RespFrame::BulkString(prefix)) => {
+                        String::from_utf8_lossy(prefix.as_ref()).into_owned()
+                    }
+                    _ => "key".to_string(),
+                };
+                let size = match args.get(3) {
+                    Some(RespFrame::BulkString(size)) => Some(
+                        String::from_utf8_lossy(size.as_ref())
+                            .parse()
+                            .map_err(|_| invalid())?,
+                    ),
+                    _ => None,
+                };
+                Ok(DebugCommand::Populate {
+                    count,
+                    prefix,
+                    size,
+                })
+            }
+            (Some("object"), Some(RespFrame::BulkString(key)), 2) => Ok(DebugCommand::Object(
+                String::from_utf8(key.0.clone().unwrap_or_default())?,
+            )),
+            (Some("protocol"), Some(RespFrame::BulkString(ty)), 2) => {
+                let ty = String::from_utf8_lossy(ty.as_ref()).to_ascii_lowercase();
+                if PROTOCOL_TYPES.contains(&ty.as_str()) {
+                    Ok(DebugCommand::Protocol(ty))
+                } else {
+                    Err(CommandError::InvalidArgument(format!(
+                        "Wrong protocol type name. Please use one of the following: {}",
+                        PROTOCOL_TYPES.join("|")
+                    )))
+                }
+            }
+            (Some(sub), _, _) => Err(CommandError::InvalidCommand(format!(
+                "Unknown DEBUG subcommand or wrong arguments: {}",
+                sub
+            ))),
+            (None, _, _) => Err(CommandError::InvalidArgument(
+                "DEBUG requires a subcommand".to_string(),
+            )),
+        }
+    }
+}
+
+// the same sample replies redis gives, attributes and verbatim strings have no frame here and
+// fall back to bulk strings like they do on RESP2
+#[allow(clippy::approx_constant)]
+fn protocol_example(ty: &str) -> RespFrame {
+    let numbers = || -> Vec<RespFrame> { (0..3).map(RespFrame::Integer).collect() };
+    match ty {
+        "string" => BulkString::new("Hello World").into(),
+        "integer" => RespFrame::Integer(12345),
+        "double" => RespFrame::Double(3.141),
+        "bignum" => BigNumber::from(1234567999999999999999999999999999999i128).into(),
+        "null" => RespFrame::Null(RespNull),
+        "array" | "push" => RespArray::new(numbers()).into(),
+        "set" => RespSet::new(numbers()).into(),
+        "map" => {
+            let mut map = RespMap::new();
+            for i in 0..3 {
+                map.insert(i.to_string(), RespFrame::Boolean(i == 1));
+            }
+            map.into()
+        }
+        "attrib" => BulkString::new("Some real reply following the attribute").into(),
+        "verbatim" => BulkString::new("This is a verbatim\nstring").into(),
+        "true" => RespFrame::Boolean(true),
+        "false" => RespFrame::Boolean(false),
+        "err" => SimpleError::new("ERR This is an error").into(),
+        _ => SimpleString::new("OK").into(),
+    }
+}
+
+impl CommandExecutor for MemoryCommand {
+    fn execute(self, backend: &crate::Backend) -> RespFrame {
+        let estimate = backend.memory_estimate();
+        match self {
+            MemoryCommand::Doctor => {
+                let report = if estimate.keys == 0 {
+                    "Hi Sam, this instance is empty or is using very little memory, my issues detector can't be used in these conditions. Please, leave for your mission on Earth and fill it with some data. The new Sam and I will be back to our programming as soon as I finished rebooting.".to_string()
+                } else if estimate.overhead > estimate.dataset() {
+                    format!(
+                        "Sam, I detected a few issues in this Redis instance memory implants:\n\n * High overhead: the per-key bookkeeping ({} bytes) is larger than the data stored ({} bytes). Grouping many tiny keys into hashes would save memory.\n\nI'm here to keep you safe, Sam. I want to help you.",
+                        estimate.overhead,
+                        estimate.dataset()
+                    )
+                } else {
+                    "Hi Sam, I can't find any memory issue in your instance. I can only account for what occurs on this base.".to_string()
+                };
+                BulkString::new(report).into()
+            }
+            MemoryCommand::Stats => {
+                let total = estimate.total();
+                let percentage = if total == 0 {
+                    0.0
+                } else {
+                    estimate.dataset() as f64 * 100.0 / total as f64
+                };
+                let mut map = RespMap::new();
+                map.insert("total.allocated".to_string(), (total as i64).into());
+                map.insert(
+                    "overhead.total".to_string(),
+                    (estimate.overhead as i64).into(),
+                );
+                map.insert("keys.count".to_string(), (estimate.keys as i64).into());
+                map.insert(
+                    "dataset.bytes".to_string(),
+                    (estimate.dataset() as i64).into(),
+                );
+                map.insert("dataset.percentage".to_string(), percentage.into());
+                map.insert(
+                    "strings.bytes".to_string(),
+                    (estimate.strings as i64).into(),
+                );
+                map.insert("hashes.bytes".to_string(), (estimate.hashes as i64).into());
+                map.insert("lists.bytes".to_string(), (estimate.lists as i64).into());
+                map.insert("sets.bytes".to_string(), (estimate.sets as i64).into());
+                map.into()
+            }
+        }
+    }
+}
+
+impl TryFrom<RespArray> for MemoryCommand {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        let args = extract_args(value, 1)?;
+        match (subcommand(&args, 0).as_deref(), args.len()) {
+            (Some("doctor"), 1) => Ok(MemoryCommand::Doctor),
+            (Some("stats"), 1) => Ok(MemoryCommand::Stats),
+            (Some(sub), _) => Err(CommandError::InvalidCommand(format!(
+                "Unknown MEMORY subcommand or wrong arguments: {}",
+                sub
+            ))),
+            (None, _) => Err(CommandError::InvalidArgument(
+                "MEMORY requires a subcommand".to_string(),
+            )),
+        }
+    }
+}
+
+impl CommandExecutor for ObjectCommand {
+    fn execute(self, backend: &crate::Backend) -> RespFrame {
+        match self {
+            ObjectCommand::Encoding(key) => match encoding(backend, &key) {
+                Some(encoding) => BulkString::new(encoding).into(),
+                None => RespFrame::Null(RespNull),
+            },
+        }
+    }
+}
+
+// OBJECT ENCODING 和 DEBUG OBJECT 共用, key 不存在时返回 None
+fn encoding(backend: &crate::Backend, key: &str) -> Option<&'static str> {
+    match backend.key_kind(key)? {
+        KeyKind::String if backend.raw_strings.contains(key) => Some("raw"),
+        KeyKind::String => backend.get(key).map(|value| string_encoding(&value)),
+        KeyKind::Hash if backend.hashtable_hashes.contains(key) => Some("hashtable"),
+        KeyKind::Hash => Some("listpack"),
+        KeyKind::List if backend.quicklist_lists.contains(key) => Some("quicklist"),
+        KeyKind::List => Some("listpack"),
+        KeyKind::Set if backend.hashtable_sets.contains(key) => Some("hashtable"),
+        KeyKind::Set if backend.listpack_sets.contains(key) => Some("listpack"),
+        KeyKind::Set => Some("intset"),
+    }
+}
+
+// the encoding redis would pick when the value is freshly stored
+fn string_encoding(value: &RespFrame) -> &'static str {
+    let bytes = match value {
+        RespFrame::Integer(_) => return "int",
+        RespFrame::BulkString(s) => s.0.as_deref().unwrap_or_default(),
+        RespFrame::SimpleString(s) => s.0.as_bytes(),
+        _ => return "raw",
+    };
+    // only the canonical form counts, "010" or "+1" stay strings
+    let canonical = std::str::from_utf8(bytes)
+        .ok()
+        .and_then(|s| s.parse::<i64>().ok().filter(|i| i.to_string() == s));
+    if canonical.is_some() {
+        "int"
+    } else if bytes.len() <= EMBSTR_SIZE_LIMIT {
+        "embstr"
+    } else {
+        "raw"
+    }
+}
+
+impl TryFrom<RespArray> for ObjectCommand {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        let args = extract_args(value, 1)?;
+        match (subcommand(&args, 0).as_deref(), args.get(1), args.len()) {
+            (Some("encoding"), Some(RespFrame::BulkString(key)), 2) => Ok(ObjectCommand::Encoding(
+                String::from_utf8(key.0.clone().unwrap_or_default())?,
+            )),
+            (Some(sub), _, _) => Err(CommandError::InvalidCommand(format!(
+                "Unknown OBJECT subcommand or wrong arguments: {}",
+                sub
+            ))),
+            (None, _, _) => Err(CommandError::InvalidArgument(
+                "OBJECT requires a subcommand".to_string(),
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::BytesMut;
+
+    use crate::{
+        cmd::{Append, DbSize, LPop, LPush, RPush, SAdd, SRem, SetRange},
+        Backend, RespDecode, RespEncode,
+    };
+
+    use super::*;
+
+    #[test]
+    fn test_debug_sleep() -> anyhow::Result<()> {
+        let mut buf = BytesMut::from("*3\r\n$5\r\nDEBUG\r\n$5\r\nSLEEP\r\n$3\r\n0.5\r\n");
+        let frame = RespArray::decode(&mut buf)?;
+
+        let debug: DebugCommand = frame.try_into()?;
+        assert!(matches!(debug, DebugCommand::Sleep(d) if d == Duration::from_millis(500)));
+
+        for secs in ["inf", "1e300", "nan", "-1", "abc"] {
+            let frame = RespArray::new(vec![
+                BulkString::new("debug").into(),
+                BulkString::new("sleep").into(),
+                BulkString::new(secs).into(),
+            ]);
+            assert!(DebugCommand::try_from(frame).is_err(), "{}", secs);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_debug_protocol() -> anyhow::Result<()> {
+        let backend = Backend::new();
+        for (ty, prefix) in [
+            ("double", b','),
+            ("bignum", b'('),
+            ("MAP", b'%'),
+            ("set", b'~'),
+            ("true", b'#'),
+        ] {
+            let frame = RespArray::new(vec![
+                BulkString::new("debug").into(),
+                BulkString::new("protocol").into(),
+                BulkString::new(ty).into(),
+            ]);
+            let debug: DebugCommand = frame.try_into()?;
+            assert_eq!(debug.execute(&backend).encode()[0], prefix, "type {}", ty);
+        }
+
+        let mut buf = BytesMut::from("*3\r\n$5\r\ndebug\r\n$8\r\nprotocol\r\n$3\r\nfoo\r\n");
+        let frame = RespArray::decode(&mut buf)?;
+        assert!(DebugCommand::try_from(frame).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_debug_populate() -> anyhow::Result<()> {
+        let mut buf = BytesMut::from("*3\r\n$5\r\ndebug\r\n$8\r\npopulate\r\n$4\r\n1000\r\n");
+        let frame = RespArray::decode(&mut buf)?;
+        let debug: DebugCommand = frame.try_into()?;
+
+        let backend = Backend::new();
+        backend.set("key:7".to_string(), BulkString::new("kept").into());
+        assert_eq!(debug.execute(&backend), RESP_OK.clone());
+        assert_eq!(DbSize.execute(&backend), RespFrame::Integer(1000));
+        assert_eq!(backend.get("key:7"), Some(BulkString::new("kept").into()));
+        assert_eq!(
+            backend.get("key:8"),
+            Some(BulkString::new("value:8").into())
+        );
+
+        let mut buf = BytesMut::from(
+            "*5\r\n$5\r\ndebug\r\n$8\r\npopulate\r\n$1\r\n2\r\n$3\r\nfoo\r\n$2\r\n10\r\n",
+        );
+        let frame = RespArray::decode(&mut buf)?;
+        let debug: DebugCommand = frame.try_into()?;
+        debug.execute(&backend);
+        assert_eq!(
+            backend.get("foo:1"),
+            Some(BulkString::new(b"value:1\0\0\0".to_vec()).into())
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_debug_populate_limits() {
+        let backend = Backend::with_config(crate::ServerConfig {
+            maxkeys: Some(5),
+            proto_max_bulk_len: 16,
+            ..Default::default()
+        });
+        let populate = |count: u64, size: Option<usize>| {
+            DebugCommand::Populate {
+                count,
+                prefix: "key".to_string(),
+                size,
+            }
+            .execute(&backend)
+        };
+
+        assert_eq!(
+            populate(1, Some(17)),
+            SimpleError::new("ERR string exceeds maximum allowed size").into()
+        );
+        assert_eq!(backend.dbsize(), 0);
+
+        assert_eq!(populate(10, Some(16)), CommandError::KeyLimit.into());
+        assert_eq!(backend.dbsize(), 5);
+        // 已存在的 key 会被跳过, 不占额度
+        assert_eq!(populate(5, None), RESP_OK.clone());
+    }
+
+    #[test]
+    fn test_debug_object() -> anyhow::Result<()> {
+        let mut buf = BytesMut::from("*3\r\n$5\r\ndebug\r\n$6\r\nobject\r\n$1\r\nk\r\n");
+        let frame = RespArray::decode(&mut buf)?;
+        let debug: DebugCommand = frame.try_into()?;
+
+        let backend = Backend::new();
+        let object = || DebugCommand::Object("k".to_string()).execute(&backend);
+        assert_eq!(object(), SimpleError::new("ERR no such key").into());
+
+        backend.set("k".to_string(), BulkString::new("v").into());
+        assert_eq!(
+            object(),
+            SimpleString::new("refcount:1 encoding:embstr").into()
+        );
+
+        // only fields redis also reports, the ttl is left to TTL/PTTL
+        backend.expire("k", 10);
+        assert_eq!(
+            debug.execute(&backend),
+            SimpleString::new("refcount:1 encoding:embstr").into()
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_memory_stats() -> anyhow::Result<()> {
+        let mut buf = BytesMut::from("*2\r\n$6\r\nmemory\r\n$5\r\nstats\r\n");
+        let frame = RespArray::decode(&mut buf)?;
+        let cmd: MemoryCommand = frame.try_into()?;
+
+        let backend = Backend::new();
+        backend.set("key".to_string(), BulkString::new("value").into());
+        backend.hset(
+            "hash".to_string(),
+            "field".to_string(),
+            BulkString::new("value").into(),
+        );
+
+        let RespFrame::Map(stats) = cmd.execute(&backend) else {
+            panic!("MEMORY STATS should return a map");
+        };
+        assert_eq!(stats.get("keys.count"), Some(&RespFrame::Integer(2)));
+        assert_eq!(stats.get("strings.bytes"), Some(&RespFrame::Integer(8)));
+        assert_eq!(stats.get("hashes.bytes"), Some(&RespFrame::Integer(14)));
+        assert_eq!(stats.get("dataset.bytes"), Some(&RespFrame::Integer(22)));
+        Ok(())
+    }
+
+    #[test]
+    fn test_memory_doctor() {
+        let backend = Backend::new();
+        let RespFrame::BulkString(report) = MemoryCommand::Doctor.execute(&backend) else {
+            panic!("MEMORY DOCTOR should return a bulk string");
+        };
+        assert!(String::from_utf8_lossy(report.as_ref()).contains("instance is empty"));
+    }
+
+    #[test]
+    fn test_object_encoding_transitions() -> anyhow::Result<()> {
+        let mut buf = BytesMut::from("*3\r\n$6\r\nOBJECT\r\n$8\r\nENCODING\r\n$1\r\nk\r\n");
+        let frame = RespArray::decode(&mut buf)?;
+        let cmd: ObjectCommand = frame.try_into()?;
+
+        let backend = Backend::new();
+        assert_eq!(cmd.execute(&backend), RespFrame::Null(RespNull));
+
+        let encoding =
+            |backend: &Backend| ObjectCommand::Encoding("k".to_string()).execute(backend);
+        backend.set("k".to_string(), BulkString::new("10").into());
+        assert_eq!(encoding(&backend), BulkString::new("int").into());
+
+        let append = Append {
+            key: "k".to_string(),
+            value: b"x".to_vec(),
+        };
+        append.execute(&backend);
+        assert_eq!(encoding(&backend), BulkString::new("raw").into());
+
+        backend.set("k".to_string(), BulkString::new("10").into());
+        let setrange = SetRange {
+            key: "k".to_string(),
+            offset: 0,
+            value: b"2".to_vec(),
+        };
+        setrange.execute(&backend);
+        assert_eq!(backend.get("k"), Some(BulkString::new("20").into()));
+        assert_eq!(encoding(&backend), BulkString::new("raw").into());
+
+        backend.set("k".to_string(), BulkString::new("010").into());
+        assert_eq!(encoding(&backend), BulkString::new("embstr").into());
+        backend.set("k".to_string(), BulkString::new("x".repeat(45)).into());
+        assert_eq!(encoding(&backend), BulkString::new("raw").into());
+        backend.set("k".to_string(), RespFrame::Integer(10));
+        assert_eq!(encoding(&backend), BulkString::new("int").into());
+        Ok(())
+    }
+
+    #[test]
+    fn test_object_encoding_for_hashes() {
+        let backend = Backend::with_config(crate::ServerConfig {
+            hash_max_listpack_entries: 4,
+            hash_max_listpack_value: 8,
+            ..Default::default()
+        });
+        let encoding = |key: &str| ObjectCommand::Encoding(key.to_string()).execute(&backend);
+
+        for i in 0..4 {
+            backend.hset("big".to_string(), format!("f{}", i), RespFrame::Integer(i));
+        }
+        assert_eq!(encoding("big"), BulkString::new("listpack").into());
+        backend.hset("big".to_string(), "f4".to_string(), RespFrame::Integer(4));
+        assert_eq!(encoding("big"), BulkString::new("hashtable").into());
+
+        backend.hset(
+            "long".to_string(),
+            "f".to_string(),
+            BulkString::new("short").into(),
+        );
+        assert_eq!(encoding("long"), BulkString::new("listpack").into());
+        backend.hset(
+            "long".to_string(),
+            "f".to_string(),
+            BulkString::new("much too long").into(),
+        );
+        assert_eq!(encoding("long"), BulkString::new("hashtable").into());
+    }
+
+    #[test]
+    fn test_object_encoding_for_sets() {
+        let backend = Backend::with_config(crate::ServerConfig {
+            set_max_intset_entries: 4,
+            set_max_listpack_entries: 3,
+            set_max_listpack_value: 8,
+            ..Default::default()
+        });
+        let encoding = |key: &str| ObjectCommand::Encoding(key.to_string()).execute(&backend);
+        let sadd = |key: &str, members: &[&str]| {
+            SAdd {
+                key: key.to_string(),
+                members: members.iter().map(|m| m.as_bytes().to_vec()).collect(),
+            }
+            .execute(&backend)
+        };
+
+        sadd("ints", &["1", "2", "-3", "4"]);
+        assert_eq!(encoding("ints"), BulkString::new("intset").into());
+        sadd("ints", &["5"]);
+        assert_eq!(encoding("ints"), BulkString::new("hashtable").into());
+
+        // "01" 不是规范的整数写法
+        sadd("small", &["1", "01"]);
+        assert_eq!(encoding("small"), BulkString::new("listpack").into());
+        SRem {
+            key: "small".to_string(),
+            members: vec![b"01".to_vec()],
+        }
+        .execute(&backend);
+        assert_eq!(encoding("small"), BulkString::new("listpack").into());
+        sadd("small", &["a", "b", "c"]);
+        assert_eq!(encoding("small"), BulkString::new("hashtable").into());
+
+        sadd("long", &["a"]);
+        assert_eq!(encoding("long"), BulkString::new("listpack").into());
+        sadd("long", &["much too long"]);
+        assert_eq!(encoding("long"), BulkString::new("hashtable").into());
+
+        backend.del("long");
+        sadd("long", &["7"]);
+        assert_eq!(encoding("long"), BulkString::new("intset").into());
+    }
+
+    #[test]
+    fn test_object_encoding_for_lists() {
+        let backend = Backend::with_config(crate::ServerConfig {
+            list_max_listpack_size: 4,
+            ..Default::default()
+        });
+        let encoding = || ObjectCommand::Encoding("list".to_string()).execute(&backend);
+        let values = |n: i64| (0..n).map(RespFrame::Integer).collect::<Vec<_>>();
+
+        RPush {
+            key: "list".to_string(),
+            values: values(4),
+        }
+        .execute(&backend);
+        assert_eq!(encoding(), BulkString::new("listpack").into());
+        LPush {
+            key: "list".to_string(),
+            values: values(1),
+        }
+        .execute(&backend);
+        assert_eq!(encoding(), BulkString::new("quicklist").into());
+        assert_eq!(
+            DebugCommand::Object("list".to_string()).execute(&backend),
+            SimpleString::new("refcount:1 encoding:quicklist").into()
+        );
+
+        // 缩到上限的一半才转回 listpack
+        let pop = |count: usize| {
+            LPop {
+                key: "list".to_string(),
+                count: Some(count),
+            }
+            .execute(&backend)
+        };
+        pop(2);
+        assert_eq!(encoding(), BulkString::new("quicklist").into());
+        pop(1);
+        assert_eq!(encoding(), BulkString::new("listpack").into());
+    }
+}
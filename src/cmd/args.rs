@@ -0,0 +1,158 @@
+use std::vec::IntoIter;
+
+use crate::{RespArray, RespFrame};
+
+use super::{extract_args, CommandError};
+
+/// Walks a command's arguments in order, so `TryFrom<RespArray>` impls don't have to
+/// match on `RespFrame::BulkString` by hand
+pub(super) struct ArgReader {
+    name: &'static str,
+    args: IntoIter<RespFrame>,
+    // 1-based position of the next argument, the command name itself is 0
+    pos: usize,
+}
+
+impl ArgReader {
+    /// Skip the command name and read the arguments after it
+    pub fn new(value: RespArray, name: &'static str) -> Result<Self, CommandError> {
+        Ok(Self {
+            name,
+            args: extract_args(value, 1)?.into_iter(),
+            pos: 1,
+        })
+    }
+
+    pub fn next_frame(&mut self) -> Result<RespFrame, CommandError> {
+        let frame = self.args.next().ok_or_else(|| self.arity_error())?;
+        self.pos += 1;
+        Ok(frame)
+    }
+
+    pub fn next_bytes(&mut self) -> Result<Vec<u8>, CommandError> {
+        let pos = self.pos;
+        match self.next_frame()? {
+            RespFrame::BulkString(s) => Ok(s.0.unwrap_or_default()),
+            _ => Err(CommandError::InvalidArgument(format!(
+                "argument {} of '{}' must be a bulk string",
+                pos, self.name
+            ))),
+        }
+    }
+
+    pub fn next_string(&mut self) -> Result<String, CommandError> {
+        Ok(String::from_utf8(self.next_bytes()?)?)
+    }
+
+    pub fn next_i64(&mut self) -> Result<i64, CommandError> {
+        let pos = self.pos;
+        String::from_utf8_lossy(&self.next_bytes()?)
+            .parse()
+            .map_err(|_| {
+                CommandError::InvalidArgument(format!(
+                    "argument {} of '{}': value is not an integer or out of range",
+                    pos, self.name
+                ))
+            })
+    }
+
+    /// Every remaining frame as is, e.g. the values of `RPUSH key value [value ...]`
+    pub fn rest_frames(&mut self) -> Vec<RespFrame> {
+        self.pos += self.args.len();
+        self.args.by_ref().collect()
+    }
+
+    /// Whether every argument has been read
+    pub fn is_empty(&self) -> bool {
+        self.args.len() == 0
+    }
+
+    /// Everything left, e.g. the keys of `DEL key [key ...]`
+    pub fn rest_strings(&mut self) -> Result<Vec<String>, CommandError> {
+        let mut rest = Vec::with_capacity(self.args.len());
+        while self.args.len() > 0 {
+            rest.push(self.next_string()?);
+        }
+        Ok(rest)
+    }
+
+    /// Fail if there are arguments nobody read
+    pub fn finish(self) -> Result<(), CommandError> {
+        match self.args.len() {
+            0 => Ok(()),
+            _ => Err(self.arity_error()),
+        }
+    }
+
+    fn arity_error(&self) -> CommandError {
+        CommandError::InvalidArgument(format!(
+            "wrong number of arguments for '{}' command",
+            self.name
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::BulkString;
+
+    use super::*;
+
+    fn reader(args: Vec<RespFrame>) -> ArgReader {
+        let mut frames: Vec<RespFrame> = vec![BulkString::new("cmd").into()];
+        frames.extend(args);
+        ArgReader::new(RespArray::new(frames), "cmd").unwrap()
+    }
+
+    #[test]
+    fn test_arg_reader_reads_in_order() -> anyhow::Result<()> {
+        let mut args = reader(vec![
+            BulkString::new("key").into(),
+            BulkString::new("-42").into(),
+            RespFrame::Integer(7),
+            BulkString::new("a").into(),
+            BulkString::new("b").into(),
+        ]);
+        assert_eq!(args.next_string()?, "key");
+        assert_eq!(args.next_i64()?, -42);
+        assert_eq!(args.next_frame()?, RespFrame::Integer(7));
+        assert_eq!(args.rest_strings()?, ["a", "b"]);
+        args.finish()?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_arg_reader_type_mismatch() {
+        let mut args = reader(vec![BulkString::new("key").into(), RespFrame::Integer(1)]);
+        args.next_string().unwrap();
+        let err = args.next_bytes().unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Invalid argument: argument 2 of 'cmd' must be a bulk string"
+        );
+
+        let mut args = reader(vec![BulkString::new("abc").into()]);
+        assert!(args
+            .next_i64()
+            .unwrap_err()
+            .to_string()
+            .contains("argument 1"));
+    }
+
+    #[test]
+    fn test_arg_reader_arity() {
+        let mut args = reader(vec![]);
+        let err = args.next_string().unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Invalid argument: wrong number of arguments for 'cmd' command"
+        );
+
+        let mut args = reader(vec![
+            BulkString::new("a").into(),
+            BulkString::new("b").into(),
+        ]);
+        args.next_string().unwrap();
+        assert!(args.finish().is_err());
+    }
+}
@@ -0,0 +1,409 @@
+use dashmap::mapref::entry::Entry;
+
+use crate::{BulkString, KeyKind, RespArray, RespFrame, RespNull};
+
+use super::{
+    check_key_limit, check_kind, extract_args, BitField, BitFieldOp, BitFieldOverflow,
+    BitFieldType, CommandError, CommandExecutor,
+};
+
+impl CommandExecutor for BitField {
+    fn execute(self, backend: &crate::Backend) -> RespFrame {
+        if let Err(e) = check_kind(backend, &self.key, KeyKind::String) {
+            return e.into();
+        }
+
+        let writes = self
+            .ops
+            .iter()
+            .any(|op| matches!(op, BitFieldOp::Set { .. } | BitFieldOp::IncrBy { .. }));
+        if !writes {
+            let mut bytes = match backend.get(&self.key) {
+                Some(RespFrame::BulkString(s)) => s.0.unwrap_or_default(),
+                Some(_) => return CommandError::WrongType.into(),
+                None => vec![],
+            };
+            return apply_ops(&mut bytes, self.ops).0;
+        }
+        if let Err(e) = check_key_limit(backend, &[&self.key]) {
+            return e.into();
+        }
+
+        // hold the entry for the whole read-modify-write so concurrent writers can't interleave
+        match backend.map.entry(self.key) {
+            Entry::Occupied(mut entry) => {
+                let mut bytes = match entry.get() {
+                    RespFrame::BulkString(s) => s.0.clone().unwrap_or_default(),
+                    _ => return CommandError::WrongType.into(),
+                };
+                let (ret, modified) = apply_ops(&mut bytes, self.ops);
+                if modified {
+                    backend.raw_strings.insert(entry.key().clone());
+                    entry.insert(BulkString::new(bytes).into());
+                }
+                ret
+            }
+            Entry::Vacant(entry) => {
+                let mut bytes = vec![];
+                let (ret, modified) = apply_ops(&mut bytes, self.ops);
+                if modified {
+                    backend.index_key(entry.key(), KeyKind::String);
+                    backend.raw_strings.insert(entry.key().clone());
+                    entry.insert(BulkString::new(bytes).into());
+                }
+                ret
+            }
+        }
+    }
+}
+
+// 依次执行每个子操作, 返回结果数组以及字符串是否被修改
+fn apply_ops(bytes: &mut Vec<u8>, ops: Vec<BitFieldOp>) -> (RespFrame, bool) {
+    let mut overflow = BitFieldOverflow::Wrap;
+    let mut modified = false;
+    let mut ret = Vec::with_capacity(ops.len());
+    for op in ops {
+        match op {
+            BitFieldOp::Overflow(o) => overflow = o,
+            BitFieldOp::Get { ty, offset } => {
+                ret.push(RespFrame::Integer(get_field(bytes, ty, offset)));
+            }
+            BitFieldOp::Set { ty, offset, value } => {
+                let old = get_field(bytes, ty, offset);
+                match ty.fit(value as i128, overflow) {
+                    Some(value) => {
+                        set_field(bytes, ty, offset, value);
+                        modified = true;
+                        ret.push(RespFrame::Integer(old));
+                    }
+                    None => ret.push(RespFrame::Null(RespNull)),
+                }
+            }
+            BitFieldOp::IncrBy {
+                ty,
+                offset,
+                increment,
+            } => {
+                let old = get_field(bytes, ty, offset);
+                match ty.fit(old as i128 + increment as i128, overflow) {
+                    Some(value) => {
+                        set_field(bytes, ty, offset, value);
+                        modified = true;
+                        ret.push(RespFrame::Integer(value));
+                    }
+                    None => ret.push(RespFrame::Null(RespNull)),
+                }
+            }
+        }
+    }
+    (RespArray::new(ret).into(), modified)
+}
+
+// 512MB 的字符串一共有这么多 bit
+const MAX_BIT_OFFSET: u64 = 512 * 1024 * 1024 * 8;
+
+// bit 0 is the most significant bit of the first byte, bits past the end read as zero
+fn get_field(bytes: &[u8], ty: BitFieldType, offset: u64) -> i64 {
+    let mut raw = 0u64;
+    for i in 0..ty.bits as u64 {
+        let pos = offset + i;
+        let bit = bytes
+            .get((pos / 8) as usize)
+            .map_or(0, |b| (b >> (7 - pos % 8)) & 1);
+        raw = (raw << 1) | bit as u64;
+    }
+    if ty.signed {
+        let shift = 64 - ty.bits;
+        ((raw << shift) as i64) >> shift
+    } else {
+        raw as i64
+    }
+}
+
+fn set_field(bytes: &mut Vec<u8>, ty: BitFieldType, offset: u64, value: i64) {
+    let needed = (offset + ty.bits as u64).div_ceil(8) as usize;
+    if bytes.len() < needed {
+        bytes.resize(needed, 0);
+    }
+    for i in 0..ty.bits as u64 {
+        let pos = offset + i;
+        let mask = 1u8 << (7 - pos % 8);
+        if (value as u64 >> (ty.bits as u64 - 1 - i)) & 1 == 1 {
+            bytes[(pos / 8) as usize] |= mask;
+        } else {
+            bytes[(pos / 8) as usize] &= !mask;
+        }
+    }
+}
+
+impl BitFieldType {
+    fn range(&self) -> (i128, i128) {
+        if self.signed {
+            (-(1i128 << (self.bits - 1)), (1i128 << (self.bits - 1)) - 1)
+        } else {
+            (0, (1i128 << self.bits) - 1)
+        }
+    }
+
+    // None means the value doesn't fit and the overflow policy is FAIL
+    fn fit(&self, value: i128, overflow: BitFieldOverflow) -> Option<i64> {
+        let (min, max) = self.range();
+        if (min..=max).contains(&value) {
+            return Some(value as i64);
+        }
+        match overflow {
+            BitFieldOverflow::Wrap => {
+                Some(((value - min).rem_euclid(1i128 << self.bits) + min) as i64)
+            }
+            BitFieldOverflow::Sat => Some(value.clamp(min, max) as i64),
+            BitFieldOverflow::Fail => None,
+        }
+    }
+}
+
+impl TryFrom<RespArray> for BitField {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        let mut args = vec![];
+        for arg in extract_args(value, 1)? {
+            match arg {
+                RespFrame::BulkString(s) => args.push(String::from_utf8(s.0.unwrap_or_default())?),
+                _ => {
+                    return Err(CommandError::InvalidArgument(
+                        "BITFIELD arguments must be bulk strings".to_string(),
+                    ))
+                }
+            }
+        }
+        let mut args = args.into_iter();
+        let key = args
+            .next()
+            .ok_or_else(|| CommandError::InvalidArgument("Invalid key".to_string()))?;
+
+        let mut ops = vec![];
+        while let Some(op) = args.next() {
+            let mut next = || {
+                args.next().ok_or_else(|| {
+                    CommandError::InvalidArgument(format!("BITFIELD {} is missing arguments", op))
+                })
+            };
+            let op = match op.to_ascii_lowercase().as_str() {
+                "get" => {
+                    let ty = parse_type(&next()?)?;
+                    let offset = parse_offset(&next()?, ty)?;
+                    BitFieldOp::Get { ty, offset }
+                }
+                "set" => {
+                    let ty = parse_type(&next()?)?;
+                    let offset = parse_offset(&next()?, ty)?;
+                    let value = parse_i64(&next()?)?;
+                    BitFieldOp::Set { ty, offset, value }
+                }
+                "incrby" => {
+                    let ty = parse_type(&next()?)?;
+                    let offset = parse_offset(&next()?, ty)?;
+                    let increment = parse_i64(&next()?)?;
+                    BitFieldOp::IncrBy {
+                        ty,
+                        offset,
+                        increment,
+                    }
+                }
+                "overflow" => match next()?.to_ascii_lowercase().as_str() {
+                    "wrap" => BitFieldOp::Overflow(BitFieldOverflow::Wrap),
+                    "sat" => BitFieldOp::Overflow(BitFieldOverflow::Sat),
+                    "fail" => BitFieldOp::Overflow(BitFieldOverflow::Fail),
+                    v => {
+                        return Err(CommandError::InvalidArgument(format!(
+                            "Invalid OVERFLOW type: {}",
+                            v
+                        )))
+                    }
+                },
+                v => {
+                    return Err(CommandError::InvalidArgument(format!(
+                        "Unknown BITFIELD subcommand: {}",
+                        v
+                    )))
+                }
+            };
+            ops.push(op);
+        }
+        Ok(BitField { key, ops })
+    }
+}
+
+// "i<bits>" for 1..=64 signed bits or "u<bits>" for 1..=63 unsigned bits
+fn parse_type(s: &str) -> Result<BitFieldType, CommandError> {
+    let err = || {
+        CommandError::InvalidArgument(
+            "Invalid bitfield type. Use something like i16 u8. Note that u64 is not supported but i64 is."
+                .to_string(),
+        )
+    };
+    let (signed, bits) = match s.as_bytes().first() {
+        Some(b'i') | Some(b'I') => (true, &s[1..]),
+        Some(b'u') | Some(b'U') => (false, &s[1..]),
+        _ => return Err(err()),
+    };
+    let bits: u32 = bits.parse().map_err(|_| err())?;
+    let max = if signed { 64 } else { 63 };
+    if bits == 0 || bits > max {
+        return Err(err());
+    }
+    Ok(BitFieldType { signed, bits })
+}
+
+// "#n" addresses the n-th field of the given width, a plain number is a bit offset. Like redis
+// the field must fit in a 512MB string, so later offset arithmetic can't overflow
+fn parse_offset(s: &str, ty: BitFieldType) -> Result<u64, CommandError> {
+    let offset = match s.strip_prefix('#') {
+        Some(n) => n
+            .parse::<u64>()
+            .ok()
+            .and_then(|n| n.checked_mul(ty.bits as u64)),
+        None => s.parse().ok(),
+    };
+    offset
+        .filter(|offset| {
+            offset
+                .checked_add(ty.bits as u64)
+                .is_some_and(|end| end <= MAX_BIT_OFFSET)
+        })
+        .ok_or_else(|| {
+            CommandError::InvalidArgument(
+                "bit offset is not an integer or out of range".to_string(),
+            )
+        })
+}
+
+fn parse_i64(s: &str) -> Result<i64, CommandError> {
+    s.parse()
+        .map_err(|_| CommandError::InvalidArgument(format!("Invalid integer: {}", s)))
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::BytesMut;
+
+    use crate::{Backend, RespDecode};
+
+    use super::*;
+
+    fn bitfield(backend: &Backend, args: &str) -> anyhow::Result<RespFrame> {
+        let args: Vec<&str> = args.split(' ').collect();
+        let mut buf = BytesMut::from(format!("*{}\r\n$8\r\nbitfield\r\n", args.len() + 1).as_str());
+        for arg in args {
+            buf.extend_from_slice(format!("${}\r\n{}\r\n", arg.len(), arg).as_bytes());
+        }
+        let frame = RespArray::decode(&mut buf)?;
+        let cmd: BitField = frame.try_into()?;
+        Ok(cmd.execute(backend))
+    }
+
+    #[test]
+    fn test_bitfield_try_from_resp_array() -> anyhow::Result<()> {
+        let mut buf = BytesMut::from(
+            "*8\r\n$8\r\nbitfield\r\n$3\r\nkey\r\n$3\r\nGET\r\n$2\r\nu8\r\n$2\r\n#1\r\n$8\r\nOVERFLOW\r\n$3\r\nSAT\r\n$6\r\nINCRBY\r\n",
+        );
+        let frame = RespArray::decode(&mut buf)?;
+        let ret: Result<BitField, _> = frame.try_into();
+        assert!(ret.is_err());
+
+        let mut buf = BytesMut::from(
+            "*7\r\n$8\r\nbitfield\r\n$3\r\nkey\r\n$3\r\nSET\r\n$3\r\ni16\r\n$2\r\n#1\r\n$2\r\n-5\r\n$8\r\nOVERFLOW\r\n",
+        );
+        let frame = RespArray::decode(&mut buf)?;
+        let ret: Result<BitField, _> = frame.try_into();
+        assert!(ret.is_err());
+
+        let mut buf = BytesMut::from(
+            "*6\r\n$8\r\nbitfield\r\n$3\r\nkey\r\n$3\r\nSET\r\n$3\r\ni16\r\n$2\r\n#1\r\n$2\r\n-5\r\n",
+        );
+        let frame = RespArray::decode(&mut buf)?;
+        let cmd: BitField = frame.try_into()?;
+        assert_eq!(cmd.key, "key");
+        assert_eq!(
+            cmd.ops,
+            vec![BitFieldOp::Set {
+                ty: BitFieldType {
+                    signed: true,
+                    bits: 16
+                },
+                offset: 16,
+                value: -5,
+            }]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_bitfield_set_get() -> anyhow::Result<()> {
+        let backend = Backend::new();
+        let ret = bitfield(&backend, "key SET u8 0 200 GET u8 0")?;
+        assert_eq!(ret, RespArray::new(vec![0.into(), 200.into()]).into());
+        assert_eq!(backend.get("key"), Some(BulkString::new(vec![200]).into()));
+
+        let ret = bitfield(&backend, "key SET i8 #1 -2 GET i8 #1 GET u4 8")?;
+        assert_eq!(
+            ret,
+            RespArray::new(vec![0.into(), (-2).into(), 15.into()]).into()
+        );
+        assert_eq!(
+            backend.get("key"),
+            Some(BulkString::new(vec![200, 0xfe]).into())
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_bitfield_incrby_overflow() -> anyhow::Result<()> {
+        let backend = Backend::new();
+        bitfield(&backend, "key SET u8 0 250")?;
+
+        let ret = bitfield(&backend, "key OVERFLOW SAT INCRBY u8 0 10")?;
+        assert_eq!(ret, RespArray::new(vec![255.into()]).into());
+
+        let ret = bitfield(&backend, "key INCRBY u8 0 10")?;
+        assert_eq!(ret, RespArray::new(vec![9.into()]).into());
+
+        let ret = bitfield(&backend, "key OVERFLOW FAIL INCRBY u8 0 300 GET u8 0")?;
+        assert_eq!(
+            ret,
+            RespArray::new(vec![RespFrame::Null(RespNull), 9.into()]).into()
+        );
+
+        let ret = bitfield(&backend, "key OVERFLOW SAT INCRBY i8 8 -200")?;
+        assert_eq!(ret, RespArray::new(vec![(-128).into()]).into());
+        Ok(())
+    }
+
+    #[test]
+    fn test_bitfield_get_missing_key_does_not_create_it() -> anyhow::Result<()> {
+        let backend = Backend::new();
+        let ret = bitfield(&backend, "key GET u8 100")?;
+        assert_eq!(ret, RespArray::new(vec![0.into()]).into());
+        assert!(backend.get("key").is_none());
+        Ok(())
+    }
+
+    #[test]
+    fn test_bitfield_offset_out_of_range() -> anyhow::Result<()> {
+        let backend = Backend::new();
+        for args in [
+            "key SET u8 18446744073709551615 1",
+            "key GET i64 18446744073709551615",
+            "key SET u8 #2305843009213693951 1",
+            // 最后一个 bit 超出 512MB
+            "key SET u8 4294967289 1",
+        ] {
+            assert!(bitfield(&backend, args).is_err(), "{}", args);
+        }
+        assert!(backend.get("key").is_none());
+
+        // 刚好放得下
+        let ret = bitfield(&backend, "key GET u8 4294967288")?;
+        assert_eq!(ret, RespArray::new(vec![0.into()]).into());
+        Ok(())
+    }
+}
@@ -0,0 +1,222 @@
+use crate::{BulkString, RespArray, RespFrame, RespMap, RespVersion, SimpleError, SimpleString};
+
+use super::{
+    extract_args, subcommand, ClientCommand, ClientFilter, CommandError, CommandExecutor, Hello,
+    Ping, Session, RESP_OK,
+};
+
+impl CommandExecutor for Hello {
+    fn execute(self, backend: &crate::Backend) -> RespFrame {
+        self.execute_in(backend, &mut Session::default())
+    }
+
+    fn execute_in(self, _backend: &crate::Backend, session: &mut Session) -> RespFrame {
+        match self.protover {
+            None => {}
+            Some(2) => session.version = RespVersion::Resp2,
+            Some(3) => session.version = RespVersion::Resp3,
+            Some(_) => {
+                return SimpleError::new("NOPROTO sorry, this protocol version is not supported.")
+                    .into()
+            }
+        }
+        let proto = match session.version {
+            RespVersion::Resp2 => 2,
+            RespVersion::Resp3 => 3,
+        };
+
+        let mut map = RespMap::new();
+        map.insert("server".to_string(), BulkString::new("redis").into());
+        map.insert(
+            "version".to_string(),
+            BulkString::new(env!("CARGO_PKG_VERSION")).into(),
+        );
+        map.insert("proto".to_string(), RespFrame::Integer(proto));
+        map.insert("id".to_string(), RespFrame::Integer(session.id as i64));
+        map.insert("mode".to_string(), BulkString::new("standalone").into());
+        map.insert("role".to_string(), BulkString::new("master").into());
+        map.insert("modules".to_string(), RespArray::new([]).into());
+        map.into()
+    }
+}
+
+impl TryFrom<RespArray> for Hello {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        let mut args = extract_args(value, 1)?.into_iter();
+        match (args.next(), args.next()) {
+            (None, _) => Ok(Hello { protover: None }),
+            (Some(RespFrame::BulkString(protover)), None) => {
+                let protover = String::from_utf8(protover.0.unwrap_or_default())?;
+                match protover.parse() {
+                    Ok(protover) => Ok(Hello {
+                        protover: Some(protover),
+                    }),
+                    Err(_) => Err(CommandError::InvalidArgument(
+                        "Protocol version is not an integer or out of range".to_string(),
+                    )),
+                }
+            }
+            _ => Err(CommandError::InvalidArgument(
+                "HELLO only supports the protocol version argument".to_string(),
+            )),
+        }
+    }
+}
+
+impl CommandExecutor for Ping {
+    fn execute(self, _backend: &crate::Backend) -> RespFrame {
+        match self.message {
+            Some(message) => BulkString::new(message).into(),
+            None => SimpleString::new("PONG").into(),
+        }
+    }
+}
+
+impl TryFrom<RespArray> for Ping {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        let args = extract_args(value, 1)?;
+        match args.as_slice() {
+            [] => Ok(Ping { message: None }),
+            [message] => Ok(Ping {
+                message: Some(string_arg(message)?),
+            }),
+            _ => Err(CommandError::InvalidArgument(
+                "wrong number of arguments for 'ping' command".to_string(),
+            )),
+        }
+    }
+}
+
+impl CommandExecutor for ClientCommand {
+    fn execute(self, backend: &crate::Backend) -> RespFrame {
+        self.execute_in(backend, &mut Session::default())
+    }
+
+    fn execute_in(self, backend: &crate::Backend, session: &mut Session) -> RespFrame {
+        match self {
+            ClientCommand::Id => RespFrame::Integer(session.id as i64),
+            ClientCommand::List => {
+                let list: String = backend
+                    .client_list()
+                    .iter()
+                    .map(|c| c.line() + "\n")
+                    .collect();
+                BulkString::new(list).into()
+            }
+            ClientCommand::Kill { filter, legacy } => {
+                let killed = backend.kill_clients(|info| match &filter {
+                    ClientFilter::Id(id) => info.id == *id,
+                    ClientFilter::Addr(addr) => info.addr == *addr,
+                });
+                match (legacy, killed) {
+                    (true, 0) => SimpleError::new("ERR No such client").into(),
+                    (true, _) => RESP_OK.clone(),
+                    (false, killed) => RespFrame::Integer(killed as i64),
+                }
+            }
+        }
+    }
+}
+
+impl TryFrom<RespArray> for ClientCommand {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        let args = extract_args(value, 1)?;
+        match (subcommand(&args, 0).as_deref(), args.len()) {
+            (Some("id"), 1) => Ok(ClientCommand::Id),
+            (Some("list"), 1) => Ok(ClientCommand::List),
+            (Some("kill"), 2) => Ok(ClientCommand::Kill {
+                filter: ClientFilter::Addr(string_arg(&args[1])?),
+                legacy: true,
+            }),
+            (Some("kill"), 3) => {
+                let value = string_arg(&args[2])?;
+                let filter = match subcommand(&args, 1).as_deref() {
+                    Some("id") => ClientFilter::Id(value.parse().map_err(|_| {
+                        CommandError::InvalidArgument("client-id should be greater than 0".into())
+                    })?),
+                    Some("addr") => ClientFilter::Addr(value),
+                    _ => {
+                        return Err(CommandError::InvalidArgument(
+                            "CLIENT KILL supports the ID and ADDR filters".to_string(),
+                        ))
+                    }
+                };
+                Ok(ClientCommand::Kill {
+                    filter,
+                    legacy: false,
+                })
+            }
+            (Some(sub), _) => Err(CommandError::InvalidCommand(format!(
+                "Unknown CLIENT subcommand or wrong arguments: {}",
+                sub
+            ))),
+            (None, _) => Err(CommandError::InvalidArgument(
+                "CLIENT requires a subcommand".to_string(),
+            )),
+        }
+    }
+}
+
+fn string_arg(arg: &RespFrame) -> Result<String, CommandError> {
+    match arg {
+        RespFrame::BulkString(s) => Ok(String::from_utf8(s.0.clone().unwrap_or_default())?),
+        _ => Err(CommandError::InvalidArgument(
+            "Invalid argument".to_string(),
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::BytesMut;
+
+    use crate::{Backend, RespDecode};
+
+    use super::*;
+
+    #[test]
+    fn test_ping() -> anyhow::Result<()> {
+        let backend = Backend::new();
+        let mut buf = BytesMut::from("*1\r\n$4\r\nping\r\n");
+        let ping: Ping = RespArray::decode(&mut buf)?.try_into()?;
+        assert_eq!(ping.execute(&backend), SimpleString::new("PONG").into());
+
+        let mut buf = BytesMut::from("*2\r\n$4\r\nping\r\n$5\r\nhello\r\n");
+        let ping: Ping = RespArray::decode(&mut buf)?.try_into()?;
+        assert_eq!(ping.execute(&backend), BulkString::new("hello").into());
+
+        let mut buf = BytesMut::from("*3\r\n$4\r\nping\r\n$1\r\na\r\n$1\r\nb\r\n");
+        let ret: Result<Ping, _> = RespArray::decode(&mut buf)?.try_into();
+        assert!(matches!(ret, Err(CommandError::InvalidArgument(_))));
+        Ok(())
+    }
+
+    #[test]
+    fn test_hello_negotiates_version() -> anyhow::Result<()> {
+        let mut buf = BytesMut::from("*2\r\n$5\r\nhello\r\n$1\r\n3\r\n");
+        let frame = RespArray::decode(&mut buf)?;
+        let hello: Hello = frame.try_into()?;
+        assert_eq!(hello.protover, Some(3));
+
+        let backend = Backend::new();
+        let mut session = Session::default();
+        assert_eq!(session.version, RespVersion::Resp2);
+
+        let RespFrame::Map(reply) = hello.execute_in(&backend, &mut session) else {
+            panic!("HELLO should reply with a map");
+        };
+        assert_eq!(session.version, RespVersion::Resp3);
+        assert_eq!(reply.get("proto"), Some(&RespFrame::Integer(3)));
+
+        let ret = Hello { protover: Some(4) }.execute_in(&backend, &mut session);
+        assert_eq!(
+            ret,
+            SimpleError::new("NOPROTO sorry, this protocol version is not supported.").into()
+        );
+        assert_eq!(session.version, RespVersion::Resp3);
+        Ok(())
+    }
+}
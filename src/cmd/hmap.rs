@@ -1,11 +1,18 @@
-use crate::{BulkString, RespArray, RespFrame};
+use dashmap::mapref::entry::Entry;
+
+use crate::{Backend, BulkString, KeyKind, RespArray, RespFrame, SimpleError};
 
 use super::{
-    extract_args, validate_command, CommandError, CommandExecutor, HGet, HGetAll, HSet, RESP_OK,
+    args::ArgReader, as_string_bytes, check_key_limit, check_kind, extract_args, validate_command,
+    CommandError, CommandExecutor, HDel, HExists, HGet, HGetAll, HIncrBy, HKeys, HLen, HMSet, HSet,
+    HVals, RESP_OK,
 };
 
 impl CommandExecutor for HGet {
     fn execute(self, backend: &crate::Backend) -> RespFrame {
+        if let Err(e) = check_kind(backend, &self.key, KeyKind::Hash) {
+            return e.into();
+        }
         match backend.hget(&self.key, &self.field) {
             Some(value) => value,
             None => RespFrame::Null(crate::RespNull),
@@ -13,31 +20,166 @@ impl CommandExecutor for HGet {
     }
 }
 
+// HKEYS/HVALS/HGETALL 共用, 保证三者的字段顺序一致
+fn hash_fields(backend: &Backend, key: &str) -> Vec<(String, RespFrame)> {
+    let mut fields: Vec<(String, RespFrame)> = match backend.hmap.get(key) {
+        Some(hmap) => hmap
+            .iter()
+            .map(|v| (v.key().to_owned(), v.value().clone()))
+            .collect(),
+        None => vec![],
+    };
+    if backend.config.sorted_hash_fields {
+        fields.sort_unstable_by(|a, b| a.0.cmp(&b.0));
+    }
+    fields
+}
+
 impl CommandExecutor for HGetAll {
     fn execute(self, backend: &crate::Backend) -> RespFrame {
-        let hmap = backend.hmap.get(&self.key);
-        match hmap {
-            Some(hmap) => {
-                let mut ret = Vec::with_capacity(hmap.len() * 2);
-                for v in hmap.iter() {
-                    let key = v.key().to_owned();
-                    ret.push(BulkString::new(key).into());
-                    ret.push(v.value().clone());
-                }
-                RespArray::new(ret).into()
-            }
-            None => RespArray::new([]).into(),
+        if let Err(e) = check_kind(backend, &self.key, KeyKind::Hash) {
+            return e.into();
+        }
+        let fields = hash_fields(backend, &self.key);
+        let mut ret = Vec::with_capacity(fields.len() * 2);
+        for (field, value) in fields {
+            ret.push(BulkString::new(field).into());
+            ret.push(value);
+        }
+        RespArray::new(ret).into()
+    }
+}
+
+impl CommandExecutor for HLen {
+    fn execute(self, backend: &crate::Backend) -> RespFrame {
+        if let Err(e) = check_kind(backend, &self.key, KeyKind::Hash) {
+            return e.into();
+        }
+        match backend.hmap.get(&self.key) {
+            Some(hmap) => RespFrame::Integer(hmap.len() as i64),
+            None => RespFrame::Integer(0),
         }
     }
 }
 
 impl CommandExecutor for HSet {
     fn execute(self, backend: &crate::Backend) -> RespFrame {
+        if let Err(e) = check_kind(backend, &self.key, KeyKind::Hash) {
+            return e.into();
+        }
+        if let Err(e) = check_key_limit(backend, &[&self.key]) {
+            return e.into();
+        }
         backend.hset(self.key, self.field, self.value);
         RESP_OK.clone()
     }
 }
 
+impl CommandExecutor for HExists {
+    fn execute(self, backend: &crate::Backend) -> RespFrame {
+        if let Err(e) = check_kind(backend, &self.key, KeyKind::Hash) {
+            return e.into();
+        }
+        let exists = backend
+            .hmap
+            .get(&self.key)
+            .is_some_and(|hmap| hmap.contains_key(&self.field));
+        RespFrame::Integer(exists as i64)
+    }
+}
+
+impl CommandExecutor for HKeys {
+    fn execute(self, backend: &crate::Backend) -> RespFrame {
+        if let Err(e) = check_kind(backend, &self.key, KeyKind::Hash) {
+            return e.into();
+        }
+        let keys: Vec<RespFrame> = hash_fields(backend, &self.key)
+            .into_iter()
+            .map(|(field, _)| BulkString::new(field).into())
+            .collect();
+        RespArray::new(keys).into()
+    }
+}
+
+impl CommandExecutor for HVals {
+    fn execute(self, backend: &crate::Backend) -> RespFrame {
+        if let Err(e) = check_kind(backend, &self.key, KeyKind::Hash) {
+            return e.into();
+        }
+        let values: Vec<RespFrame> = hash_fields(backend, &self.key)
+            .into_iter()
+            .map(|(_, value)| value)
+            .collect();
+        RespArray::new(values).into()
+    }
+}
+
+impl CommandExecutor for HIncrBy {
+    fn execute(self, backend: &crate::Backend) -> RespFrame {
+        if let Err(e) = check_kind(backend, &self.key, KeyKind::Hash) {
+            return e.into();
+        }
+        if let Err(e) = check_key_limit(backend, &[&self.key]) {
+            return e.into();
+        }
+        // 外层 entry 锁住整个 hash, 读取和写回之间不会被其他连接修改
+        let hmap = backend.hmap.entry(self.key.clone()).or_default();
+        backend.index_key(&self.key, KeyKind::Hash);
+        let value = match hmap.entry(self.field.clone()) {
+            Entry::Occupied(mut entry) => {
+                let current = as_string_bytes(entry.get())
+                    .ok()
+                    .and_then(|bytes| String::from_utf8_lossy(&bytes).parse::<i64>().ok());
+                let Some(current) = current else {
+                    return SimpleError::new("ERR hash value is not an integer").into();
+                };
+                let Some(value) = current.checked_add(self.delta) else {
+                    return SimpleError::new("ERR increment or decrement would overflow").into();
+                };
+                entry.insert(BulkString::new(value.to_string()).into());
+                value
+            }
+            Entry::Vacant(entry) => {
+                entry.insert(BulkString::new(self.delta.to_string()).into());
+                self.delta
+            }
+        };
+        // 字段的 entry 已经释放, 这里才能对整个 hash 计数
+        let frame = BulkString::new(value.to_string()).into();
+        backend.track_hash_write(&self.key, hmap.len(), &self.field, &frame);
+        RespFrame::Integer(value)
+    }
+}
+
+impl CommandExecutor for HDel {
+    fn execute(self, backend: &crate::Backend) -> RespFrame {
+        if let Err(e) = check_kind(backend, &self.key, KeyKind::Hash) {
+            return e.into();
+        }
+        let removed = self
+            .fields
+            .iter()
+            .filter(|field| backend.hdel(&self.key, field))
+            .count();
+        RespFrame::Integer(removed as i64)
+    }
+}
+
+impl CommandExecutor for HMSet {
+    fn execute(self, backend: &crate::Backend) -> RespFrame {
+        if let Err(e) = check_kind(backend, &self.key, KeyKind::Hash) {
+            return e.into();
+        }
+        if let Err(e) = check_key_limit(backend, &[&self.key]) {
+            return e.into();
+        }
+        for (field, value) in self.pairs {
+            backend.hset(self.key.clone(), field, value);
+        }
+        RESP_OK.clone()
+    }
+}
+
 impl TryFrom<RespArray> for HGet {
     type Error = CommandError;
 
@@ -47,8 +189,8 @@ impl TryFrom<RespArray> for HGet {
         let mut args = extract_args(value, 1)?.into_iter();
         match (args.next(), args.next()) {
             (Some(RespFrame::BulkString(key)), Some(RespFrame::BulkString(field))) => Ok(HGet {
-                key: String::from_utf8(key.0.unwrap())?,
-                field: String::from_utf8(field.0.unwrap())?,
+                key: String::from_utf8(key.0.unwrap_or_default())?,
+                field: String::from_utf8(field.0.unwrap_or_default())?,
             }),
             _ => Err(CommandError::InvalidArgument(
                 "Expected key and field arguments".to_string(),
@@ -65,7 +207,24 @@ impl TryFrom<RespArray> for HGetAll {
         let mut args = extract_args(value, 1)?.into_iter();
         match args.next() {
             Some(RespFrame::BulkString(key)) => Ok(HGetAll {
-                key: String::from_utf8(key.0.unwrap())?,
+                key: String::from_utf8(key.0.unwrap_or_default())?,
+            }),
+            _ => Err(CommandError::InvalidArgument(
+                "Expected key argument".to_string(),
+            )),
+        }
+    }
+}
+
+impl TryFrom<RespArray> for HLen {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["hlen"], 1)?;
+
+        let mut args = extract_args(value, 1)?.into_iter();
+        match args.next() {
+            Some(RespFrame::BulkString(key)) => Ok(HLen {
+                key: String::from_utf8(key.0.unwrap_or_default())?,
             }),
             _ => Err(CommandError::InvalidArgument(
                 "Expected key argument".to_string(),
@@ -74,6 +233,131 @@ impl TryFrom<RespArray> for HGetAll {
     }
 }
 
+impl TryFrom<RespArray> for HMSet {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        let mut args = extract_args(value, 1)?.into_iter();
+        let key = match args.next() {
+            Some(RespFrame::BulkString(key)) => String::from_utf8(key.0.unwrap_or_default())?,
+            _ => {
+                return Err(CommandError::InvalidArgument(
+                    "Expected key argument".to_string(),
+                ))
+            }
+        };
+        if args.len() == 0 || args.len() % 2 != 0 {
+            return Err(CommandError::InvalidArgument(
+                "wrong number of arguments for 'hmset' command".to_string(),
+            ));
+        }
+        let mut pairs = Vec::with_capacity(args.len() / 2);
+        while let (Some(field), Some(value)) = (args.next(), args.next()) {
+            match field {
+                RespFrame::BulkString(field) => {
+                    pairs.push((String::from_utf8(field.0.unwrap_or_default())?, value))
+                }
+                _ => return Err(CommandError::InvalidArgument("Invalid field".to_string())),
+            }
+        }
+        Ok(HMSet { key, pairs })
+    }
+}
+
+impl TryFrom<RespArray> for HExists {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        let mut args = ArgReader::new(value, "hexists")?;
+        let (key, field) = (args.next_string()?, args.next_string()?);
+        args.finish()?;
+        Ok(HExists { key, field })
+    }
+}
+
+impl TryFrom<RespArray> for HKeys {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["hkeys"], 1)?;
+        Ok(HKeys {
+            key: extract_key(value)?,
+        })
+    }
+}
+
+impl TryFrom<RespArray> for HVals {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["hvals"], 1)?;
+        Ok(HVals {
+            key: extract_key(value)?,
+        })
+    }
+}
+
+impl TryFrom<RespArray> for HIncrBy {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["hincrby"], 3)?;
+
+        let mut args = extract_args(value, 1)?.into_iter();
+        match (args.next(), args.next(), args.next()) {
+            (
+                Some(RespFrame::BulkString(key)),
+                Some(RespFrame::BulkString(field)),
+                Some(RespFrame::BulkString(delta)),
+            ) => Ok(HIncrBy {
+                key: String::from_utf8(key.0.unwrap_or_default())?,
+                field: String::from_utf8(field.0.unwrap_or_default())?,
+                delta: String::from_utf8_lossy(delta.as_ref())
+                    .parse()
+                    .map_err(|_| {
+                        CommandError::InvalidArgument(
+                            "value is not an integer or out of range".to_string(),
+                        )
+                    })?,
+            }),
+            _ => Err(CommandError::InvalidArgument(
+                "Expected key, field and increment arguments".to_string(),
+            )),
+        }
+    }
+}
+
+fn extract_key(value: RespArray) -> Result<String, CommandError> {
+    match extract_args(value, 1)?.into_iter().next() {
+        Some(RespFrame::BulkString(key)) => Ok(String::from_utf8(key.0.unwrap_or_default())?),
+        _ => Err(CommandError::InvalidArgument(
+            "Expected key argument".to_string(),
+        )),
+    }
+}
+
+impl TryFrom<RespArray> for HDel {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        let mut args = extract_args(value, 1)?.into_iter();
+        let key = match args.next() {
+            Some(RespFrame::BulkString(key)) => String::from_utf8(key.0.unwrap_or_default())?,
+            _ => {
+                return Err(CommandError::InvalidArgument(
+                    "Expected key argument".to_string(),
+                ))
+            }
+        };
+        let fields = args
+            .map(|field| match field {
+                RespFrame::BulkString(field) => Ok(String::from_utf8(field.0.unwrap_or_default())?),
+                _ => Err(CommandError::InvalidArgument("Invalid field".to_string())),
+            })
+            .collect::<Result<Vec<_>, CommandError>>()?;
+        if fields.is_empty() {
+            return Err(CommandError::InvalidArgument(
+                "hdel command must have at least 1 field".to_string(),
+            ));
+        }
+        Ok(HDel { key, fields })
+    }
+}
+
 impl TryFrom<RespArray> for HSet {
     type Error = CommandError;
     fn try_from(value: RespArray) -> Result<Self, Self::Error> {
@@ -83,8 +367,8 @@ impl TryFrom<RespArray> for HSet {
         match (args.next(), args.next(), args.next()) {
             (Some(RespFrame::BulkString(key)), Some(RespFrame::BulkString(field)), Some(value)) => {
                 Ok(HSet {
-                    key: String::from_utf8(key.0.unwrap())?,
-                    field: String::from_utf8(field.0.unwrap())?,
+                    key: String::from_utf8(key.0.unwrap_or_default())?,
+                    field: String::from_utf8(field.0.unwrap_or_default())?,
                     value,
                 })
             }
@@ -99,7 +383,7 @@ impl TryFrom<RespArray> for HSet {
 mod tests {
     use bytes::BytesMut;
 
-    use crate::RespDecode;
+    use crate::{Backend, RespDecode};
 
     use super::*;
 
@@ -114,6 +398,35 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_hash_commands_on_string_key() {
+        let backend = Backend::new();
+        backend.set("key".to_string(), BulkString::new("value").into());
+        let key = || "key".to_string();
+        let wrongtype: RespFrame = CommandError::WrongType.into();
+
+        let hget = HGet {
+            key: key(),
+            field: "field".to_string(),
+        };
+        assert_eq!(hget.execute(&backend), wrongtype);
+        assert_eq!(HGetAll { key: key() }.execute(&backend), wrongtype);
+        let hmget = crate::cmd::HMGet {
+            key: key(),
+            fields: vec!["field".to_string()],
+        };
+        assert_eq!(hmget.execute(&backend), wrongtype);
+        let hset = HSet {
+            key: key(),
+            field: "field".to_string(),
+            value: b"1".into(),
+        };
+        assert_eq!(hset.execute(&backend), wrongtype);
+        // 原来的字符串不受影响
+        assert_eq!(backend.get("key"), Some(BulkString::new("value").into()));
+        assert!(!backend.hmap.contains_key("key"));
+    }
+
     #[test]
     fn test_hgetall() -> anyhow::Result<()> {
         let mut buf = BytesMut::from("*2\r\n$7\r\nhgetall\r\n$3\r\nkey\r\n");
@@ -136,4 +449,228 @@ mod tests {
         assert_eq!(hset.value, RespFrame::BulkString(b"value".into()));
         Ok(())
     }
+
+    #[test]
+    fn test_hlen() -> anyhow::Result<()> {
+        let mut buf = BytesMut::from("*2\r\n$4\r\nhlen\r\n$3\r\nkey\r\n");
+        let frame = RespArray::decode(&mut buf)?;
+        let hlen: HLen = frame.try_into()?;
+        assert_eq!(hlen.key, "key");
+
+        let backend = Backend::new();
+        backend.hset("hash".to_string(), "a".to_string(), b"1".into());
+        backend.hset("hash".to_string(), "b".to_string(), b"2".into());
+        backend.set("string".to_string(), b"value".into());
+
+        let len = |key: &str| {
+            HLen {
+                key: key.to_string(),
+            }
+            .execute(&backend)
+        };
+        assert_eq!(len("hash"), RespFrame::Integer(2));
+        assert_eq!(len("missing"), RespFrame::Integer(0));
+        assert_eq!(len("string"), CommandError::WrongType.into());
+        Ok(())
+    }
+
+    #[test]
+    fn test_hdel() -> anyhow::Result<()> {
+        let mut buf =
+            BytesMut::from("*4\r\n$4\r\nhdel\r\n$4\r\nhash\r\n$1\r\na\r\n$7\r\nmissing\r\n");
+        let frame = RespArray::decode(&mut buf)?;
+        let hdel: HDel = frame.try_into()?;
+        assert_eq!(hdel.fields, ["a", "missing"]);
+
+        let backend = Backend::new();
+        backend.hset("hash".to_string(), "a".to_string(), b"1".into());
+        backend.hset("hash".to_string(), "b".to_string(), b"2".into());
+        assert_eq!(hdel.execute(&backend), RespFrame::Integer(1));
+        assert_eq!(backend.hget("hash", "a"), None);
+        assert_eq!(backend.hget("hash", "b"), Some(b"2".into()));
+
+        let hdel = HDel {
+            key: "hash".to_string(),
+            fields: vec!["b".to_string(), "b".to_string()],
+        };
+        assert_eq!(hdel.execute(&backend), RespFrame::Integer(1));
+        // 最后一个 field 删掉后整个 hash 都不存在了
+        assert!(!backend.hmap.contains_key("hash"));
+        assert!(!backend.exists("hash"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_hexists_hkeys_hvals() -> anyhow::Result<()> {
+        let mut buf = BytesMut::from("*3\r\n$7\r\nhexists\r\n$4\r\nhash\r\n$1\r\na\r\n");
+        let hexists: HExists = RespArray::decode(&mut buf)?.try_into()?;
+        let mut buf = BytesMut::from("*2\r\n$5\r\nhkeys\r\n$4\r\nhash\r\n");
+        let hkeys: HKeys = RespArray::decode(&mut buf)?.try_into()?;
+        let mut buf = BytesMut::from("*2\r\n$5\r\nhvals\r\n$4\r\nhash\r\n");
+        let hvals: HVals = RespArray::decode(&mut buf)?.try_into()?;
+
+        let backend = Backend::new();
+        // 缺失的 hash 等同于空 hash
+        let missing = || RespArray::new([]).into();
+        assert_eq!(
+            HExists {
+                key: "hash".to_string(),
+                field: "a".to_string()
+            }
+            .execute(&backend),
+            RespFrame::Integer(0)
+        );
+        assert_eq!(
+            HKeys {
+                key: "hash".to_string()
+            }
+            .execute(&backend),
+            missing()
+        );
+        assert_eq!(
+            HVals {
+                key: "hash".to_string()
+            }
+            .execute(&backend),
+            missing()
+        );
+
+        backend.hset("hash".to_string(), "a".to_string(), b"1".into());
+        backend.hset("hash".to_string(), "b".to_string(), b"2".into());
+        assert_eq!(hexists.execute(&backend), RespFrame::Integer(1));
+
+        // field 的顺序不固定, 排序后再比较
+        let sorted = |frame: RespFrame| {
+            let RespFrame::Array(RespArray(Some(mut frames))) = frame else {
+                panic!("expected an array");
+            };
+            frames.sort_by_key(|f| format!("{:?}", f));
+            frames
+        };
+        assert_eq!(
+            sorted(hkeys.execute(&backend)),
+            [BulkString::new("a").into(), BulkString::new("b").into()]
+        );
+        assert_eq!(
+            sorted(hvals.execute(&backend)),
+            [RespFrame::from(b"1"), RespFrame::from(b"2")]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_sorted_hash_fields() -> anyhow::Result<()> {
+        let backend = Backend::with_config(crate::ServerConfig {
+            sorted_hash_fields: true,
+            ..Default::default()
+        });
+        for field in ["c", "a", "d", "b", "e"] {
+            backend.hset(
+                "hash".to_string(),
+                field.to_string(),
+                BulkString::new(field.to_uppercase()).into(),
+            );
+        }
+        let key = || "hash".to_string();
+        let frames = |frame: RespFrame| match frame {
+            RespFrame::Array(RespArray(Some(frames))) => frames,
+            _ => panic!("expected an array"),
+        };
+        let keys = frames(HKeys { key: key() }.execute(&backend));
+        let vals = frames(HVals { key: key() }.execute(&backend));
+        let all = frames(HGetAll { key: key() }.execute(&backend));
+
+        let expected: Vec<RespFrame> = ["a", "b", "c", "d", "e"]
+            .into_iter()
+            .map(|field| BulkString::new(field).into())
+            .collect();
+        assert_eq!(keys, expected);
+        // HKEYS/HVALS 和 HGETALL 的每一对都对得上
+        for (i, pair) in all.chunks(2).enumerate() {
+            assert_eq!(pair[0], keys[i]);
+            assert_eq!(pair[1], vals[i]);
+        }
+        assert_eq!(all.len(), keys.len() * 2);
+        Ok(())
+    }
+
+    #[test]
+    fn test_hincrby() -> anyhow::Result<()> {
+        let mut buf = BytesMut::from("*4\r\n$7\r\nhincrby\r\n$4\r\nhash\r\n$1\r\nn\r\n$1\r\n5\r\n");
+        let hincrby: HIncrBy = RespArray::decode(&mut buf)?.try_into()?;
+
+        let backend = Backend::new();
+        assert_eq!(hincrby.execute(&backend), RespFrame::Integer(5));
+        assert_eq!(backend.key_kind("hash"), Some(KeyKind::Hash));
+
+        let incr = |field: &str, delta: i64| {
+            HIncrBy {
+                key: "hash".to_string(),
+                field: field.to_string(),
+                delta,
+            }
+            .execute(&backend)
+        };
+        assert_eq!(incr("n", -7), RespFrame::Integer(-2));
+        assert_eq!(
+            backend.hget("hash", "n"),
+            Some(BulkString::new("-2").into())
+        );
+
+        backend.hset("hash".to_string(), "s".to_string(), b"abc".into());
+        assert_eq!(
+            incr("s", 1),
+            SimpleError::new("ERR hash value is not an integer").into()
+        );
+        assert_eq!(backend.hget("hash", "s"), Some(b"abc".into()));
+        Ok(())
+    }
+
+    #[test]
+    fn test_hincrby_converts_to_hashtable() {
+        let backend = Backend::with_config(crate::ServerConfig {
+            hash_max_listpack_entries: 2,
+            ..Default::default()
+        });
+        for field in ["a", "b"] {
+            HIncrBy {
+                key: "hash".to_string(),
+                field: field.to_string(),
+                delta: 1,
+            }
+            .execute(&backend);
+        }
+        assert!(!backend.hashtable_hashes.contains("hash"));
+        HIncrBy {
+            key: "hash".to_string(),
+            field: "c".to_string(),
+            delta: 1,
+        }
+        .execute(&backend);
+        assert!(backend.hashtable_hashes.contains("hash"));
+    }
+
+    #[test]
+    fn test_hmset() -> anyhow::Result<()> {
+        let mut buf = BytesMut::from(
+            "*8\r\n$5\r\nhmset\r\n$4\r\nhash\r\n$1\r\na\r\n$1\r\n1\r\n$1\r\nb\r\n$1\r\n2\r\n$1\r\nc\r\n$1\r\n3\r\n",
+        );
+        let hmset: HMSet = RespArray::decode(&mut buf)?.try_into()?;
+
+        let backend = Backend::new();
+        assert_eq!(hmset.execute(&backend), RESP_OK.clone());
+        for (field, value) in [("a", b"1"), ("b", b"2"), ("c", b"3")] {
+            let hget = HGet {
+                key: "hash".to_string(),
+                field: field.to_string(),
+            };
+            assert_eq!(hget.execute(&backend), value.into());
+        }
+
+        let mut buf =
+            BytesMut::from("*5\r\n$5\r\nhmset\r\n$4\r\nhash\r\n$1\r\na\r\n$1\r\n1\r\n$1\r\nb\r\n");
+        let ret: Result<HMSet, _> = RespArray::decode(&mut buf)?.try_into();
+        assert!(matches!(ret, Err(CommandError::InvalidArgument(_))));
+        Ok(())
+    }
 }
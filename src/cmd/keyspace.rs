@@ -0,0 +1,1011 @@
+use std::{
+    collections::hash_map::DefaultHasher,
+    hash::{Hash, Hasher},
+};
+
+use crate::{Backend, BulkString, KeyKind, RespArray, RespFrame, SimpleError, SimpleString};
+
+use super::{
+    args::ArgReader, checked_ttl, extract_args, glob::glob_match, subcommand, validate_command,
+    CommandError, CommandExecutor, DbSize, Del, Exists, Expire, FlushAll, FlushDb, Keys, Rename,
+    Scan, Ttl, Type, RESP_OK,
+};
+
+// redis 默认的 COUNT
+const SCAN_BATCH: usize = 10;
+
+impl CommandExecutor for FlushAll {
+    fn execute(self, backend: &crate::Backend) -> RespFrame {
+        // there is only one database, so flushing all of them is flushing it
+        backend.flush();
+        RESP_OK.clone()
+    }
+}
+
+impl CommandExecutor for FlushDb {
+    fn execute(self, backend: &crate::Backend) -> RespFrame {
+        backend.flush();
+        RESP_OK.clone()
+    }
+}
+
+impl CommandExecutor for DbSize {
+    fn execute(self, backend: &crate::Backend) -> RespFrame {
+        RespFrame::Integer(backend.dbsize() as i64)
+    }
+}
+
+impl CommandExecutor for Del {
+    fn execute(self, backend: &crate::Backend) -> RespFrame {
+        let deleted = self.keys.iter().filter(|key| backend.del(key)).count();
+        RespFrame::Integer(deleted as i64)
+    }
+}
+
+impl CommandExecutor for Exists {
+    fn execute(self, backend: &crate::Backend) -> RespFrame {
+        // 重复的 key 会被重复计数, 和 redis 一致
+        let found = self.keys.iter().filter(|key| backend.exists(key)).count();
+        RespFrame::Integer(found as i64)
+    }
+}
+
+impl CommandExecutor for Expire {
+    fn execute(self, backend: &crate::Backend) -> RespFrame {
+        // a non-positive ttl deletes the key right away
+        let done = match self.secs {
+            secs if secs > 0 => match checked_ttl(secs, false, "expire") {
+                Ok(ttl) => backend.expire_in(&self.key, ttl),
+                Err(e) => return e.into(),
+            },
+            _ => backend.del(&self.key),
+        };
+        RespFrame::Integer(done as i64)
+    }
+}
+
+impl CommandExecutor for Rename {
+    fn execute(self, backend: &crate::Backend) -> RespFrame {
+        if self.expire.is_some() && !backend.config.enable_extensions {
+            return CommandError::InvalidArgument("syntax error".to_string()).into();
+        }
+        match backend.rename(&self.from, &self.to, self.expire) {
+            true => RESP_OK.clone(),
+            false => SimpleError::new("ERR no such key").into(),
+        }
+    }
+}
+
+impl CommandExecutor for Ttl {
+    fn execute(self, backend: &crate::Backend) -> RespFrame {
+        RespFrame::Integer(backend.ttl(&self.key))
+    }
+}
+
+impl CommandExecutor for Keys {
+    fn execute(self, backend: &crate::Backend) -> RespFrame {
+        // 写入时会替换掉其他类型的同名 key, 每个 key 只在一个存储里, 排序只是为了输出稳定
+        let mut keys: Vec<String> = candidate_keys(backend, self.kind)
+            .into_iter()
+            .filter(|key| glob_match(self.pattern.as_bytes(), key.as_bytes()))
+            .collect();
+        keys.sort_unstable();
+        let keys: Vec<RespFrame> = keys
+            .into_iter()
+            .map(|k| BulkString::new(k).into())
+            .collect();
+        RespArray::new(keys).into()
+    }
+}
+
+impl CommandExecutor for Type {
+    fn execute(self, backend: &crate::Backend) -> RespFrame {
+        SimpleString::new(backend.type_of(&self.key)).into()
+    }
+}
+
+// The cursor is the hash of the next key to return, so a scan resumes from any cursor without
+// server side state: keys removed since the last call are simply not there any more and the
+// cursor never has to point at an existing key. Each call only orders the `count` smallest
+// hashes past the cursor instead of sorting the whole keyspace.
+impl CommandExecutor for Scan {
+    fn execute(self, backend: &crate::Backend) -> RespFrame {
+        let mut keys: Vec<(u64, String)> = candidate_keys(backend, self.kind)
+            .into_iter()
+            .map(|key| (key_hash(&key), key))
+            .filter(|(hash, _)| *hash >= self.cursor)
+            .collect();
+        if keys.len() > self.count {
+            keys.select_nth_unstable(self.count);
+            keys.truncate(self.count + 1);
+        }
+        keys.sort_unstable();
+
+        // 下一批从第 count + 1 个 key 开始, 它被删掉了也没关系, 游标只是一个位置
+        let next = keys.get(self.count).map_or(0, |(hash, _)| *hash);
+        keys.truncate(self.count);
+        // 收集之后其他连接可能已经删掉了一部分 key, 跳过它们而不是返回已经不存在的 key
+        let batch: Vec<RespFrame> = keys
+            .into_iter()
+            .filter(|(_, key)| backend.exists(key))
+            .map(|(_, key)| BulkString::new(key).into())
+            .collect();
+        RespArray::new(vec![
+            BulkString::new(next.to_string()).into(),
+            RespArray::new(batch).into(),
+        ])
+        .into()
+    }
+}
+
+// with a TYPE filter only keys of that kind are read, straight from the type index when it is on
+fn candidate_keys(backend: &Backend, kind: Option<KeyKind>) -> Vec<String> {
+    match kind {
+        Some(kind) => backend
+            .keys_of_kind(kind)
+            .into_iter()
+            .filter(|key| backend.key_kind(key) == Some(kind))
+            .collect(),
+        None => backend.keys(),
+    }
+}
+
+// TYPE 选项接受 TYPE 命令返回的类型名
+fn parse_kind(name: &[u8]) -> Result<KeyKind, CommandError> {
+    match name.to_ascii_lowercase().as_slice() {
+        b"string" => Ok(KeyKind::String),
+        b"hash" => Ok(KeyKind::Hash),
+        b"list" => Ok(KeyKind::List),
+        b"set" => Ok(KeyKind::Set),
+        _ => Err(CommandError::InvalidArgument(
+            "unknown type name".to_string(),
+        )),
+    }
+}
+
+fn key_hash(key: &str) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    key.hash(&mut hasher);
+    hasher.finish()
+}
+
+impl TryFrom<RespArray> for FlushAll {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_flush_args(value, "flushall")?;
+        Ok(FlushAll)
+    }
+}
+
+impl TryFrom<RespArray> for FlushDb {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_flush_args(value, "flushdb")?;
+        Ok(FlushDb)
+    }
+}
+
+// FLUSHALL/FLUSHDB accept an optional ASYNC or SYNC modifier, both are executed synchronously
+fn validate_flush_args(value: RespArray, name: &str) -> Result<(), CommandError> {
+    let mut args = extract_args(value, 1)?.into_iter();
+    match (args.next(), args.next()) {
+        (None, _) => Ok(()),
+        (Some(RespFrame::BulkString(mode)), None)
+            if matches!(
+                mode.as_ref().to_ascii_lowercase().as_slice(),
+                b"async" | b"sync"
+            ) =>
+        {
+            Ok(())
+        }
+        _ => Err(CommandError::InvalidArgument(format!(
+            "{} only accepts an optional ASYNC or SYNC argument",
+            name
+        ))),
+    }
+}
+
+impl TryFrom<RespArray> for DbSize {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["dbsize"], 0)?;
+        Ok(DbSize)
+    }
+}
+
+impl TryFrom<RespArray> for Del {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        // UNLINK is parsed as DEL too, both free the key right away
+        let name = value
+            .as_ref()
+            .and_then(|args| subcommand(args, 0))
+            .unwrap_or_default();
+        let keys = extract_keys(value, &name)?;
+        Ok(Del { keys })
+    }
+}
+
+impl TryFrom<RespArray> for Exists {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        let keys = extract_keys(value, "exists")?;
+        Ok(Exists { keys })
+    }
+}
+
+impl TryFrom<RespArray> for Expire {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["expire"], 2)?;
+
+        let mut args = extract_args(value, 1)?.into_iter();
+        match (args.next(), args.next()) {
+            (Some(RespFrame::BulkString(key)), Some(RespFrame::BulkString(secs))) => {
+                let secs = String::from_utf8_lossy(secs.as_ref())
+                    .parse()
+                    .map_err(|_| {
+                        CommandError::InvalidArgument(
+                            "value is not an integer or out of range".to_string(),
+                        )
+                    })?;
+                if secs > 0 {
+                    checked_ttl(secs, false, "expire")?;
+                }
+                Ok(Expire {
+                    key: String::from_utf8(key.0.unwrap_or_default())?,
+                    secs,
+                })
+            }
+            _ => Err(CommandError::InvalidArgument(
+                "Invalid key or seconds".to_string(),
+            )),
+        }
+    }
+}
+
+impl TryFrom<RespArray> for Rename {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        let mut args = ArgReader::new(value, "rename")?;
+        let mut rename = Rename {
+            from: args.next_string()?,
+            to: args.next_string()?,
+            expire: None,
+        };
+        // EX 是扩展选项, 是否允许要到执行时看配置
+        if !args.is_empty() {
+            if !args.next_bytes()?.eq_ignore_ascii_case(b"ex") {
+                return Err(CommandError::InvalidArgument("syntax error".to_string()));
+            }
+            rename.expire = Some(checked_ttl(args.next_i64()?, false, "rename")?);
+        }
+        args.finish()?;
+        Ok(rename)
+    }
+}
+
+impl TryFrom<RespArray> for Ttl {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        let mut args = ArgReader::new(value, "ttl")?;
+        let key = args.next_string()?;
+        args.finish()?;
+        Ok(Ttl { key })
+    }
+}
+
+impl TryFrom<RespArray> for Keys {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        let mut args = ArgReader::new(value, "keys")?;
+        let pattern = args.next_string()?;
+        let kind = match args.is_empty() {
+            true => None,
+            false if args.next_bytes()?.eq_ignore_ascii_case(b"type") => {
+                Some(parse_kind(&args.next_bytes()?)?)
+            }
+            false => return Err(CommandError::InvalidArgument("syntax error".to_string())),
+        };
+        args.finish()?;
+        Ok(Keys { pattern, kind })
+    }
+}
+
+impl TryFrom<RespArray> for Type {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        let mut args = ArgReader::new(value, "type")?;
+        let key = args.next_string()?;
+        args.finish()?;
+        Ok(Type { key })
+    }
+}
+
+// commands taking `key [key ...]`
+fn extract_keys(value: RespArray, name: &str) -> Result<Vec<String>, CommandError> {
+    let args = extract_args(value, 1)?;
+    if args.is_empty() {
+        return Err(CommandError::InvalidArgument(format!(
+            "{} command must have at least 1 argument",
+            name
+        )));
+    }
+    args.into_iter()
+        .map(|arg| match arg {
+            RespFrame::BulkString(key) => Ok(String::from_utf8(key.0.unwrap_or_default())?),
+            _ => Err(CommandError::InvalidArgument("Invalid key".to_string())),
+        })
+        .collect()
+}
+
+impl TryFrom<RespArray> for Scan {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        let mut args = extract_args(value, 1)?.into_iter();
+        let cursor = match args.next() {
+            Some(RespFrame::BulkString(cursor)) => String::from_utf8_lossy(cursor.as_ref())
+                .parse()
+                .map_err(|_| CommandError::InvalidArgument("invalid cursor".to_string()))?,
+            _ => {
+                return Err(CommandError::InvalidArgument(
+                    "SCAN requires a cursor".to_string(),
+                ))
+            }
+        };
+        let mut scan = Scan {
+            cursor,
+            count: SCAN_BATCH,
+            kind: None,
+        };
+        let syntax_error = || CommandError::InvalidArgument("syntax error".to_string());
+        while let Some(arg) = args.next() {
+            let RespFrame::BulkString(option) = arg else {
+                return Err(syntax_error());
+            };
+            match option.as_ref().to_ascii_lowercase().as_slice() {
+                b"count" => {
+                    scan.count = match args.next() {
+                        Some(RespFrame::BulkString(count)) => {
+                            String::from_utf8_lossy(count.as_ref())
+                                .parse::<usize>()
+                                .ok()
+                                .filter(|count| *count > 0)
+                                .ok_or_else(syntax_error)?
+                        }
+                        _ => return Err(syntax_error()),
+                    }
+                }
+                b"type" => match args.next() {
+                    Some(RespFrame::BulkString(name)) => {
+                        scan.kind = Some(parse_kind(name.as_ref())?)
+                    }
+                    _ => return Err(syntax_error()),
+                },
+                _ => return Err(syntax_error()),
+            }
+        }
+        Ok(scan)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use bytes::BytesMut;
+
+    use crate::{
+        cmd::{Command, Get},
+        Backend, BulkString, RespDecode, RespNull,
+    };
+
+    use super::*;
+
+    fn run(backend: &Backend, args: &[&str]) -> RespFrame {
+        let args: Vec<RespFrame> = args
+            .iter()
+            .map(|arg| BulkString::new(*arg).into())
+            .collect();
+        Command::try_from(RespArray::new(args))
+            .unwrap()
+            .execute(backend)
+    }
+
+    #[test]
+    fn test_flushall_try_from_resp_array() -> anyhow::Result<()> {
+        let mut buf = BytesMut::from("*1\r\n$8\r\nflushall\r\n");
+        let frame = RespArray::decode(&mut buf)?;
+        let _: FlushAll = frame.try_into()?;
+
+        let mut buf = BytesMut::from("*2\r\n$8\r\nflushall\r\n$5\r\nASYNC\r\n");
+        let frame = RespArray::decode(&mut buf)?;
+        let _: FlushAll = frame.try_into()?;
+
+        let mut buf = BytesMut::from("*2\r\n$8\r\nflushall\r\n$4\r\nlazy\r\n");
+        let frame = RespArray::decode(&mut buf)?;
+        let ret: Result<FlushAll, _> = frame.try_into();
+        assert!(ret.is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_flushall_command() {
+        let backend = Backend::new();
+        backend.set("key".to_string(), BulkString::new("value").into());
+        backend.hset(
+            "hash".to_string(),
+            "field".to_string(),
+            BulkString::new("value").into(),
+        );
+
+        let ret = FlushAll.execute(&backend);
+        assert_eq!(ret, RESP_OK.clone());
+        assert!(backend.get("key").is_none());
+        assert!(backend.hget("hash", "field").is_none());
+        assert!(backend.map.is_empty());
+        assert!(backend.hmap.is_empty());
+    }
+
+    #[test]
+    fn test_flushdb_command() -> anyhow::Result<()> {
+        let mut buf = BytesMut::from("*2\r\n$7\r\nflushdb\r\n$4\r\nSYNC\r\n");
+        let frame = RespArray::decode(&mut buf)?;
+        let flushdb: FlushDb = frame.try_into()?;
+
+        let backend = Backend::new();
+        backend.set("a".to_string(), BulkString::new("1").into());
+        backend.set("b".to_string(), BulkString::new("2").into());
+        backend.hset(
+            "hash".to_string(),
+            "field".to_string(),
+            BulkString::new("value").into(),
+        );
+        assert_eq!(flushdb.execute(&backend), RESP_OK.clone());
+        for key in ["a", "b", "hash"] {
+            assert_eq!(
+                Get {
+                    key: key.to_string()
+                }
+                .execute(&backend),
+                RespFrame::Null(RespNull)
+            );
+        }
+        assert_eq!(backend.dbsize(), 0);
+        Ok(())
+    }
+
+    #[test]
+    fn test_dbsize_command() -> anyhow::Result<()> {
+        let mut buf = BytesMut::from("*1\r\n$6\r\ndbsize\r\n");
+        let frame = RespArray::decode(&mut buf)?;
+        let dbsize: DbSize = frame.try_into()?;
+
+        let backend = Backend::new();
+        backend.set("a".to_string(), BulkString::new("1").into());
+        backend.set("b".to_string(), BulkString::new("2").into());
+        backend.set("a".to_string(), BulkString::new("3").into());
+        for field in ["x", "y"] {
+            backend.hset("hash".to_string(), field.to_string(), b"1".into());
+        }
+        backend.hset("other".to_string(), "x".to_string(), b"1".into());
+        assert_eq!(dbsize.execute(&backend), RespFrame::Integer(4));
+
+        // SET 覆盖 hash 时 key 只留在字符串里, 不会被算两次
+        backend.set("other".to_string(), BulkString::new("4").into());
+        assert_eq!(DbSize.execute(&backend), RespFrame::Integer(4));
+        assert!(!backend.hmap.contains_key("other"));
+        assert_eq!(backend.type_of("other"), "string");
+        Ok(())
+    }
+
+    #[test]
+    fn test_rename_command() -> anyhow::Result<()> {
+        let mut buf = BytesMut::from("*3\r\n$6\r\nrename\r\n$3\r\nold\r\n$3\r\nnew\r\n");
+        let frame = RespArray::decode(&mut buf)?;
+        let rename: Rename = frame.try_into()?;
+
+        let backend = Backend::new();
+        backend.hset("old".to_string(), "field".to_string(), b"1".into());
+        backend.expire("old", 100);
+        backend.set("new".to_string(), BulkString::new("value").into());
+        assert_eq!(rename.execute(&backend), RESP_OK.clone());
+        assert!(!backend.exists("old"));
+        assert_eq!(backend.type_of("new"), "hash");
+        assert_eq!(backend.ttl("new"), 100);
+        assert_eq!(backend.dbsize(), 1);
+
+        let rename = Rename {
+            from: "missing".to_string(),
+            to: "new".to_string(),
+            expire: None,
+        };
+        assert_eq!(
+            rename.execute(&backend),
+            SimpleError::new("ERR no such key").into()
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_rename_with_expire() -> anyhow::Result<()> {
+        let rename = || -> anyhow::Result<Rename> {
+            let mut buf = BytesMut::from(
+                "*5\r\n$6\r\nrename\r\n$3\r\nold\r\n$3\r\nnew\r\n$2\r\nEX\r\n$2\r\n10\r\n",
+            );
+            Ok(RespArray::decode(&mut buf)?.try_into()?)
+        };
+
+        // 扩展选项默认关闭
+        let backend = Backend::new();
+        backend.set("old".to_string(), BulkString::new("value").into());
+        assert!(matches!(rename()?.execute(&backend), RespFrame::Error(_)));
+        assert!(backend.exists("old"));
+
+        let backend = Backend::with_config(crate::ServerConfig {
+            enable_extensions: true,
+            ..Default::default()
+        });
+        backend.set("old".to_string(), BulkString::new("value").into());
+        assert_eq!(rename()?.execute(&backend), RESP_OK.clone());
+        assert!(!backend.exists("old"));
+        assert_eq!(backend.get("new"), Some(BulkString::new("value").into()));
+        assert_eq!(backend.ttl("new"), 10);
+        assert!(!backend.expires.contains_key("old"));
+
+        // EX 替换掉 key 原来的 ttl, 改名成自己也生效
+        let rename = Rename {
+            from: "new".to_string(),
+            to: "new".to_string(),
+            expire: Some(std::time::Duration::from_secs(20)),
+        };
+        assert_eq!(rename.execute(&backend), RESP_OK.clone());
+        assert_eq!(backend.ttl("new"), 20);
+
+        let mut buf = BytesMut::from(
+            "*5\r\n$6\r\nrename\r\n$3\r\nnew\r\n$3\r\nold\r\n$2\r\nEX\r\n$19\r\n9223372036854775807\r\n",
+        );
+        let frame = RespArray::decode(&mut buf)?;
+        assert!(Rename::try_from(frame).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_del_command() -> anyhow::Result<()> {
+        let mut buf =
+            BytesMut::from("*4\r\n$3\r\ndel\r\n$3\r\nkey\r\n$4\r\nhash\r\n$7\r\nmissing\r\n");
+        let frame = RespArray::decode(&mut buf)?;
+        let del: Del = frame.try_into()?;
+        assert_eq!(del.keys, ["key", "hash", "missing"]);
+
+        let backend = Backend::new();
+        backend.set("key".to_string(), BulkString::new("value").into());
+        backend.hset(
+            "hash".to_string(),
+            "field".to_string(),
+            RespFrame::Integer(1),
+        );
+        assert_eq!(del.execute(&backend), RespFrame::Integer(2));
+        assert!(backend.keys().is_empty());
+
+        let del = Del {
+            keys: vec!["key".to_string()],
+        };
+        assert_eq!(del.execute(&backend), RespFrame::Integer(0));
+
+        let mut buf = BytesMut::from("*1\r\n$3\r\ndel\r\n");
+        let frame = RespArray::decode(&mut buf)?;
+        assert!(Del::try_from(frame).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_del_clears_ttl() -> anyhow::Result<()> {
+        let backend = Backend::new();
+        backend.set("key".to_string(), BulkString::new("value").into());
+        assert!(backend.expire("key", 100));
+
+        let mut buf = BytesMut::from("*2\r\n$6\r\nUNLINK\r\n$3\r\nkey\r\n");
+        let frame = RespArray::decode(&mut buf)?;
+        let Command::Del(unlink) = Command::try_from(frame)? else {
+            panic!("UNLINK should parse as DEL");
+        };
+        assert_eq!(unlink.execute(&backend), RespFrame::Integer(1));
+        assert_eq!(backend.ttl("key"), -2);
+        assert!(!backend.exists("key"));
+        assert!(!backend.expires.contains_key("key"));
+        assert!(!backend.raw_strings.contains("key"));
+
+        let mut buf = BytesMut::from("*1\r\n$6\r\nunlink\r\n");
+        let frame = RespArray::decode(&mut buf)?;
+        let err = Command::try_from(frame).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Invalid argument: unlink command must have at least 1 argument"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_exists_command() -> anyhow::Result<()> {
+        let mut buf = BytesMut::from(
+            "*5\r\n$6\r\nexists\r\n$1\r\na\r\n$1\r\na\r\n$4\r\nhash\r\n$7\r\nmissing\r\n",
+        );
+        let frame = RespArray::decode(&mut buf)?;
+        let exists: Exists = frame.try_into()?;
+
+        let backend = Backend::new();
+        assert_eq!(
+            Exists {
+                keys: vec!["missing".to_string()]
+            }
+            .execute(&backend),
+            RespFrame::Integer(0)
+        );
+
+        backend.set("a".to_string(), BulkString::new("1").into());
+        backend.hset(
+            "hash".to_string(),
+            "field".to_string(),
+            RespFrame::Integer(1),
+        );
+        assert_eq!(exists.execute(&backend), RespFrame::Integer(3));
+        Ok(())
+    }
+
+    #[test]
+    fn test_keys_pattern() -> anyhow::Result<()> {
+        let mut buf = BytesMut::from("*2\r\n$4\r\nkeys\r\n$1\r\n*\r\n");
+        let frame = RespArray::decode(&mut buf)?;
+        let all: Keys = frame.try_into()?;
+
+        let backend = Backend::new();
+        for key in ["user:1", "user:2", "user:10", "session"] {
+            run(&backend, &["set", key, "v"]);
+        }
+        run(&backend, &["hset", "user:3", "name", "v"]);
+        // SET over a hash replaces it, so the key is still listed once
+        run(&backend, &["hset", "user:1", "name", "v"]);
+        run(&backend, &["set", "user:1", "v"]);
+        assert_eq!(backend.type_of("user:1"), "string");
+
+        let keys = |pattern: &str| {
+            let RespFrame::Array(keys) = Keys {
+                pattern: pattern.to_string(),
+                kind: None,
+            }
+            .execute(&backend) else {
+                panic!("KEYS should reply with an array");
+            };
+            keys.0
+                .unwrap_or_default()
+                .into_iter()
+                .map(|k| match k {
+                    RespFrame::BulkString(k) => String::from_utf8(k.0.unwrap()).unwrap(),
+                    other => panic!("unexpected key {:?}", other),
+                })
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(all.pattern, "*");
+        assert_eq!(
+            keys("*"),
+            ["session", "user:1", "user:10", "user:2", "user:3"]
+        );
+        assert_eq!(keys("user:*"), ["user:1", "user:10", "user:2", "user:3"]);
+        assert_eq!(keys("user:[12]"), ["user:1", "user:2"]);
+        assert_eq!(keys("user:?"), ["user:1", "user:2", "user:3"]);
+        assert!(keys("nothing*").is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_type() -> anyhow::Result<()> {
+        let mut buf = BytesMut::from("*2\r\n$4\r\ntype\r\n$3\r\nkey\r\n");
+        let frame = RespArray::decode(&mut buf)?;
+        let cmd: Type = frame.try_into()?;
+
+        let backend = Backend::new();
+        let type_of = |key: &str| {
+            Type {
+                key: key.to_string(),
+            }
+            .execute(&backend)
+        };
+        assert_eq!(cmd.execute(&backend), SimpleString::new("none").into());
+
+        backend.set("key".to_string(), BulkString::new("value").into());
+        assert_eq!(type_of("key"), SimpleString::new("string").into());
+
+        backend.hset(
+            "hash".to_string(),
+            "field".to_string(),
+            BulkString::new("value").into(),
+        );
+        assert_eq!(type_of("hash"), SimpleString::new("hash").into());
+        Ok(())
+    }
+
+    #[test]
+    fn test_expire_non_positive_deletes() {
+        let backend = Backend::new();
+        backend.set("key".to_string(), BulkString::new("value").into());
+        let expire = |key: &str| {
+            Expire {
+                key: key.to_string(),
+                secs: -5,
+            }
+            .execute(&backend)
+        };
+        assert_eq!(expire("key"), RespFrame::Integer(1));
+        assert!(!backend.exists("key"));
+        assert!(backend.expires.is_empty());
+        assert_eq!(expire("missing"), RespFrame::Integer(0));
+    }
+
+    #[test]
+    fn test_expire_out_of_range() -> anyhow::Result<()> {
+        let mut buf =
+            BytesMut::from("*3\r\n$6\r\nexpire\r\n$3\r\nkey\r\n$19\r\n9223372036854775807\r\n");
+        let frame = RespArray::decode(&mut buf)?;
+        assert!(Expire::try_from(frame).is_err());
+
+        let backend = Backend::new();
+        backend.set("key".to_string(), BulkString::new("value").into());
+        let expire = Expire {
+            key: "key".to_string(),
+            secs: i64::MAX,
+        };
+        assert_eq!(
+            expire.execute(&backend),
+            SimpleError::new("ERR Invalid argument: invalid expire time in 'expire' command")
+                .into()
+        );
+        assert!(!backend.expire("key", i64::MAX as u64));
+        assert!(!backend.expire("key", u64::MAX));
+        assert_eq!(backend.ttl("key"), -1);
+        Ok(())
+    }
+
+    #[test]
+    fn test_expire_and_ttl() -> anyhow::Result<()> {
+        let mut buf = BytesMut::from("*3\r\n$6\r\nexpire\r\n$3\r\nkey\r\n$3\r\n100\r\n");
+        let frame = RespArray::decode(&mut buf)?;
+        let expire: Expire = frame.try_into()?;
+        let mut buf = BytesMut::from("*2\r\n$3\r\nttl\r\n$3\r\nkey\r\n");
+        let frame = RespArray::decode(&mut buf)?;
+        let ttl: Ttl = frame.try_into()?;
+
+        let backend = Backend::new();
+        let ttl_of = |key: &str| {
+            Ttl {
+                key: key.to_string(),
+            }
+            .execute(&backend)
+        };
+        assert_eq!(ttl_of("key"), RespFrame::Integer(-2));
+        backend.set("key".to_string(), BulkString::new("value").into());
+        assert_eq!(ttl_of("key"), RespFrame::Integer(-1));
+
+        assert_eq!(expire.execute(&backend), RespFrame::Integer(1));
+        assert_eq!(ttl.execute(&backend), RespFrame::Integer(100));
+
+        let expire = Expire {
+            key: "key".to_string(),
+            secs: -1,
+        };
+        assert_eq!(expire.execute(&backend), RespFrame::Integer(1));
+        assert_eq!(ttl_of("key"), RespFrame::Integer(-2));
+        Ok(())
+    }
+
+    // 返回 (下一个游标, 本批的 key)
+    fn scan(backend: &Backend, cursor: u64, count: usize) -> (u64, Vec<String>) {
+        let scan = Scan {
+            cursor,
+            count,
+            kind: None,
+        };
+        let RespFrame::Array(reply) = scan.execute(backend) else {
+            panic!("SCAN should reply with an array");
+        };
+        let mut reply = reply.0.unwrap().into_iter();
+        let (Some(RespFrame::BulkString(next)), Some(RespFrame::Array(keys))) =
+            (reply.next(), reply.next())
+        else {
+            panic!("SCAN reply should be [cursor, keys]");
+        };
+        let next = String::from_utf8_lossy(next.as_ref()).parse().unwrap();
+        let keys = keys
+            .0
+            .unwrap()
+            .into_iter()
+            .map(|key| match key {
+                RespFrame::BulkString(key) => String::from_utf8(key.0.unwrap()).unwrap(),
+                _ => panic!("SCAN keys should be bulk strings"),
+            })
+            .collect();
+        (next, keys)
+    }
+
+    #[test]
+    fn test_scan_try_from_resp_array() -> anyhow::Result<()> {
+        let mut buf = BytesMut::from("*2\r\n$4\r\nscan\r\n$2\r\n42\r\n");
+        let frame = RespArray::decode(&mut buf)?;
+        let scan: Scan = frame.try_into()?;
+        assert_eq!(scan.cursor, 42);
+        assert_eq!(scan.count, SCAN_BATCH);
+
+        let mut buf = BytesMut::from("*4\r\n$4\r\nscan\r\n$1\r\n0\r\n$5\r\nCOUNT\r\n$1\r\n5\r\n");
+        let frame = RespArray::decode(&mut buf)?;
+        let scan: Scan = frame.try_into()?;
+        assert_eq!(scan.count, 5);
+
+        let mut buf = BytesMut::from("*4\r\n$4\r\nscan\r\n$1\r\n0\r\n$5\r\ncount\r\n$1\r\n0\r\n");
+        let frame = RespArray::decode(&mut buf)?;
+        assert!(Scan::try_from(frame).is_err());
+
+        let mut buf = BytesMut::from("*2\r\n$4\r\nscan\r\n$2\r\nab\r\n");
+        let frame = RespArray::decode(&mut buf)?;
+        assert!(Scan::try_from(frame).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_scan_and_keys_type_filter() -> anyhow::Result<()> {
+        // 结果按名字排序, 方便比较
+        let run = |backend: &Backend, input: &str| -> anyhow::Result<Vec<String>> {
+            let frame = RespArray::decode(&mut BytesMut::from(input))?;
+            let reply = Command::try_from(frame)?.execute(backend);
+            let keys = match reply {
+                RespFrame::Array(RespArray(Some(mut reply))) if input.contains("scan") => {
+                    match reply.pop() {
+                        Some(RespFrame::Array(keys)) => keys,
+                        _ => panic!("SCAN reply should be [cursor, keys]"),
+                    }
+                }
+                RespFrame::Array(keys) => keys,
+                reply => panic!("unexpected reply {:?}", reply),
+            };
+            let mut keys: Vec<String> = keys
+                .0
+                .unwrap_or_default()
+                .into_iter()
+                .map(|key| match key {
+                    RespFrame::BulkString(key) => String::from_utf8_lossy(key.as_ref()).to_string(),
+                    key => panic!("unexpected key {:?}", key),
+                })
+                .collect();
+            keys.sort();
+            Ok(keys)
+        };
+
+        for type_index in [false, true] {
+            let backend = Backend::with_config(crate::ServerConfig {
+                type_index,
+                ..Default::default()
+            });
+            backend.set("s1".to_string(), BulkString::new("1").into());
+            backend.set("s2".to_string(), BulkString::new("2").into());
+            backend.hset("h1".to_string(), "f".to_string(), b"1".into());
+            backend.hset("h2".to_string(), "f".to_string(), b"1".into());
+            backend.hset("gone".to_string(), "f".to_string(), b"1".into());
+            backend.expire_in("gone", std::time::Duration::from_millis(1));
+            std::thread::sleep(std::time::Duration::from_millis(5));
+
+            let scan = "*6\r\n$4\r\nscan\r\n$1\r\n0\r\n$5\r\nCOUNT\r\n$3\r\n100\r\n$4\r\nTYPE\r\n$4\r\nhash\r\n";
+            assert_eq!(run(&backend, scan)?, ["h1", "h2"]);
+            let keys = "*4\r\n$4\r\nkeys\r\n$1\r\n*\r\n$4\r\ntype\r\n$6\r\nstring\r\n";
+            assert_eq!(run(&backend, keys)?, ["s1", "s2"]);
+            let keys = "*4\r\n$4\r\nkeys\r\n$2\r\n*2\r\n$4\r\ntype\r\n$4\r\nhash\r\n";
+            assert_eq!(run(&backend, keys)?, ["h2"]);
+        }
+
+        let mut buf = BytesMut::from("*4\r\n$4\r\nscan\r\n$1\r\n0\r\n$4\r\nTYPE\r\n$4\r\nzset\r\n");
+        let frame = RespArray::decode(&mut buf)?;
+        assert!(Scan::try_from(frame).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_scan_count_bounds_batches() {
+        let backend = Backend::new();
+        for i in 0..50 {
+            backend.set(format!("key{}", i), BulkString::new("value").into());
+        }
+
+        let mut seen = HashSet::new();
+        let (mut cursor, mut calls) = (0, 0);
+        loop {
+            let (next, keys) = scan(&backend, cursor, 5);
+            assert!(keys.len() <= 5);
+            seen.extend(keys);
+            calls += 1;
+            if next == 0 {
+                break;
+            }
+            cursor = next;
+        }
+        assert_eq!(seen.len(), 50);
+        assert!(calls >= 10);
+    }
+
+    #[test]
+    fn test_scan_with_keys_removed_mid_iteration() {
+        let backend = Backend::new();
+        for i in 0..25 {
+            backend.set(format!("key{}", i), BulkString::new("value").into());
+        }
+        backend.hset("hash".to_string(), "f".to_string(), RespFrame::Integer(1));
+
+        let (mut cursor, first) = scan(&backend, 0, SCAN_BATCH);
+        assert_eq!(first.len(), SCAN_BATCH);
+        assert_ne!(cursor, 0);
+        let mut seen: HashSet<String> = first.into_iter().collect();
+
+        // drop the key the cursor points at plus another one that hasn't been returned yet
+        let pending: Vec<String> = backend
+            .keys()
+            .into_iter()
+            .filter(|k| !seen.contains(k))
+            .collect();
+        let at_cursor = pending
+            .iter()
+            .find(|k| key_hash(k) == cursor)
+            .unwrap()
+            .clone();
+        let other = pending.iter().find(|k| **k != at_cursor).unwrap().clone();
+        for key in [&at_cursor, &other] {
+            backend.map.remove(key);
+            backend.hmap.remove(key);
+        }
+
+        loop {
+            let (next, keys) = scan(&backend, cursor, SCAN_BATCH);
+            seen.extend(keys);
+            if next == 0 {
+                break;
+            }
+            cursor = next;
+        }
+
+        let remaining: HashSet<String> = backend.keys().into_iter().collect();
+        assert!(remaining.is_subset(&seen));
+        assert!(!seen.contains(&at_cursor));
+        assert!(!seen.contains(&other));
+    }
+
+    #[test]
+    fn test_scan_during_concurrent_deletes() {
+        let backend = Backend::new();
+        for i in 0..2000 {
+            backend.set(format!("key{}", i), BulkString::new("value").into());
+            backend.hset(format!("hash{}", i), "f".to_string(), RespFrame::Integer(1));
+        }
+
+        let deleter = {
+            let backend = backend.clone();
+            std::thread::spawn(move || {
+                for i in 0..2000 {
+                    backend.del(&format!("key{}", i));
+                    backend.hdel(&format!("hash{}", i), "f");
+                }
+            })
+        };
+
+        let mut cursor = 0;
+        loop {
+            let (next, keys) = scan(&backend, cursor, 50);
+            assert!(keys.len() <= 50);
+            assert!(keys
+                .iter()
+                .all(|k| k.starts_with("key") || k.starts_with("hash")));
+            if next == 0 {
+                break;
+            }
+            cursor = next;
+        }
+        deleter.join().expect("deleter should not panic");
+
+        assert_eq!(scan(&backend, 0, 50), (0, vec![]));
+    }
+}
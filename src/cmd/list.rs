@@ -0,0 +1,450 @@
+use std::collections::VecDeque;
+
+use crate::{KeyKind, RespArray, RespFrame, RespNull, SimpleError};
+
+use super::{
+    args::ArgReader, check_key_limit, check_kind, CommandError, CommandExecutor, LIndex, LLen,
+    LPop, LPush, LRange, LSet, RPop, RPush, RESP_OK,
+};
+
+impl CommandExecutor for LPush {
+    fn execute(self, backend: &crate::Backend) -> RespFrame {
+        if let Err(e) = check_kind(backend, &self.key, KeyKind::List) {
+            return e.into();
+        }
+        if let Err(e) = check_key_limit(backend, &[&self.key]) {
+            return e.into();
+        }
+        let mut list = backend.list.entry(self.key.clone()).or_default();
+        backend.index_key(&self.key, KeyKind::List);
+        // 和 redis 一样逐个插入到头部, 所以 LPUSH a b c 之后是 c b a
+        for value in self.values {
+            list.push_front(value);
+        }
+        backend.track_list_len(&self.key, list.len());
+        RespFrame::Integer(list.len() as i64)
+    }
+}
+
+impl CommandExecutor for RPush {
+    fn execute(self, backend: &crate::Backend) -> RespFrame {
+        if let Err(e) = check_kind(backend, &self.key, KeyKind::List) {
+            return e.into();
+        }
+        if let Err(e) = check_key_limit(backend, &[&self.key]) {
+            return e.into();
+        }
+        let mut list = backend.list.entry(self.key.clone()).or_default();
+        backend.index_key(&self.key, KeyKind::List);
+        list.extend(self.values);
+        backend.track_list_len(&self.key, list.len());
+        RespFrame::Integer(list.len() as i64)
+    }
+}
+
+impl CommandExecutor for LRange {
+    fn execute(self, backend: &crate::Backend) -> RespFrame {
+        if let Err(e) = check_kind(backend, &self.key, KeyKind::List) {
+            return e.into();
+        }
+        let Some(list) = backend.list.get(&self.key) else {
+            return RespArray::new([]).into();
+        };
+        // 负数下标从末尾开始计算, 越界的部分截断
+        let len = list.len() as i64;
+        let index = |i: i64| if i < 0 { (len + i).max(0) } else { i };
+        let (start, stop) = (index(self.start), index(self.stop).min(len - 1));
+        if start > stop {
+            return RespArray::new([]).into();
+        }
+        let items: Vec<RespFrame> = list
+            .range(start as usize..=stop as usize)
+            .cloned()
+            .collect();
+        RespArray::new(items).into()
+    }
+}
+
+impl CommandExecutor for LLen {
+    fn execute(self, backend: &crate::Backend) -> RespFrame {
+        if let Err(e) = check_kind(backend, &self.key, KeyKind::List) {
+            return e.into();
+        }
+        let len = backend.list.get(&self.key).map_or(0, |list| list.len());
+        RespFrame::Integer(len as i64)
+    }
+}
+
+impl CommandExecutor for LPop {
+    fn execute(self, backend: &crate::Backend) -> RespFrame {
+        pop(backend, &self.key, self.count, VecDeque::pop_front)
+    }
+}
+
+impl CommandExecutor for RPop {
+    fn execute(self, backend: &crate::Backend) -> RespFrame {
+        pop(backend, &self.key, self.count, VecDeque::pop_back)
+    }
+}
+
+impl CommandExecutor for LIndex {
+    fn execute(self, backend: &crate::Backend) -> RespFrame {
+        if let Err(e) = check_kind(backend, &self.key, KeyKind::List) {
+            return e.into();
+        }
+        backend
+            .list
+            .get(&self.key)
+            .and_then(|list| position(list.len(), self.index).map(|i| list[i].clone()))
+            .unwrap_or(RespFrame::Null(RespNull))
+    }
+}
+
+impl CommandExecutor for LSet {
+    fn execute(self, backend: &crate::Backend) -> RespFrame {
+        if let Err(e) = check_kind(backend, &self.key, KeyKind::List) {
+            return e.into();
+        }
+        let Some(mut list) = backend.list.get_mut(&self.key) else {
+            return SimpleError::new("ERR no such key").into();
+        };
+        match position(list.len(), self.index) {
+            Some(i) => {
+                list[i] = self.value;
+                RESP_OK.clone()
+            }
+            None => SimpleError::new("ERR index out of range").into(),
+        }
+    }
+}
+
+// 负数下标从末尾开始计算, 越界返回 None
+fn position(len: usize, index: i64) -> Option<usize> {
+    let index = if index < 0 { len as i64 + index } else { index };
+    (0..len as i64).contains(&index).then_some(index as usize)
+}
+
+// 不带 count 时返回单个元素, 带 count 时返回数组; 弹空的 list 会被删除
+fn pop(
+    backend: &crate::Backend,
+    key: &str,
+    count: Option<usize>,
+    pop_one: fn(&mut VecDeque<RespFrame>) -> Option<RespFrame>,
+) -> RespFrame {
+    if let Err(e) = check_kind(backend, key, KeyKind::List) {
+        return e.into();
+    }
+    let popped = match backend.list.get_mut(key) {
+        Some(mut list) => {
+            let n = count.unwrap_or(1).min(list.len());
+            let popped = (0..n)
+                .filter_map(|_| pop_one(&mut list))
+                .collect::<Vec<_>>();
+            backend.track_list_len(key, list.len());
+            popped
+        }
+        None => return RespFrame::Null(RespNull),
+    };
+    if backend
+        .list
+        .remove_if(key, |_, list| list.is_empty())
+        .is_some()
+    {
+        backend.expires.remove(key);
+        backend.unindex_key(key, KeyKind::List);
+    }
+    match count {
+        Some(_) => RespArray::new(popped).into(),
+        None => popped
+            .into_iter()
+            .next()
+            .unwrap_or(RespFrame::Null(RespNull)),
+    }
+}
+
+impl TryFrom<RespArray> for LPush {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        let (key, values) = extract_push(value, "lpush")?;
+        Ok(LPush { key, values })
+    }
+}
+
+impl TryFrom<RespArray> for RPush {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        let (key, values) = extract_push(value, "rpush")?;
+        Ok(RPush { key, values })
+    }
+}
+
+impl TryFrom<RespArray> for LRange {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        let mut args = ArgReader::new(value, "lrange")?;
+        let (key, start, stop) = (args.next_string()?, args.next_i64()?, args.next_i64()?);
+        args.finish()?;
+        Ok(LRange { key, start, stop })
+    }
+}
+
+impl TryFrom<RespArray> for LLen {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        let mut args = ArgReader::new(value, "llen")?;
+        let key = args.next_string()?;
+        args.finish()?;
+        Ok(LLen { key })
+    }
+}
+
+impl TryFrom<RespArray> for LPop {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        let (key, count) = extract_pop(value, "lpop")?;
+        Ok(LPop { key, count })
+    }
+}
+
+impl TryFrom<RespArray> for RPop {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        let (key, count) = extract_pop(value, "rpop")?;
+        Ok(RPop { key, count })
+    }
+}
+
+impl TryFrom<RespArray> for LIndex {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        let mut args = ArgReader::new(value, "lindex")?;
+        let (key, index) = (args.next_string()?, args.next_i64()?);
+        args.finish()?;
+        Ok(LIndex { key, index })
+    }
+}
+
+impl TryFrom<RespArray> for LSet {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        let mut args = ArgReader::new(value, "lset")?;
+        let (key, index, value) = (args.next_string()?, args.next_i64()?, args.next_frame()?);
+        args.finish()?;
+        Ok(LSet { key, index, value })
+    }
+}
+
+// key [count]
+fn extract_pop(
+    value: RespArray,
+    name: &'static str,
+) -> Result<(String, Option<usize>), CommandError> {
+    let mut args = ArgReader::new(value, name)?;
+    let key = args.next_string()?;
+    let count = if args.is_empty() {
+        None
+    } else {
+        let count = usize::try_from(args.next_i64()?).map_err(|_| {
+            CommandError::InvalidArgument("value is out of range, must be positive".to_string())
+        })?;
+        Some(count)
+    };
+    args.finish()?;
+    Ok((key, count))
+}
+
+// key value [value ...]
+fn extract_push(
+    value: RespArray,
+    name: &'static str,
+) -> Result<(String, Vec<RespFrame>), CommandError> {
+    let mut args = ArgReader::new(value, name)?;
+    let key = args.next_string()?;
+    let mut values = vec![args.next_frame()?];
+    values.extend(args.rest_frames());
+    Ok((key, values))
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::BytesMut;
+
+    use crate::{Backend, BulkString, RespDecode, SimpleError};
+
+    use super::*;
+
+    fn items(values: &[&str]) -> RespFrame {
+        let values: Vec<RespFrame> = values.iter().map(|v| BulkString::new(*v).into()).collect();
+        RespArray::new(values).into()
+    }
+
+    fn lrange(backend: &Backend, start: i64, stop: i64) -> RespFrame {
+        LRange {
+            key: "list".to_string(),
+            start,
+            stop,
+        }
+        .execute(backend)
+    }
+
+    #[test]
+    fn test_push_and_lrange() -> anyhow::Result<()> {
+        let backend = Backend::new();
+        let mut buf = BytesMut::from("*4\r\n$5\r\nrpush\r\n$4\r\nlist\r\n$1\r\nc\r\n$1\r\nd\r\n");
+        let rpush: RPush = RespArray::decode(&mut buf)?.try_into()?;
+        assert_eq!(rpush.execute(&backend), RespFrame::Integer(2));
+
+        let mut buf = BytesMut::from("*4\r\n$5\r\nlpush\r\n$4\r\nlist\r\n$1\r\nb\r\n$1\r\na\r\n");
+        let lpush: LPush = RespArray::decode(&mut buf)?.try_into()?;
+        assert_eq!(lpush.execute(&backend), RespFrame::Integer(4));
+
+        assert_eq!(lrange(&backend, 0, -1), items(&["a", "b", "c", "d"]));
+        assert_eq!(lrange(&backend, -3, -2), items(&["b", "c"]));
+        assert_eq!(lrange(&backend, -100, 1), items(&["a", "b"]));
+        assert_eq!(lrange(&backend, 2, 100), items(&["c", "d"]));
+        assert_eq!(lrange(&backend, 3, 1), items(&[]));
+        assert_eq!(lrange(&backend, 5, 10), items(&[]));
+        assert_eq!(backend.type_of("list"), "list");
+        Ok(())
+    }
+
+    #[test]
+    fn test_missing_list() -> anyhow::Result<()> {
+        let backend = Backend::new();
+        assert_eq!(lrange(&backend, 0, -1), items(&[]));
+
+        let mut buf = BytesMut::from("*2\r\n$4\r\nllen\r\n$4\r\nlist\r\n");
+        let llen: LLen = RespArray::decode(&mut buf)?.try_into()?;
+        assert_eq!(llen.execute(&backend), RespFrame::Integer(0));
+
+        let mut buf = BytesMut::from("*2\r\n$5\r\nlpush\r\n$4\r\nlist\r\n");
+        assert!(LPush::try_from(RespArray::decode(&mut buf)?).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_list_wrong_type() {
+        let backend = Backend::new();
+        backend.set("list".to_string(), BulkString::new("value").into());
+        let wrongtype: RespFrame = SimpleError::new(CommandError::WrongType.to_string()).into();
+        assert_eq!(lrange(&backend, 0, -1), wrongtype);
+        let rpush = RPush {
+            key: "list".to_string(),
+            values: vec![BulkString::new("a").into()],
+        };
+        assert_eq!(rpush.execute(&backend), wrongtype);
+    }
+
+    fn push(backend: &Backend, values: &[&str]) {
+        let values = values.iter().map(|v| BulkString::new(*v).into()).collect();
+        RPush {
+            key: "list".to_string(),
+            values,
+        }
+        .execute(backend);
+    }
+
+    #[test]
+    fn test_pop_single() -> anyhow::Result<()> {
+        let backend = Backend::new();
+        push(&backend, &["a", "b", "c"]);
+
+        let mut buf = BytesMut::from("*2\r\n$4\r\nlpop\r\n$4\r\nlist\r\n");
+        let lpop: LPop = RespArray::decode(&mut buf)?.try_into()?;
+        assert_eq!(lpop.count, None);
+        assert_eq!(lpop.execute(&backend), BulkString::new("a").into());
+
+        let rpop = RPop {
+            key: "list".to_string(),
+            count: None,
+        };
+        assert_eq!(rpop.execute(&backend), BulkString::new("c").into());
+        assert_eq!(lrange(&backend, 0, -1), items(&["b"]));
+        Ok(())
+    }
+
+    #[test]
+    fn test_pop_count_exceeding_len() -> anyhow::Result<()> {
+        let backend = Backend::new();
+        push(&backend, &["a", "b", "c"]);
+        backend.expire("list", 100);
+
+        let mut buf = BytesMut::from("*3\r\n$4\r\nrpop\r\n$4\r\nlist\r\n$2\r\n10\r\n");
+        let rpop: RPop = RespArray::decode(&mut buf)?.try_into()?;
+        assert_eq!(rpop.count, Some(10));
+        assert_eq!(rpop.execute(&backend), items(&["c", "b", "a"]));
+
+        // 弹空之后 key 连同 TTL 一起消失
+        assert!(!backend.list.contains_key("list"));
+        assert!(backend.expires.is_empty());
+        assert_eq!(backend.type_of("list"), "none");
+
+        let mut buf = BytesMut::from("*3\r\n$4\r\nlpop\r\n$4\r\nlist\r\n$2\r\n-1\r\n");
+        assert!(LPop::try_from(RespArray::decode(&mut buf)?).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_pop_missing_key() {
+        let backend = Backend::new();
+        for count in [None, Some(2)] {
+            let lpop = LPop {
+                key: "list".to_string(),
+                count,
+            };
+            assert_eq!(lpop.execute(&backend), RespFrame::Null(RespNull));
+        }
+        assert!(backend.list.is_empty());
+    }
+
+    fn lindex(backend: &Backend, index: i64) -> RespFrame {
+        LIndex {
+            key: "list".to_string(),
+            index,
+        }
+        .execute(backend)
+    }
+
+    #[test]
+    fn test_lindex() -> anyhow::Result<()> {
+        let backend = Backend::new();
+        push(&backend, &["a", "b", "c"]);
+
+        let mut buf = BytesMut::from("*3\r\n$6\r\nlindex\r\n$4\r\nlist\r\n$2\r\n-1\r\n");
+        let cmd: LIndex = RespArray::decode(&mut buf)?.try_into()?;
+        assert_eq!(cmd.index, -1);
+        assert_eq!(cmd.execute(&backend), BulkString::new("c").into());
+
+        assert_eq!(lindex(&backend, 0), BulkString::new("a").into());
+        assert_eq!(lindex(&backend, -3), BulkString::new("a").into());
+        assert_eq!(lindex(&backend, 3), RespFrame::Null(RespNull));
+        assert_eq!(lindex(&backend, -4), RespFrame::Null(RespNull));
+        Ok(())
+    }
+
+    #[test]
+    fn test_lset() -> anyhow::Result<()> {
+        let backend = Backend::new();
+        let lset = |index: i64, value: &str| {
+            LSet {
+                key: "list".to_string(),
+                index,
+                value: BulkString::new(value).into(),
+            }
+            .execute(&backend)
+        };
+        assert_eq!(lset(0, "x"), SimpleError::new("ERR no such key").into());
+
+        push(&backend, &["a", "b", "c"]);
+        let mut buf = BytesMut::from("*4\r\n$4\r\nlset\r\n$4\r\nlist\r\n$1\r\n1\r\n$1\r\nB\r\n");
+        let cmd: LSet = RespArray::decode(&mut buf)?.try_into()?;
+        assert_eq!(cmd.execute(&backend), RESP_OK.clone());
+        assert_eq!(lset(-1, "C"), RESP_OK.clone());
+        assert_eq!(lrange(&backend, 0, -1), items(&["a", "B", "C"]));
+
+        let out_of_range: RespFrame = SimpleError::new("ERR index out of range").into();
+        assert_eq!(lset(3, "x"), out_of_range);
+        assert_eq!(lset(-4, "x"), out_of_range);
+        Ok(())
+    }
+}
@@ -1,9 +1,26 @@
-use crate::{RespArray, RespFrame, RespNull};
+use std::time::Instant;
 
-use super::{extract_args, validate_command, CommandError, CommandExecutor, Get, Set, RESP_OK};
+use dashmap::mapref::entry::Entry;
+
+use crate::{Backend, BigNumber, BulkString, KeyKind, RespArray, RespFrame, RespNull, SimpleError};
+
+use super::{
+    args::ArgReader, as_string_bytes, check_key_limit, check_kind, checked_ttl, extract_args,
+    validate_command, Append, CommandError, CommandExecutor, Decr, DecrBy, Get, GetDel, GetEx,
+    GetRange, GetSet, Incr, IncrBy, MGet, MSet, Set, SetNx, SetRange, Strlen, RESP_OK,
+};
+
+// 超过 proto_max_bulk_len 时返回的错误
+fn string_too_long(backend: &Backend, len: usize) -> Option<RespFrame> {
+    (len > backend.config.proto_max_bulk_len)
+        .then(|| SimpleError::new("ERR string exceeds maximum allowed size").into())
+}
 
 impl CommandExecutor for Get {
     fn execute(self, backend: &crate::Backend) -> RespFrame {
+        if let Err(e) = check_kind(backend, &self.key, KeyKind::String) {
+            return e.into();
+        }
         match backend.get(&self.key) {
             Some(value) => value,
             None => RespFrame::Null(RespNull),
@@ -13,11 +30,377 @@ impl CommandExecutor for Get {
 
 impl CommandExecutor for Set {
     fn execute(self, backend: &crate::Backend) -> RespFrame {
-        backend.set(self.key.clone(), self.value.clone());
+        if self.get {
+            if let Err(e) = check_kind(backend, &self.key, KeyKind::String) {
+                return e.into();
+            }
+        }
+        let reply = |old: Option<RespFrame>| match (self.get, old) {
+            (true, Some(old)) => old,
+            (true, None) => RespFrame::Null(RespNull),
+            (false, _) => RESP_OK.clone(),
+        };
+        let skipped = |old: Option<RespFrame>| match self.get {
+            true => reply(old),
+            false => RespFrame::Null(RespNull),
+        };
+
+        // 其他类型的 key 不在 map 里, entry 锁不住, 先在这里处理掉
+        let replaced = match backend.key_kind(&self.key) {
+            None | Some(KeyKind::String) => false,
+            Some(_) if self.nx => return skipped(None),
+            Some(_) => backend.del(&self.key),
+        };
+        // dbsize 会遍历 map, 必须在拿 entry 锁之前检查
+        if let Err(e) = check_key_limit(backend, &[&self.key]) {
+            return e.into();
+        }
+        let deadline = self.expire.and_then(|ttl| Instant::now().checked_add(ttl));
+        // set 会清掉 ttl, KEEPTTL 时保留原来的过期时间
+        let update_ttl = |key: &String| match (deadline, self.keepttl) {
+            (Some(deadline), _) => {
+                backend.expires.insert(key.clone(), deadline);
+            }
+            (None, true) => {}
+            (None, false) => {
+                backend.expires.remove(key);
+            }
+        };
+        // 持有 entry 锁, NX/XX 的判断、GET 读旧值、写入和 ttl 之间不会被其他连接插入
+        match backend.map.entry(self.key) {
+            Entry::Occupied(mut entry) => {
+                if self.nx {
+                    return skipped(Some(entry.get().clone()));
+                }
+                backend.raw_strings.remove(entry.key());
+                update_ttl(entry.key());
+                reply(Some(entry.insert(self.value)))
+            }
+            Entry::Vacant(entry) => {
+                if self.xx && !replaced {
+                    return skipped(None);
+                }
+                backend.index_key(entry.key(), KeyKind::String);
+                update_ttl(entry.key());
+                entry.insert(self.value);
+                reply(None)
+            }
+        }
+    }
+}
+
+impl CommandExecutor for MSet {
+    fn execute(self, backend: &crate::Backend) -> RespFrame {
+        let keys: Vec<&str> = self.pairs.iter().map(|(key, _)| key.as_str()).collect();
+        if let Err(e) = check_key_limit(backend, &keys) {
+            return e.into();
+        }
+        for (key, value) in self.pairs {
+            backend.set(key, value);
+        }
         RESP_OK.clone()
     }
 }
 
+impl CommandExecutor for SetNx {
+    fn execute(self, backend: &crate::Backend) -> RespFrame {
+        // 先清掉已过期的 key, 也挡住同名的 hash
+        if backend.exists(&self.key) {
+            return RespFrame::Integer(0);
+        }
+        if let Err(e) = check_key_limit(backend, &[&self.key]) {
+            return e.into();
+        }
+        match backend.map.entry(self.key) {
+            Entry::Occupied(_) => RespFrame::Integer(0),
+            Entry::Vacant(entry) => {
+                backend.index_key(entry.key(), KeyKind::String);
+                entry.insert(self.value);
+                RespFrame::Integer(1)
+            }
+        }
+    }
+}
+
+impl CommandExecutor for GetSet {
+    fn execute(self, backend: &crate::Backend) -> RespFrame {
+        if let Err(e) = check_kind(backend, &self.key, KeyKind::String) {
+            return e.into();
+        }
+        if let Err(e) = check_key_limit(backend, &[&self.key]) {
+            return e.into();
+        }
+        // 持有 entry 锁, 读旧值和写新值之间不会被其他连接插入
+        match backend.map.entry(self.key) {
+            Entry::Occupied(mut entry) => {
+                backend.raw_strings.remove(entry.key());
+                backend.expires.remove(entry.key());
+                entry.insert(self.value)
+            }
+            Entry::Vacant(entry) => {
+                backend.index_key(entry.key(), KeyKind::String);
+                entry.insert(self.value);
+                RespFrame::Null(RespNull)
+            }
+        }
+    }
+}
+
+impl CommandExecutor for GetDel {
+    fn execute(self, backend: &crate::Backend) -> RespFrame {
+        if let Err(e) = check_kind(backend, &self.key, KeyKind::String) {
+            return e.into();
+        }
+        // check_kind 已经处理掉过期的 key, 取值和删除在同一个 entry 锁里完成
+        match backend.map.entry(self.key) {
+            Entry::Occupied(entry) => {
+                backend.raw_strings.remove(entry.key());
+                backend.expires.remove(entry.key());
+                backend.unindex_key(entry.key(), KeyKind::String);
+                entry.remove()
+            }
+            Entry::Vacant(_) => RespFrame::Null(RespNull),
+        }
+    }
+}
+
+impl CommandExecutor for GetEx {
+    fn execute(self, backend: &crate::Backend) -> RespFrame {
+        if let Err(e) = check_kind(backend, &self.key, KeyKind::String) {
+            return e.into();
+        }
+        let Some(value) = backend.get(&self.key) else {
+            return RespFrame::Null(RespNull);
+        };
+        if let Some(expire) = self.expire {
+            backend.expire_in(&self.key, expire);
+        } else if self.persist {
+            backend.expires.remove(&self.key);
+        }
+        value
+    }
+}
+
+impl CommandExecutor for Append {
+    fn execute(self, backend: &crate::Backend) -> RespFrame {
+        if let Err(e) = check_kind(backend, &self.key, KeyKind::String) {
+            return e.into();
+        }
+        if let Err(e) = check_key_limit(backend, &[&self.key]) {
+            return e.into();
+        }
+        match backend.map.entry(self.key) {
+            Entry::Occupied(mut entry) => {
+                let mut bytes = match as_string_bytes(entry.get()) {
+                    Ok(bytes) => bytes,
+                    Err(e) => return e.into(),
+                };
+                if let Some(e) = string_too_long(backend, bytes.len() + self.value.len()) {
+                    return e;
+                }
+                bytes.extend_from_slice(&self.value);
+                let len = bytes.len() as i64;
+                backend.raw_strings.insert(entry.key().clone());
+                entry.insert(BulkString::new(bytes).into());
+                RespFrame::Integer(len)
+            }
+            Entry::Vacant(entry) => {
+                if let Some(e) = string_too_long(backend, self.value.len()) {
+                    return e;
+                }
+                let len = self.value.len() as i64;
+                backend.index_key(entry.key(), KeyKind::String);
+                backend.raw_strings.insert(entry.key().clone());
+                entry.insert(BulkString::new(self.value).into());
+                RespFrame::Integer(len)
+            }
+        }
+    }
+}
+
+impl CommandExecutor for SetRange {
+    fn execute(self, backend: &crate::Backend) -> RespFrame {
+        if let Err(e) = check_kind(backend, &self.key, KeyKind::String) {
+            return e.into();
+        }
+        if !self.value.is_empty() {
+            if let Err(e) = check_key_limit(backend, &[&self.key]) {
+                return e.into();
+            }
+        }
+        match backend.map.entry(self.key) {
+            Entry::Occupied(mut entry) => {
+                let mut bytes = match as_string_bytes(entry.get()) {
+                    Ok(bytes) => bytes,
+                    Err(e) => return e.into(),
+                };
+                if self.value.is_empty() {
+                    return RespFrame::Integer(bytes.len() as i64);
+                }
+                if let Some(e) = setrange_too_long(backend, self.offset, &self.value) {
+                    return e;
+                }
+                overwrite(&mut bytes, self.offset, &self.value);
+                let len = bytes.len() as i64;
+                backend.raw_strings.insert(entry.key().clone());
+                entry.insert(BulkString::new(bytes).into());
+                RespFrame::Integer(len)
+            }
+            // an empty write never creates the key
+            Entry::Vacant(_) if self.value.is_empty() => RespFrame::Integer(0),
+            Entry::Vacant(entry) => {
+                if let Some(e) = setrange_too_long(backend, self.offset, &self.value) {
+                    return e;
+                }
+                let mut bytes = vec![];
+                overwrite(&mut bytes, self.offset, &self.value);
+                let len = bytes.len() as i64;
+                backend.index_key(entry.key(), KeyKind::String);
+                backend.raw_strings.insert(entry.key().clone());
+                entry.insert(BulkString::new(bytes).into());
+                RespFrame::Integer(len)
+            }
+        }
+    }
+}
+
+// 偏移加长度溢出时也算超长
+fn setrange_too_long(backend: &Backend, offset: usize, value: &[u8]) -> Option<RespFrame> {
+    match offset.checked_add(value.len()) {
+        Some(end) => string_too_long(backend, end),
+        None => string_too_long(backend, usize::MAX),
+    }
+}
+
+// zero-pads the string when the offset is past its end
+fn overwrite(bytes: &mut Vec<u8>, offset: usize, value: &[u8]) {
+    let end = offset + value.len();
+    if bytes.len() < end {
+        bytes.resize(end, 0);
+    }
+    bytes[offset..end].copy_from_slice(value);
+}
+
+impl CommandExecutor for Incr {
+    fn execute(self, backend: &crate::Backend) -> RespFrame {
+        incr_by(backend, self.key, 1)
+    }
+}
+
+impl CommandExecutor for Decr {
+    fn execute(self, backend: &crate::Backend) -> RespFrame {
+        incr_by(backend, self.key, -1)
+    }
+}
+
+impl CommandExecutor for IncrBy {
+    fn execute(self, backend: &crate::Backend) -> RespFrame {
+        incr_by(backend, self.key, self.delta)
+    }
+}
+
+impl CommandExecutor for DecrBy {
+    fn execute(self, backend: &crate::Backend) -> RespFrame {
+        match self.delta.checked_neg() {
+            Some(delta) => incr_by(backend, self.key, delta),
+            None => SimpleError::new("ERR decrement would overflow").into(),
+        }
+    }
+}
+
+// the read-modify-write happens under the entry lock so concurrent INCRs can't lose updates.
+// With `wide_integers` the counter is an i128 and values outside i64 reply as big numbers
+fn incr_by(backend: &Backend, key: String, delta: i64) -> RespFrame {
+    if let Err(e) = check_kind(backend, &key, KeyKind::String) {
+        return e.into();
+    }
+    if let Err(e) = check_key_limit(backend, &[&key]) {
+        return e.into();
+    }
+    let not_integer = || SimpleError::new("ERR value is not an integer or out of range").into();
+    let mut entry = match backend.map.entry(key) {
+        Entry::Occupied(entry) => entry,
+        Entry::Vacant(entry) => {
+            backend.index_key(entry.key(), KeyKind::String);
+            entry.insert(BulkString::new(delta.to_string()).into());
+            return RespFrame::Integer(delta);
+        }
+    };
+    let in_range = |value: &i128| backend.config.wide_integers || i64::try_from(*value).is_ok();
+    let current = match as_string_bytes(entry.get()) {
+        Ok(bytes) => String::from_utf8_lossy(&bytes).parse::<i128>().ok(),
+        Err(e) => return e.into(),
+    };
+    let Some(current) = current.filter(in_range) else {
+        return not_integer();
+    };
+    match current.checked_add(delta as i128).filter(in_range) {
+        Some(value) => {
+            backend.raw_strings.remove(entry.key());
+            entry.insert(BulkString::new(value.to_string()).into());
+            match i64::try_from(value) {
+                Ok(value) => RespFrame::Integer(value),
+                // RESP2 连接在编码时会降级成 bulk string
+                Err(_) => BigNumber::from(value).into(),
+            }
+        }
+        None => SimpleError::new("ERR increment or decrement would overflow").into(),
+    }
+}
+
+impl CommandExecutor for GetRange {
+    fn execute(self, backend: &crate::Backend) -> RespFrame {
+        if let Err(e) = check_kind(backend, &self.key, KeyKind::String) {
+            return e.into();
+        }
+        let bytes = match backend.get(&self.key).as_ref().map(as_string_bytes) {
+            Some(Ok(bytes)) => bytes,
+            Some(Err(e)) => return e.into(),
+            None => vec![],
+        };
+        // 负数下标从末尾开始计算, 越界的部分截断
+        let len = bytes.len() as i64;
+        let index = |i: i64| if i < 0 { (len + i).max(0) } else { i };
+        let (start, end) = (index(self.start), index(self.end).min(len - 1));
+        if len == 0 || start > end {
+            return BulkString::new(vec![]).into();
+        }
+        BulkString::new(&bytes[start as usize..=end as usize]).into()
+    }
+}
+
+impl CommandExecutor for MGet {
+    fn execute(self, backend: &crate::Backend) -> RespFrame {
+        // 不存在或不是字符串的 key 都返回 nil, 保证结果和 key 一一对应
+        let values: Vec<RespFrame> = self
+            .keys
+            .iter()
+            .map(|key| match backend.get(key) {
+                Some(
+                    value @ (RespFrame::BulkString(_)
+                    | RespFrame::SimpleString(_)
+                    | RespFrame::Integer(_)),
+                ) => value,
+                _ => BulkString(None).into(),
+            })
+            .collect();
+        RespArray::new(values).into()
+    }
+}
+
+impl CommandExecutor for Strlen {
+    fn execute(self, backend: &crate::Backend) -> RespFrame {
+        if let Err(e) = check_kind(backend, &self.key, KeyKind::String) {
+            return e.into();
+        }
+        match backend.get(&self.key).as_ref().map(as_string_bytes) {
+            Some(Ok(bytes)) => RespFrame::Integer(bytes.len() as i64),
+            Some(Err(e)) => e.into(),
+            None => RespFrame::Integer(0),
+        }
+    }
+}
+
 impl TryFrom<RespArray> for Get {
     type Error = CommandError;
     fn try_from(value: RespArray) -> Result<Self, Self::Error> {
@@ -26,7 +409,7 @@ impl TryFrom<RespArray> for Get {
         let mut args = extract_args(value, 1)?.into_iter();
         match args.next() {
             Some(RespFrame::BulkString(key)) => Ok(Get {
-                key: String::from_utf8(key.0.unwrap())?,
+                key: String::from_utf8(key.0.unwrap_or_default())?,
             }),
             _ => Err(CommandError::InvalidArgument("Invalid key".to_string())),
         }
@@ -36,13 +419,139 @@ impl TryFrom<RespArray> for Get {
 impl TryFrom<RespArray> for Set {
     type Error = CommandError;
     fn try_from(value: RespArray) -> Result<Self, Self::Error> {
-        validate_command(&value, &["set"], 2)?;
+        let mut args = extract_args(value, 1)?.into_iter();
+        let mut set = match (args.next(), args.next()) {
+            (Some(RespFrame::BulkString(key)), Some(value)) => Set {
+                key: String::from_utf8(key.0.unwrap_or_default())?,
+                value,
+                expire: None,
+                nx: false,
+                xx: false,
+                get: false,
+                keepttl: false,
+            },
+            _ => {
+                return Err(CommandError::InvalidArgument(
+                    "Invalid key or value".to_string(),
+                ))
+            }
+        };
+
+        let syntax_error = || CommandError::InvalidArgument("syntax error".to_string());
+        while let Some(arg) = args.next() {
+            let RespFrame::BulkString(arg) = arg else {
+                return Err(syntax_error());
+            };
+            match arg.as_ref().to_ascii_lowercase().as_slice() {
+                b"nx" if !set.xx => set.nx = true,
+                b"xx" if !set.nx => set.xx = true,
+                b"get" => set.get = true,
+                b"keepttl" if set.expire.is_none() => set.keepttl = true,
+                unit @ (b"ex" | b"px") if set.expire.is_none() && !set.keepttl => {
+                    let amount = match args.next() {
+                        Some(RespFrame::BulkString(amount)) => {
+                            String::from_utf8_lossy(amount.as_ref()).parse::<i64>().ok()
+                        }
+                        _ => return Err(syntax_error()),
+                    };
+                    // 不是数字也按过期时间非法处理
+                    set.expire = Some(checked_ttl(amount.unwrap_or(0), unit == b"px", "set")?);
+                }
+                _ => return Err(syntax_error()),
+            }
+        }
+        Ok(set)
+    }
+}
+
+impl TryFrom<RespArray> for MSet {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        let args = extract_args(value, 1)?;
+        if args.is_empty() || args.len() % 2 != 0 {
+            return Err(CommandError::InvalidArgument(
+                "wrong number of arguments for 'mset' command".to_string(),
+            ));
+        }
+        let mut pairs = Vec::with_capacity(args.len() / 2);
+        let mut args = args.into_iter();
+        while let (Some(key), Some(value)) = (args.next(), args.next()) {
+            match key {
+                RespFrame::BulkString(key) => {
+                    pairs.push((String::from_utf8(key.0.unwrap_or_default())?, value))
+                }
+                _ => return Err(CommandError::InvalidArgument("Invalid key".to_string())),
+            }
+        }
+        Ok(MSet { pairs })
+    }
+}
+
+impl TryFrom<RespArray> for SetNx {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        let mut args = ArgReader::new(value, "setnx")?;
+        let (key, value) = (args.next_string()?, args.next_frame()?);
+        args.finish()?;
+        Ok(SetNx { key, value })
+    }
+}
+
+impl TryFrom<RespArray> for GetSet {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        let mut args = ArgReader::new(value, "getset")?;
+        let (key, value) = (args.next_string()?, args.next_frame()?);
+        args.finish()?;
+        Ok(GetSet { key, value })
+    }
+}
+
+impl TryFrom<RespArray> for GetDel {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        let mut args = ArgReader::new(value, "getdel")?;
+        let key = args.next_string()?;
+        args.finish()?;
+        Ok(GetDel { key })
+    }
+}
+
+impl TryFrom<RespArray> for GetEx {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        let mut args = ArgReader::new(value, "getex")?;
+        let mut getex = GetEx {
+            key: args.next_string()?,
+            expire: None,
+            persist: false,
+        };
+        if !args.is_empty() {
+            let syntax_error = || CommandError::InvalidArgument("syntax error".to_string());
+            match args.next_bytes()?.to_ascii_lowercase().as_slice() {
+                b"persist" => getex.persist = true,
+                unit @ (b"ex" | b"px") => {
+                    let amount = args.next_i64()?;
+                    getex.expire = Some(checked_ttl(amount, unit == b"px", "getex")?);
+                }
+                _ => return Err(syntax_error()),
+            }
+        }
+        args.finish()?;
+        Ok(getex)
+    }
+}
+
+impl TryFrom<RespArray> for Append {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["append"], 2)?;
 
         let mut args = extract_args(value, 1)?.into_iter();
         match (args.next(), args.next()) {
-            (Some(RespFrame::BulkString(key)), Some(value)) => Ok(Set {
-                key: String::from_utf8(key.0.unwrap())?,
-                value,
+            (Some(RespFrame::BulkString(key)), Some(RespFrame::BulkString(value))) => Ok(Append {
+                key: String::from_utf8(key.0.unwrap_or_default())?,
+                value: value.0.unwrap_or_default(),
             }),
             _ => Err(CommandError::InvalidArgument(
                 "Invalid key or value".to_string(),
@@ -51,12 +560,150 @@ impl TryFrom<RespArray> for Set {
     }
 }
 
+impl TryFrom<RespArray> for MGet {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        let mut args = ArgReader::new(value, "mget")?;
+        // 至少一个 key
+        let mut keys = vec![args.next_string()?];
+        keys.extend(args.rest_strings()?);
+        Ok(MGet { keys })
+    }
+}
+
+impl TryFrom<RespArray> for Strlen {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["strlen"], 1)?;
+        Ok(Strlen {
+            key: extract_key(value)?,
+        })
+    }
+}
+
+impl TryFrom<RespArray> for Incr {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["incr"], 1)?;
+        Ok(Incr {
+            key: extract_key(value)?,
+        })
+    }
+}
+
+impl TryFrom<RespArray> for Decr {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["decr"], 1)?;
+        Ok(Decr {
+            key: extract_key(value)?,
+        })
+    }
+}
+
+impl TryFrom<RespArray> for IncrBy {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        let mut args = ArgReader::new(value, "incrby")?;
+        let (key, delta) = (args.next_string()?, args.next_i64()?);
+        args.finish()?;
+        Ok(IncrBy { key, delta })
+    }
+}
+
+impl TryFrom<RespArray> for DecrBy {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        let mut args = ArgReader::new(value, "decrby")?;
+        let (key, delta) = (args.next_string()?, args.next_i64()?);
+        args.finish()?;
+        Ok(DecrBy { key, delta })
+    }
+}
+
+fn extract_key(value: RespArray) -> Result<String, CommandError> {
+    match extract_args(value, 1)?.into_iter().next() {
+        Some(RespFrame::BulkString(key)) => Ok(String::from_utf8(key.0.unwrap_or_default())?),
+        _ => Err(CommandError::InvalidArgument("Invalid key".to_string())),
+    }
+}
+
+impl TryFrom<RespArray> for GetRange {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["getrange"], 3)?;
+
+        let mut args = extract_args(value, 1)?.into_iter();
+        match (args.next(), args.next(), args.next()) {
+            (
+                Some(RespFrame::BulkString(key)),
+                Some(RespFrame::BulkString(start)),
+                Some(RespFrame::BulkString(end)),
+            ) => {
+                let parse = |s: BulkString| {
+                    String::from_utf8_lossy(s.as_ref())
+                        .parse::<i64>()
+                        .map_err(|_| {
+                            CommandError::InvalidArgument(
+                                "value is not an integer or out of range".to_string(),
+                            )
+                        })
+                };
+                Ok(GetRange {
+                    key: String::from_utf8(key.0.unwrap_or_default())?,
+                    start: parse(start)?,
+                    end: parse(end)?,
+                })
+            }
+            _ => Err(CommandError::InvalidArgument(
+                "Invalid key or range".to_string(),
+            )),
+        }
+    }
+}
+
+impl TryFrom<RespArray> for SetRange {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["setrange"], 3)?;
+
+        let mut args = extract_args(value, 1)?.into_iter();
+        match (args.next(), args.next(), args.next()) {
+            (
+                Some(RespFrame::BulkString(key)),
+                Some(RespFrame::BulkString(offset)),
+                Some(RespFrame::BulkString(value)),
+            ) => {
+                let value = value.0.unwrap_or_default();
+                // redis 按 long 解析偏移, 负数或者更大的值都超出范围
+                let offset = String::from_utf8_lossy(offset.as_ref())
+                    .parse::<i64>()
+                    .ok()
+                    .and_then(|offset| usize::try_from(offset).ok())
+                    .ok_or_else(|| {
+                        CommandError::InvalidArgument("offset is out of range".to_string())
+                    })?;
+                Ok(SetRange {
+                    key: String::from_utf8(key.0.unwrap_or_default())?,
+                    offset,
+                    value,
+                })
+            }
+            _ => Err(CommandError::InvalidArgument(
+                "Invalid key, offset or value".to_string(),
+            )),
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
+    use std::time::Duration;
+
     use anyhow::Result;
     use bytes::BytesMut;
 
-    use crate::{Backend, RespDecode};
+    use crate::{Backend, RespDecode, RespEncode};
 
     use super::*;
 
@@ -81,12 +728,114 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_set_options() -> Result<()> {
+        let parse = |input: &str| -> Result<Set> {
+            let frame = RespArray::decode(&mut BytesMut::from(input))?;
+            Ok(frame.try_into()?)
+        };
+        let backend = Backend::new();
+
+        let set = parse("*4\r\n$3\r\nset\r\n$1\r\nk\r\n$1\r\nv\r\n$2\r\nxx\r\n")?;
+        assert!(set.xx);
+        assert_eq!(set.execute(&backend), RespFrame::Null(RespNull));
+        assert!(backend.get("k").is_none());
+
+        let set = parse(
+            "*6\r\n$3\r\nset\r\n$1\r\nk\r\n$1\r\nv\r\n$2\r\nnx\r\n$2\r\nex\r\n$3\r\n100\r\n",
+        )?;
+        assert_eq!(set.expire, Some(Duration::from_secs(100)));
+        assert_eq!(set.execute(&backend), RESP_OK.clone());
+        assert_eq!(backend.ttl("k"), 100);
+
+        let set = parse("*4\r\n$3\r\nset\r\n$1\r\nk\r\n$1\r\nw\r\n$2\r\nNX\r\n")?;
+        assert_eq!(set.execute(&backend), RespFrame::Null(RespNull));
+        assert_eq!(backend.get("k"), Some(BulkString::new("v").into()));
+
+        let set =
+            parse("*6\r\n$3\r\nset\r\n$1\r\nk\r\n$1\r\nw\r\n$2\r\nxx\r\n$2\r\npx\r\n$2\r\n20\r\n")?;
+        assert_eq!(set.expire, Some(Duration::from_millis(20)));
+        assert_eq!(set.execute(&backend), RESP_OK.clone());
+        std::thread::sleep(Duration::from_millis(40));
+        assert!(backend.get("k").is_none());
+
+        for input in [
+            "*5\r\n$3\r\nset\r\n$1\r\nk\r\n$1\r\nv\r\n$2\r\nnx\r\n$2\r\nxx\r\n",
+            "*5\r\n$3\r\nset\r\n$1\r\nk\r\n$1\r\nv\r\n$2\r\nex\r\n$1\r\n0\r\n",
+            "*4\r\n$3\r\nset\r\n$1\r\nk\r\n$1\r\nv\r\n$4\r\nkeep\r\n",
+            "*5\r\n$3\r\nset\r\n$1\r\nk\r\n$1\r\nv\r\n$2\r\nXX\r\n$2\r\nPX\r\n",
+            "*7\r\n$3\r\nset\r\n$1\r\nk\r\n$1\r\nv\r\n$7\r\nkeepttl\r\n$2\r\nex\r\n$1\r\n1\r\n",
+        ] {
+            assert!(parse(input).is_err(), "{:?}", input);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_set_expire_out_of_range() -> Result<()> {
+        for amount in ["18446744073709551615", "9223372036854775807", "-1"] {
+            let input = format!(
+                "*5\r\n$3\r\nset\r\n$1\r\nk\r\n$1\r\nv\r\n$2\r\nEX\r\n${}\r\n{}\r\n",
+                amount.len(),
+                amount
+            );
+            let frame = RespArray::decode(&mut BytesMut::from(input.as_str()))?;
+            let err = Set::try_from(frame).unwrap_err();
+            assert_eq!(
+                err.to_string(),
+                "Invalid argument: invalid expire time in 'set' command"
+            );
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_set_keepttl_and_get() -> Result<()> {
+        let parse = |input: &str| -> Result<Set> {
+            let frame = RespArray::decode(&mut BytesMut::from(input))?;
+            Ok(frame.try_into()?)
+        };
+        let set = parse("*5\r\n$3\r\nset\r\n$1\r\nk\r\n$1\r\nv\r\n$3\r\nGET\r\n$7\r\nKEEPTTL\r\n")?;
+        assert!(set.get && set.keepttl);
+
+        // (keepttl, get) -> (reply, ttl afterwards)
+        let old: RespFrame = BulkString::new("old").into();
+        for (keepttl, get, reply, ttl) in [
+            (false, false, RESP_OK.clone(), -1),
+            (true, false, RESP_OK.clone(), 100),
+            (false, true, old.clone(), -1),
+            (true, true, old.clone(), 100),
+        ] {
+            let backend = Backend::new();
+            backend.set("k".to_string(), old.clone());
+            backend.expire("k", 100);
+            let set = Set {
+                key: "k".to_string(),
+                value: BulkString::new("new").into(),
+                expire: None,
+                nx: false,
+                xx: false,
+                get,
+                keepttl,
+            };
+            assert_eq!(set.execute(&backend), reply, "keepttl={keepttl} get={get}");
+            assert_eq!(backend.ttl("k"), ttl, "keepttl={keepttl} get={get}");
+            assert_eq!(backend.get("k"), Some(BulkString::new("new").into()));
+        }
+        Ok(())
+    }
+
     #[test]
     fn test_set_get_command() -> Result<()> {
         let backend = Backend::new();
         let cmd = Set {
             key: "key".to_string(),
             value: RespFrame::BulkString(b"value".into()),
+            expire: None,
+            nx: false,
+            xx: false,
+            get: false,
+            keepttl: false,
         };
         let result = cmd.execute(&backend);
         assert_eq!(result, RESP_OK.clone());
@@ -98,4 +847,738 @@ mod tests {
         assert_eq!(value, RespFrame::BulkString(b"value".into()));
         Ok(())
     }
+
+    #[test]
+    fn test_get_on_hash_key_is_wrong_type() {
+        let backend = Backend::new();
+        backend.hset(
+            "hash".to_string(),
+            "field".to_string(),
+            RespFrame::BulkString(b"value".into()),
+        );
+
+        let cmd = Get {
+            key: "hash".to_string(),
+        };
+        assert_eq!(cmd.execute(&backend), CommandError::WrongType.into());
+    }
+
+    #[test]
+    fn test_append_to_missing_key() -> Result<()> {
+        let mut buf = BytesMut::from("*3\r\n$6\r\nappend\r\n$3\r\nkey\r\n$5\r\nhello\r\n");
+        let frame = RespArray::decode(&mut buf)?;
+        let append: Append = frame.try_into()?;
+
+        let backend = Backend::new();
+        assert_eq!(append.execute(&backend), RespFrame::Integer(5));
+        assert_eq!(backend.get("key"), Some(BulkString::new("hello").into()));
+
+        let append = Append {
+            key: "key".to_string(),
+            value: b" world".to_vec(),
+        };
+        assert_eq!(append.execute(&backend), RespFrame::Integer(11));
+        assert_eq!(
+            backend.get("key"),
+            Some(BulkString::new("hello world").into())
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_append_to_integer() {
+        let backend = Backend::new();
+        backend.set("key".to_string(), RespFrame::Integer(10));
+        let append = Append {
+            key: "key".to_string(),
+            value: b"5".to_vec(),
+        };
+        assert_eq!(append.execute(&backend), RespFrame::Integer(3));
+        assert_eq!(backend.get("key"), Some(BulkString::new("105").into()));
+
+        backend.hset(
+            "hash".to_string(),
+            "field".to_string(),
+            RespFrame::Integer(1),
+        );
+        let append = Append {
+            key: "hash".to_string(),
+            value: b"5".to_vec(),
+        };
+        assert_eq!(append.execute(&backend), CommandError::WrongType.into());
+    }
+
+    #[test]
+    fn test_append_to_non_string_frame() {
+        let backend = Backend::new();
+        let list: RespFrame = RespArray::new([BulkString::new("a").into()]).into();
+        backend.set("key".to_string(), list.clone());
+        let append = Append {
+            key: "key".to_string(),
+            value: b"b".to_vec(),
+        };
+        assert_eq!(append.execute(&backend), CommandError::WrongType.into());
+        assert_eq!(backend.get("key"), Some(list));
+    }
+
+    #[test]
+    fn test_mset() -> Result<()> {
+        let mut buf =
+            BytesMut::from("*5\r\n$4\r\nmset\r\n$1\r\na\r\n$1\r\n1\r\n$1\r\nb\r\n$1\r\n2\r\n");
+        let frame = RespArray::decode(&mut buf)?;
+        let mset: MSet = frame.try_into()?;
+
+        let backend = Backend::new();
+        assert_eq!(mset.execute(&backend), RESP_OK.clone());
+        assert_eq!(backend.get("a"), Some(BulkString::new("1").into()));
+        assert_eq!(backend.get("b"), Some(BulkString::new("2").into()));
+
+        let mut buf = BytesMut::from("*4\r\n$4\r\nmset\r\n$1\r\na\r\n$1\r\n1\r\n$1\r\nb\r\n");
+        let frame = RespArray::decode(&mut buf)?;
+        let ret: Result<MSet, _> = frame.try_into();
+        assert!(matches!(ret, Err(CommandError::InvalidArgument(_))));
+        Ok(())
+    }
+
+    #[test]
+    fn test_setnx() -> Result<()> {
+        let mut buf = BytesMut::from("*3\r\n$5\r\nsetnx\r\n$3\r\nkey\r\n$5\r\nfirst\r\n");
+        let frame = RespArray::decode(&mut buf)?;
+        let setnx: SetNx = frame.try_into()?;
+
+        let backend = Backend::new();
+        assert_eq!(setnx.execute(&backend), RespFrame::Integer(1));
+        let setnx = SetNx {
+            key: "key".to_string(),
+            value: BulkString::new("second").into(),
+        };
+        assert_eq!(setnx.execute(&backend), RespFrame::Integer(0));
+        assert_eq!(backend.get("key"), Some(BulkString::new("first").into()));
+        Ok(())
+    }
+
+    #[test]
+    fn test_maxkeys() {
+        let backend = Backend::with_config(crate::ServerConfig {
+            maxkeys: Some(2),
+            ..Default::default()
+        });
+        let set = |key: &str, value: &str| {
+            Set {
+                key: key.to_string(),
+                value: BulkString::new(value).into(),
+                expire: None,
+                nx: false,
+                xx: false,
+                get: false,
+                keepttl: false,
+            }
+            .execute(&backend)
+        };
+        let limit: RespFrame =
+            SimpleError::new("ERR reached the configured maximum number of keys").into();
+
+        assert_eq!(set("a", "1"), RESP_OK.clone());
+        backend.hset("h".to_string(), "f".to_string(), RespFrame::Integer(1));
+        assert_eq!(set("b", "1"), limit);
+        assert!(!backend.exists("b"));
+
+        // 已存在的 key 仍然可以写
+        assert_eq!(set("a", "2"), RESP_OK.clone());
+        let incr = Incr {
+            key: "a".to_string(),
+        };
+        assert_eq!(incr.execute(&backend), RespFrame::Integer(3));
+        let incr = Incr {
+            key: "c".to_string(),
+        };
+        assert_eq!(incr.execute(&backend), limit);
+
+        let mset = MSet {
+            pairs: vec![
+                ("a".to_string(), BulkString::new("4").into()),
+                ("c".to_string(), BulkString::new("5").into()),
+            ],
+        };
+        assert_eq!(mset.execute(&backend), limit);
+        assert_eq!(backend.get("a"), Some(BulkString::new("3").into()));
+        assert_eq!(backend.dbsize(), 2);
+    }
+
+    #[test]
+    fn test_maxkeys_ignores_expired_keys() {
+        let backend = Backend::with_config(crate::ServerConfig {
+            maxkeys: Some(2),
+            ..Default::default()
+        });
+        let set = |key: &str, expire: Option<Duration>| {
+            Set {
+                key: key.to_string(),
+                value: BulkString::new("v").into(),
+                expire,
+                nx: false,
+                xx: false,
+                get: false,
+                keepttl: false,
+            }
+            .execute(&backend)
+        };
+
+        assert_eq!(set("a", Some(Duration::from_millis(1))), RESP_OK.clone());
+        assert_eq!(set("b", Some(Duration::from_millis(1))), RESP_OK.clone());
+        std::thread::sleep(Duration::from_millis(10));
+        // a 和 b 已经过期, 但还没有被访问过
+        assert_eq!(set("c", None), RESP_OK.clone());
+        assert_eq!(backend.dbsize(), 1);
+    }
+
+    #[test]
+    fn test_getset() -> Result<()> {
+        let mut buf = BytesMut::from("*3\r\n$6\r\ngetset\r\n$3\r\nkey\r\n$3\r\nnew\r\n");
+        let frame = RespArray::decode(&mut buf)?;
+        let getset: GetSet = frame.try_into()?;
+
+        let backend = Backend::new();
+        backend.set("key".to_string(), BulkString::new("old").into());
+        assert_eq!(getset.execute(&backend), BulkString::new("old").into());
+        assert_eq!(backend.get("key"), Some(BulkString::new("new").into()));
+
+        let getset = GetSet {
+            key: "missing".to_string(),
+            value: BulkString::new("value").into(),
+        };
+        assert_eq!(getset.execute(&backend), RespFrame::Null(RespNull));
+        assert_eq!(
+            backend.get("missing"),
+            Some(BulkString::new("value").into())
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_getdel() -> Result<()> {
+        let mut buf = BytesMut::from("*2\r\n$6\r\ngetdel\r\n$3\r\nkey\r\n");
+        let frame = RespArray::decode(&mut buf)?;
+        let getdel: GetDel = frame.try_into()?;
+
+        let backend = Backend::new();
+        backend.set("key".to_string(), BulkString::new("value").into());
+        backend.expire("key", 100);
+        assert_eq!(getdel.execute(&backend), BulkString::new("value").into());
+        assert!(!backend.exists("key"));
+        assert!(backend.expires.is_empty());
+
+        let getdel = GetDel {
+            key: "key".to_string(),
+        };
+        assert_eq!(getdel.execute(&backend), RespFrame::Null(RespNull));
+
+        backend.hset(
+            "hash".to_string(),
+            "field".to_string(),
+            BulkString::new("value").into(),
+        );
+        let getdel = GetDel {
+            key: "hash".to_string(),
+        };
+        assert_eq!(getdel.execute(&backend), CommandError::WrongType.into());
+        assert!(backend.exists("hash"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_getex() -> Result<()> {
+        let mut buf = BytesMut::from("*3\r\n$5\r\ngetex\r\n$3\r\nkey\r\n$7\r\npersist\r\n");
+        let frame = RespArray::decode(&mut buf)?;
+        let getex: GetEx = frame.try_into()?;
+
+        let backend = Backend::new();
+        backend.set("key".to_string(), BulkString::new("value").into());
+        backend.expire("key", 100);
+        assert_eq!(getex.execute(&backend), BulkString::new("value").into());
+        assert_eq!(backend.ttl("key"), -1);
+
+        let mut buf = BytesMut::from("*4\r\n$5\r\ngetex\r\n$3\r\nkey\r\n$2\r\nEX\r\n$2\r\n10\r\n");
+        let frame = RespArray::decode(&mut buf)?;
+        let getex: GetEx = frame.try_into()?;
+        assert_eq!(getex.execute(&backend), BulkString::new("value").into());
+        assert_eq!(backend.ttl("key"), 10);
+
+        let mut buf = BytesMut::from("*4\r\n$5\r\ngetex\r\n$3\r\nkey\r\n$2\r\nex\r\n$1\r\n0\r\n");
+        let frame = RespArray::decode(&mut buf)?;
+        assert!(GetEx::try_from(frame).is_err());
+
+        let mut buf = BytesMut::from(
+            "*4\r\n$5\r\ngetex\r\n$3\r\nkey\r\n$2\r\nex\r\n$19\r\n9223372036854775807\r\n",
+        );
+        let frame = RespArray::decode(&mut buf)?;
+        assert!(GetEx::try_from(frame).is_err());
+        assert_eq!(backend.ttl("key"), 10);
+
+        backend.hset(
+            "hash".to_string(),
+            "field".to_string(),
+            BulkString::new("value").into(),
+        );
+        let getex = GetEx {
+            key: "hash".to_string(),
+            expire: None,
+            persist: true,
+        };
+        assert_eq!(getex.execute(&backend), CommandError::WrongType.into());
+        Ok(())
+    }
+
+    #[test]
+    fn test_mget() -> Result<()> {
+        let mut buf = BytesMut::from(
+            "*5\r\n$4\r\nmget\r\n$1\r\na\r\n$7\r\nmissing\r\n$4\r\nhash\r\n$1\r\nb\r\n",
+        );
+        let frame = RespArray::decode(&mut buf)?;
+        let mget: MGet = frame.try_into()?;
+
+        let backend = Backend::new();
+        backend.set("a".to_string(), BulkString::new("1").into());
+        backend.set("b".to_string(), RespFrame::Integer(2));
+        backend.hset(
+            "hash".to_string(),
+            "field".to_string(),
+            BulkString::new("v").into(),
+        );
+
+        assert_eq!(
+            mget.execute(&backend),
+            RespArray::new([
+                BulkString::new("1").into(),
+                BulkString(None).into(),
+                BulkString(None).into(),
+                RespFrame::Integer(2),
+            ])
+            .into()
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_strlen_counts_bytes() -> Result<()> {
+        let mut buf = BytesMut::from("*2\r\n$6\r\nstrlen\r\n$3\r\nkey\r\n");
+        let frame = RespArray::decode(&mut buf)?;
+        let strlen: Strlen = frame.try_into()?;
+
+        let backend = Backend::new();
+        assert_eq!(
+            Strlen {
+                key: "key".to_string()
+            }
+            .execute(&backend),
+            RespFrame::Integer(0)
+        );
+
+        // 4 个字符, 12 个字节
+        backend.set("key".to_string(), BulkString::new("你好世界").into());
+        assert_eq!(strlen.execute(&backend), RespFrame::Integer(12));
+
+        backend.set("list".to_string(), RespArray::new([]).into());
+        let strlen = Strlen {
+            key: "list".to_string(),
+        };
+        assert_eq!(strlen.execute(&backend), CommandError::WrongType.into());
+        Ok(())
+    }
+
+    #[test]
+    fn test_getrange() -> Result<()> {
+        let mut buf = BytesMut::from("*4\r\n$8\r\ngetrange\r\n$3\r\nint\r\n$1\r\n0\r\n$1\r\n2\r\n");
+        let frame = RespArray::decode(&mut buf)?;
+        let cmd: GetRange = frame.try_into()?;
+
+        let backend = Backend::new();
+        backend.set("int".to_string(), RespFrame::Integer(12345));
+        assert_eq!(cmd.execute(&backend), BulkString::new("123").into());
+
+        backend.set("str".to_string(), BulkString::new("Hello World").into());
+        let range = |key: &str, start, end| GetRange {
+            key: key.to_string(),
+            start,
+            end,
+        };
+        assert_eq!(
+            range("str", -5, -1).execute(&backend),
+            BulkString::new("World").into()
+        );
+        assert_eq!(
+            range("str", 0, 100).execute(&backend),
+            BulkString::new("Hello World").into()
+        );
+        assert_eq!(
+            range("str", 20, 30).execute(&backend),
+            BulkString::new("").into()
+        );
+        assert_eq!(
+            range("str", 5, 2).execute(&backend),
+            BulkString::new("").into()
+        );
+        assert_eq!(
+            range("missing", 0, -1).execute(&backend),
+            BulkString::new("").into()
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_setrange() -> Result<()> {
+        let mut buf =
+            BytesMut::from("*4\r\n$8\r\nsetrange\r\n$3\r\nkey\r\n$1\r\n6\r\n$5\r\nRedis\r\n");
+        let frame = RespArray::decode(&mut buf)?;
+        let cmd: SetRange = frame.try_into()?;
+
+        let backend = Backend::new();
+        backend.set("key".to_string(), BulkString::new("Hello World").into());
+        assert_eq!(cmd.execute(&backend), RespFrame::Integer(11));
+        assert_eq!(
+            backend.get("key"),
+            Some(BulkString::new("Hello Redis").into())
+        );
+
+        let cmd = SetRange {
+            key: "padded".to_string(),
+            offset: 2,
+            value: b"ab".to_vec(),
+        };
+        assert_eq!(cmd.execute(&backend), RespFrame::Integer(4));
+        assert_eq!(
+            backend.get("padded"),
+            Some(BulkString::new(b"\0\0ab".to_vec()).into())
+        );
+
+        let cmd = SetRange {
+            key: "missing".to_string(),
+            offset: 5,
+            value: vec![],
+        };
+        assert_eq!(cmd.execute(&backend), RespFrame::Integer(0));
+        assert!(backend.get("missing").is_none());
+        Ok(())
+    }
+
+    #[test]
+    fn test_setrange_offset_out_of_range() -> Result<()> {
+        let mut buf = BytesMut::from(
+            "*4\r\n$8\r\nsetrange\r\n$1\r\nk\r\n$20\r\n18446744073709551615\r\n$2\r\nab\r\n",
+        );
+        let frame = RespArray::decode(&mut buf)?;
+        let err = SetRange::try_from(frame).unwrap_err();
+        assert_eq!(err.to_string(), "Invalid argument: offset is out of range");
+
+        // 绕过解析直接构造, 偏移加长度溢出也不能 panic
+        let backend = Backend::new();
+        backend.set("k".to_string(), BulkString::new("value").into());
+        let too_long: RespFrame =
+            SimpleError::new("ERR string exceeds maximum allowed size").into();
+        for key in ["k", "missing"] {
+            let cmd = SetRange {
+                key: key.to_string(),
+                offset: usize::MAX,
+                value: b"ab".to_vec(),
+            };
+            assert_eq!(cmd.execute(&backend), too_long);
+        }
+        assert_eq!(backend.get("k"), Some(BulkString::new("value").into()));
+        assert!(!backend.exists("missing"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_incr_decr() -> Result<()> {
+        let mut buf = BytesMut::from("*2\r\n$4\r\nincr\r\n$3\r\nkey\r\n");
+        let frame = RespArray::decode(&mut buf)?;
+        let incr: Incr = frame.try_into()?;
+        let mut buf = BytesMut::from("*2\r\n$4\r\nDECR\r\n$3\r\nkey\r\n");
+        let frame = RespArray::decode(&mut buf)?;
+        let decr: Decr = frame.try_into()?;
+
+        let backend = Backend::new();
+        assert_eq!(incr.execute(&backend), RespFrame::Integer(1));
+        backend.set("key".to_string(), BulkString::new("10").into());
+        assert_eq!(decr.execute(&backend), RespFrame::Integer(9));
+        assert_eq!(backend.get("key"), Some(BulkString::new("9").into()));
+
+        backend.set("key".to_string(), BulkString::new("ten").into());
+        let incr = Incr {
+            key: "key".to_string(),
+        };
+        assert_eq!(
+            incr.execute(&backend),
+            SimpleError::new("ERR value is not an integer or out of range").into()
+        );
+
+        backend.set("key".to_string(), RespFrame::Integer(i64::MAX));
+        let incr = Incr {
+            key: "key".to_string(),
+        };
+        assert_eq!(
+            incr.execute(&backend),
+            SimpleError::new("ERR increment or decrement would overflow").into()
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_incrby_decrby() -> Result<()> {
+        let mut buf = BytesMut::from("*3\r\n$6\r\nincrby\r\n$3\r\nkey\r\n$2\r\n-5\r\n");
+        let frame = RespArray::decode(&mut buf)?;
+        let incrby: IncrBy = frame.try_into()?;
+        assert_eq!(incrby.delta, -5);
+
+        let backend = Backend::new();
+        assert_eq!(incrby.execute(&backend), RespFrame::Integer(-5));
+        let get = Get {
+            key: "key".to_string(),
+        };
+        assert_eq!(get.execute(&backend), BulkString::new("-5").into());
+
+        let mut buf = BytesMut::from("*3\r\n$6\r\ndecrby\r\n$3\r\nkey\r\n$2\r\n10\r\n");
+        let frame = RespArray::decode(&mut buf)?;
+        let decrby: DecrBy = frame.try_into()?;
+        assert_eq!(decrby.execute(&backend), RespFrame::Integer(-15));
+
+        let decrby = DecrBy {
+            key: "key".to_string(),
+            delta: i64::MAX,
+        };
+        assert_eq!(
+            decrby.execute(&backend),
+            SimpleError::new("ERR increment or decrement would overflow").into()
+        );
+        let decrby = DecrBy {
+            key: "key".to_string(),
+            delta: i64::MIN,
+        };
+        assert_eq!(
+            decrby.execute(&backend),
+            SimpleError::new("ERR decrement would overflow").into()
+        );
+
+        let mut buf = BytesMut::from("*3\r\n$6\r\nincrby\r\n$3\r\nkey\r\n$3\r\none\r\n");
+        let frame = RespArray::decode(&mut buf)?;
+        assert!(IncrBy::try_from(frame).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_incr_past_i64_with_wide_integers() {
+        let backend = Backend::with_config(crate::ServerConfig {
+            wide_integers: true,
+            ..Default::default()
+        });
+        let incr_by = |delta: i64| {
+            IncrBy {
+                key: "key".to_string(),
+                delta,
+            }
+            .execute(&backend)
+        };
+
+        backend.set("key".to_string(), RespFrame::Integer(i64::MAX - 1));
+        assert_eq!(incr_by(1), RespFrame::Integer(i64::MAX));
+        let big = incr_by(1);
+        assert_eq!(big, BigNumber::from(9223372036854775808i128).into());
+        assert_eq!(big.clone().encode(), b"(9223372036854775808\r\n");
+        assert_eq!(big.into_resp2().encode(), b"$19\r\n9223372036854775808\r\n");
+        assert_eq!(
+            backend.get("key"),
+            Some(BulkString::new("9223372036854775808").into())
+        );
+        assert_eq!(incr_by(-1), RespFrame::Integer(i64::MAX));
+
+        backend.set("key".to_string(), RespFrame::Integer(i64::MIN));
+        assert_eq!(
+            incr_by(-1),
+            BigNumber::from(-9223372036854775809i128).into()
+        );
+        assert_eq!(incr_by(1), RespFrame::Integer(i64::MIN));
+
+        backend.set(
+            "key".to_string(),
+            BulkString::new(i128::MAX.to_string()).into(),
+        );
+        assert_eq!(
+            incr_by(1),
+            SimpleError::new("ERR increment or decrement would overflow").into()
+        );
+
+        // 默认关闭时, 超出 i64 的值不算整数
+        let backend = Backend::new();
+        backend.set(
+            "key".to_string(),
+            BulkString::new("9223372036854775808").into(),
+        );
+        let incr = Incr {
+            key: "key".to_string(),
+        };
+        assert_eq!(
+            incr.execute(&backend),
+            SimpleError::new("ERR value is not an integer or out of range").into()
+        );
+    }
+
+    #[test]
+    fn test_concurrent_incr() {
+        let backend = Backend::new();
+        let handles: Vec<_> = (0..4)
+            .map(|_| {
+                let backend = backend.clone();
+                std::thread::spawn(move || {
+                    for _ in 0..1000 {
+                        Incr {
+                            key: "counter".to_string(),
+                        }
+                        .execute(&backend);
+                    }
+                })
+            })
+            .collect();
+        for handle in handles {
+            handle.join().unwrap();
+        }
+        assert_eq!(backend.get("counter"), Some(BulkString::new("4000").into()));
+    }
+
+    #[test]
+    fn test_concurrent_set_nx() {
+        let backend = Backend::new();
+        let handles: Vec<_> = (0..8)
+            .map(|i| {
+                let backend = backend.clone();
+                std::thread::spawn(move || {
+                    (0..200)
+                        .filter(|round| {
+                            Set {
+                                key: format!("key{}", round),
+                                value: RespFrame::Integer(i),
+                                expire: None,
+                                nx: true,
+                                xx: false,
+                                get: false,
+                                keepttl: false,
+                            }
+                            .execute(&backend)
+                                == RESP_OK.clone()
+                        })
+                        .count()
+                })
+            })
+            .collect();
+        let written: usize = handles.into_iter().map(|h| h.join().unwrap()).sum();
+        // 每个 key 只有一个连接能写进去
+        assert_eq!(written, 200);
+        assert_eq!(backend.dbsize(), 200);
+    }
+
+    #[test]
+    fn test_concurrent_set_get() {
+        let backend = Backend::new();
+        let handles: Vec<_> = (0..4)
+            .map(|i| {
+                let backend = backend.clone();
+                std::thread::spawn(move || {
+                    (0..500)
+                        .map(|round| {
+                            Set {
+                                key: "key".to_string(),
+                                value: RespFrame::Integer(i * 1000 + round),
+                                expire: None,
+                                nx: false,
+                                xx: false,
+                                get: true,
+                                keepttl: false,
+                            }
+                            .execute(&backend)
+                        })
+                        .collect::<Vec<_>>()
+                })
+            })
+            .collect();
+        let mut seen: Vec<RespFrame> = handles
+            .into_iter()
+            .flat_map(|h| h.join().unwrap())
+            .filter(|old| *old != RespFrame::Null(RespNull))
+            .collect();
+        seen.extend(backend.get("key"));
+        // 每个写入的值恰好被下一个 SET GET 读到一次, 最后一个留在 key 里
+        let mut seen: Vec<i64> = seen
+            .into_iter()
+            .map(|frame| match frame {
+                RespFrame::Integer(n) => n,
+                frame => panic!("unexpected {:?}", frame),
+            })
+            .collect();
+        seen.sort();
+        let mut written: Vec<i64> = (0..4)
+            .flat_map(|i| (0..500).map(move |round| i * 1000 + round))
+            .collect();
+        written.sort();
+        assert_eq!(seen, written);
+    }
+
+    #[test]
+    fn test_concurrent_getdel() {
+        let backend = Backend::new();
+        for i in 0..500 {
+            backend.set(format!("key{}", i), RespFrame::Integer(i));
+        }
+        let handles: Vec<_> = (0..4)
+            .map(|_| {
+                let backend = backend.clone();
+                std::thread::spawn(move || {
+                    (0..500)
+                        .filter(|i| {
+                            GetDel {
+                                key: format!("key{}", i),
+                            }
+                            .execute(&backend)
+                                != RespFrame::Null(RespNull)
+                        })
+                        .count()
+                })
+            })
+            .collect();
+        let taken: usize = handles.into_iter().map(|h| h.join().unwrap()).sum();
+        // 每个值只会被一个 GETDEL 拿到
+        assert_eq!(taken, 500);
+        assert_eq!(backend.dbsize(), 0);
+    }
+
+    #[test]
+    fn test_string_length_cap() {
+        let backend = Backend::with_config(crate::ServerConfig {
+            proto_max_bulk_len: 16,
+            ..Default::default()
+        });
+        let too_long: RespFrame =
+            SimpleError::new("ERR string exceeds maximum allowed size").into();
+
+        let setrange = SetRange {
+            key: "key".to_string(),
+            offset: 15,
+            value: b"ab".to_vec(),
+        };
+        assert_eq!(setrange.execute(&backend), too_long);
+        assert!(backend.get("key").is_none());
+
+        let append = |value: &[u8]| {
+            Append {
+                key: "key".to_string(),
+                value: value.to_vec(),
+            }
+            .execute(&backend)
+        };
+        assert_eq!(append(b"0123456789"), RespFrame::Integer(10));
+        assert_eq!(append(b"abcdef"), RespFrame::Integer(16));
+        assert_eq!(append(b"g"), too_long);
+        assert_eq!(
+            backend.get("key"),
+            Some(BulkString::new("0123456789abcdef").into())
+        );
+    }
 }
@@ -1,12 +1,31 @@
+mod admin;
+mod args;
+mod bitops;
+mod connection;
+mod glob;
 mod hmap;
+mod keyspace;
+mod list;
 mod map;
 mod new_cmd;
+mod registry;
+mod set;
 
-use crate::{Backend, RespArray, RespError, RespFrame, SimpleString};
+use std::{
+    collections::HashSet,
+    time::{Duration, Instant},
+};
+
+use crate::{
+    Backend, KeyKind, RespArray, RespError, RespFrame, RespVersion, SimpleError, SimpleString,
+    UnknownCommandMode,
+};
 use enum_dispatch::enum_dispatch;
 use lazy_static::lazy_static;
 use thiserror::Error;
 
+pub use registry::{CommandRegistry, CommandSpec, BUILTIN_COMMANDS};
+
 lazy_static! {
     static ref RESP_OK: RespFrame = SimpleString::new("OK").into();
 }
@@ -21,23 +40,110 @@ pub enum CommandError {
     RespError(#[from] RespError),
     #[error("Utf8 error: {0}")]
     Utf8Error(#[from] std::string::FromUtf8Error),
+    #[error("WRONGTYPE Operation against a key holding the wrong kind of value")]
+    WrongType,
+    #[error("reached the configured maximum number of keys")]
+    KeyLimit,
+}
+
+impl From<CommandError> for RespFrame {
+    fn from(e: CommandError) -> Self {
+        match e {
+            CommandError::WrongType => SimpleError::new(e.to_string()).into(),
+            e => SimpleError::new(format!("ERR {}", e)).into(),
+        }
+    }
 }
 
 #[enum_dispatch]
 pub trait CommandExecutor {
     fn execute(self, backend: &Backend) -> RespFrame;
+
+    /// Execute on behalf of a connection. Only connection-scoped commands (HELLO ...) look at
+    /// or update the session, everything else just runs against the backend.
+    fn execute_in(self, backend: &Backend, _session: &mut Session) -> RespFrame
+    where
+        Self: Sized,
+    {
+        self.execute(backend)
+    }
+}
+
+/// Per-connection state kept by the network layer across commands
+#[derive(Debug, Default)]
+pub struct Session {
+    /// the connection's client id, 0 when running outside a connection
+    pub id: u64,
+    pub version: RespVersion,
 }
 
 #[enum_dispatch(CommandExecutor)]
 #[derive(Debug)]
 pub enum Command {
     Get(Get),
+    MGet(MGet),
     Set(Set),
+    MSet(MSet),
+    GetSet(GetSet),
+    GetDel(GetDel),
+    GetEx(GetEx),
+    SetNx(SetNx),
     HGet(HGet),
     HMGet(HMGet),
     HSet(HSet),
+    HMSet(HMSet),
     HGetAll(HGetAll),
+    HLen(HLen),
+    HDel(HDel),
+    HExists(HExists),
+    HKeys(HKeys),
+    HVals(HVals),
+    HIncrBy(HIncrBy),
+    LPush(LPush),
+    RPush(RPush),
+    LRange(LRange),
+    LLen(LLen),
+    LPop(LPop),
+    RPop(RPop),
+    LIndex(LIndex),
+    LSet(LSet),
+    SAdd(SAdd),
+    SRem(SRem),
+    SMembers(SMembers),
+    SIsMember(SIsMember),
+    SCard(SCard),
+    SInter(SInter),
+    SUnion(SUnion),
+    SDiff(SDiff),
+    SInterCard(SInterCard),
+    Append(Append),
+    GetRange(GetRange),
+    Strlen(Strlen),
+    SetRange(SetRange),
+    Incr(Incr),
+    Decr(Decr),
+    IncrBy(IncrBy),
+    DecrBy(DecrBy),
     Echo(Echo),
+    FlushAll(FlushAll),
+    FlushDb(FlushDb),
+    Scan(Scan),
+    Keys(Keys),
+    Del(Del),
+    Exists(Exists),
+    Expire(Expire),
+    Rename(Rename),
+    Ttl(Ttl),
+    Type(Type),
+    DbSize(DbSize),
+    Debug(DebugCommand),
+    BitField(BitField),
+    Memory(MemoryCommand),
+    Object(ObjectCommand),
+    Hello(Hello),
+    Ping(Ping),
+    Client(ClientCommand),
+    CommandMeta(CommandMeta),
 
     Unrecognized(Unrecognized),
 }
@@ -48,7 +154,171 @@ pub struct Echo {
 }
 
 #[derive(Debug)]
-pub struct Unrecognized;
+pub struct Unrecognized {
+    pub name: String,
+    pub args: Vec<String>,
+}
+
+/// COMMAND introspection, `Info` with no names lists every command
+#[derive(Debug)]
+pub enum CommandMeta {
+    Count,
+    /// redis-cli probes this on startup, we have no docs to hand out
+    Docs,
+    /// a full command line, the command name first
+    GetKeysAndFlags(Vec<String>),
+    Info(Vec<String>),
+}
+
+#[derive(Debug)]
+pub enum ClientCommand {
+    Id,
+    List,
+    /// the filters to match, `legacy` is the old `CLIENT KILL addr` form replying `+OK`
+    Kill {
+        filter: ClientFilter,
+        legacy: bool,
+    },
+}
+
+#[derive(Debug)]
+pub enum ClientFilter {
+    Id(u64),
+    Addr(String),
+}
+
+#[derive(Debug)]
+pub struct Ping {
+    pub message: Option<String>,
+}
+
+#[derive(Debug)]
+pub struct Hello {
+    pub protover: Option<i64>,
+}
+
+#[derive(Debug)]
+pub struct FlushAll;
+
+#[derive(Debug)]
+pub struct FlushDb;
+
+#[derive(Debug)]
+pub struct DbSize;
+
+#[derive(Debug)]
+pub struct Expire {
+    pub key: String,
+    pub secs: i64,
+}
+
+#[derive(Debug)]
+pub struct Keys {
+    pub pattern: String,
+    /// the non-standard `TYPE t` filter, served from the type index when it is on
+    pub kind: Option<KeyKind>,
+}
+
+#[derive(Debug)]
+pub struct Ttl {
+    pub key: String,
+}
+
+#[derive(Debug)]
+pub struct Type {
+    pub key: String,
+}
+
+#[derive(Debug)]
+pub struct Exists {
+    pub keys: Vec<String>,
+}
+
+/// RENAME, `expire` is the non-standard `EX seconds` extension applied to `to`
+#[derive(Debug)]
+pub struct Rename {
+    pub from: String,
+    pub to: String,
+    pub expire: Option<Duration>,
+}
+
+#[derive(Debug)]
+pub struct Del {
+    pub keys: Vec<String>,
+}
+
+#[derive(Debug)]
+pub struct Scan {
+    pub cursor: u64,
+    /// how many keys to return per call, at most
+    pub count: usize,
+    /// only return keys holding this type
+    pub kind: Option<KeyKind>,
+}
+
+#[derive(Debug)]
+pub enum DebugCommand {
+    Sleep(Duration),
+    Protocol(String),
+    Object(String),
+    Populate {
+        count: u64,
+        prefix: String,
+        size: Option<usize>,
+    },
+    // lets tests exercise the network layer's panic boundary
+    #[cfg(test)]
+    Panic,
+}
+
+#[derive(Debug)]
+pub enum MemoryCommand {
+    Doctor,
+    Stats,
+}
+
+#[derive(Debug)]
+pub enum ObjectCommand {
+    Encoding(String),
+}
+
+#[derive(Debug)]
+pub struct BitField {
+    pub key: String,
+    pub ops: Vec<BitFieldOp>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BitFieldType {
+    pub signed: bool,
+    pub bits: u32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BitFieldOverflow {
+    Wrap,
+    Sat,
+    Fail,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum BitFieldOp {
+    Get {
+        ty: BitFieldType,
+        offset: u64,
+    },
+    Set {
+        ty: BitFieldType,
+        offset: u64,
+        value: i64,
+    },
+    IncrBy {
+        ty: BitFieldType,
+        offset: u64,
+        increment: i64,
+    },
+    Overflow(BitFieldOverflow),
+}
 
 #[derive(Debug)]
 pub struct Get {
@@ -59,6 +329,15 @@ pub struct Get {
 pub struct Set {
     pub key: String,
     pub value: RespFrame,
+    pub expire: Option<Duration>,
+    /// only set if the key doesn't exist yet
+    pub nx: bool,
+    /// only set if the key already exists
+    pub xx: bool,
+    /// reply with the previous value instead of OK
+    pub get: bool,
+    /// keep the key's current ttl instead of clearing it
+    pub keepttl: bool,
 }
 
 #[derive(Debug)]
@@ -85,6 +364,227 @@ pub struct HGetAll {
     pub key: String,
 }
 
+#[derive(Debug)]
+pub struct Append {
+    pub key: String,
+    pub value: Vec<u8>,
+}
+
+#[derive(Debug)]
+pub struct GetRange {
+    pub key: String,
+    pub start: i64,
+    pub end: i64,
+}
+
+#[derive(Debug)]
+pub struct Strlen {
+    pub key: String,
+}
+
+#[derive(Debug)]
+pub struct SetRange {
+    pub key: String,
+    pub offset: usize,
+    pub value: Vec<u8>,
+}
+
+#[derive(Debug)]
+pub struct MGet {
+    pub keys: Vec<String>,
+}
+
+#[derive(Debug)]
+pub struct MSet {
+    pub pairs: Vec<(String, RespFrame)>,
+}
+
+#[derive(Debug)]
+pub struct SetNx {
+    pub key: String,
+    pub value: RespFrame,
+}
+
+#[derive(Debug)]
+pub struct GetSet {
+    pub key: String,
+    pub value: RespFrame,
+}
+
+#[derive(Debug)]
+pub struct GetDel {
+    pub key: String,
+}
+
+/// GETEX, `expire` sets a new TTL and `persist` drops the current one
+#[derive(Debug)]
+pub struct GetEx {
+    pub key: String,
+    pub expire: Option<Duration>,
+    pub persist: bool,
+}
+
+#[derive(Debug)]
+pub struct Incr {
+    pub key: String,
+}
+
+#[derive(Debug)]
+pub struct Decr {
+    pub key: String,
+}
+
+#[derive(Debug)]
+pub struct IncrBy {
+    pub key: String,
+    pub delta: i64,
+}
+
+#[derive(Debug)]
+pub struct DecrBy {
+    pub key: String,
+    pub delta: i64,
+}
+
+#[derive(Debug)]
+pub struct HLen {
+    pub key: String,
+}
+
+#[derive(Debug)]
+pub struct HMSet {
+    pub key: String,
+    pub pairs: Vec<(String, RespFrame)>,
+}
+
+#[derive(Debug)]
+pub struct HExists {
+    pub key: String,
+    pub field: String,
+}
+
+#[derive(Debug)]
+pub struct HKeys {
+    pub key: String,
+}
+
+#[derive(Debug)]
+pub struct HVals {
+    pub key: String,
+}
+
+#[derive(Debug)]
+pub struct HIncrBy {
+    pub key: String,
+    pub field: String,
+    pub delta: i64,
+}
+
+#[derive(Debug)]
+pub struct HDel {
+    pub key: String,
+    pub fields: Vec<String>,
+}
+
+#[derive(Debug)]
+pub struct LPush {
+    pub key: String,
+    pub values: Vec<RespFrame>,
+}
+
+#[derive(Debug)]
+pub struct RPush {
+    pub key: String,
+    pub values: Vec<RespFrame>,
+}
+
+#[derive(Debug)]
+pub struct LRange {
+    pub key: String,
+    pub start: i64,
+    pub stop: i64,
+}
+
+#[derive(Debug)]
+pub struct LLen {
+    pub key: String,
+}
+
+#[derive(Debug)]
+pub struct LPop {
+    pub key: String,
+    pub count: Option<usize>,
+}
+
+#[derive(Debug)]
+pub struct RPop {
+    pub key: String,
+    pub count: Option<usize>,
+}
+
+#[derive(Debug)]
+pub struct LIndex {
+    pub key: String,
+    pub index: i64,
+}
+
+#[derive(Debug)]
+pub struct LSet {
+    pub key: String,
+    pub index: i64,
+    pub value: RespFrame,
+}
+
+#[derive(Debug)]
+pub struct SAdd {
+    pub key: String,
+    pub members: Vec<Vec<u8>>,
+}
+
+#[derive(Debug)]
+pub struct SRem {
+    pub key: String,
+    pub members: Vec<Vec<u8>>,
+}
+
+#[derive(Debug)]
+pub struct SMembers {
+    pub key: String,
+}
+
+#[derive(Debug)]
+pub struct SIsMember {
+    pub key: String,
+    pub member: Vec<u8>,
+}
+
+#[derive(Debug)]
+pub struct SCard {
+    pub key: String,
+}
+
+#[derive(Debug)]
+pub struct SInter {
+    pub keys: Vec<String>,
+}
+
+#[derive(Debug)]
+pub struct SUnion {
+    pub keys: Vec<String>,
+}
+
+#[derive(Debug)]
+pub struct SDiff {
+    pub keys: Vec<String>,
+}
+
+#[derive(Debug)]
+pub struct SInterCard {
+    pub keys: Vec<String>,
+    /// stop counting once this many members matched, 0 means no limit
+    pub limit: usize,
+}
+
 impl TryFrom<RespFrame> for Command {
     type Error = CommandError;
     fn try_from(value: RespFrame) -> Result<Self, Self::Error> {
@@ -101,16 +601,75 @@ impl TryFrom<RespArray> for Command {
     type Error = CommandError;
     fn try_from(value: RespArray) -> Result<Self, Self::Error> {
         match value.as_ref().unwrap().first() {
-            Some(RespFrame::BulkString(ref cmd)) => match cmd.as_ref() {
-                b"get" => Ok(Command::Get(Get::try_from(value)?)),
-                b"set" => Ok(Command::Set(Set::try_from(value)?)),
-                b"hget" => Ok(Command::HGet(HGet::try_from(value)?)),
-                b"hset" => Ok(Command::HSet(HSet::try_from(value)?)),
-                b"hgetall" => Ok(Command::HGetAll(HGetAll::try_from(value)?)),
-                b"echo" => Ok(Command::Echo(Echo::try_from(value)?)),
-                b"hmget" => Ok(Command::HMGet(HMGet::try_from(value)?)),
-                _ => Ok(Unrecognized.into()),
-            },
+            Some(RespFrame::BulkString(ref cmd)) => {
+                match cmd.as_ref().to_ascii_lowercase().as_slice() {
+                    b"get" => Ok(Command::Get(Get::try_from(value)?)),
+                    b"mget" => Ok(Command::MGet(MGet::try_from(value)?)),
+                    b"set" => Ok(Command::Set(Set::try_from(value)?)),
+                    b"mset" => Ok(Command::MSet(MSet::try_from(value)?)),
+                    b"getset" => Ok(Command::GetSet(GetSet::try_from(value)?)),
+                    b"getdel" => Ok(Command::GetDel(GetDel::try_from(value)?)),
+                    b"getex" => Ok(Command::GetEx(GetEx::try_from(value)?)),
+                    b"setnx" => Ok(Command::SetNx(SetNx::try_from(value)?)),
+                    b"hget" => Ok(Command::HGet(HGet::try_from(value)?)),
+                    b"hset" => Ok(Command::HSet(HSet::try_from(value)?)),
+                    b"hmset" => Ok(Command::HMSet(HMSet::try_from(value)?)),
+                    b"hgetall" => Ok(Command::HGetAll(HGetAll::try_from(value)?)),
+                    b"hlen" => Ok(Command::HLen(HLen::try_from(value)?)),
+                    b"hdel" => Ok(Command::HDel(HDel::try_from(value)?)),
+                    b"hexists" => Ok(Command::HExists(HExists::try_from(value)?)),
+                    b"hkeys" => Ok(Command::HKeys(HKeys::try_from(value)?)),
+                    b"hvals" => Ok(Command::HVals(HVals::try_from(value)?)),
+                    b"hincrby" => Ok(Command::HIncrBy(HIncrBy::try_from(value)?)),
+                    b"lpush" => Ok(Command::LPush(LPush::try_from(value)?)),
+                    b"rpush" => Ok(Command::RPush(RPush::try_from(value)?)),
+                    b"lrange" => Ok(Command::LRange(LRange::try_from(value)?)),
+                    b"llen" => Ok(Command::LLen(LLen::try_from(value)?)),
+                    b"lpop" => Ok(Command::LPop(LPop::try_from(value)?)),
+                    b"rpop" => Ok(Command::RPop(RPop::try_from(value)?)),
+                    b"lindex" => Ok(Command::LIndex(LIndex::try_from(value)?)),
+                    b"lset" => Ok(Command::LSet(LSet::try_from(value)?)),
+                    b"sadd" => Ok(Command::SAdd(SAdd::try_from(value)?)),
+                    b"srem" => Ok(Command::SRem(SRem::try_from(value)?)),
+                    b"smembers" => Ok(Command::SMembers(SMembers::try_from(value)?)),
+                    b"sismember" => Ok(Command::SIsMember(SIsMember::try_from(value)?)),
+                    b"scard" => Ok(Command::SCard(SCard::try_from(value)?)),
+                    b"sinter" => Ok(Command::SInter(SInter::try_from(value)?)),
+                    b"sunion" => Ok(Command::SUnion(SUnion::try_from(value)?)),
+                    b"sdiff" => Ok(Command::SDiff(SDiff::try_from(value)?)),
+                    b"sintercard" => Ok(Command::SInterCard(SInterCard::try_from(value)?)),
+                    b"append" => Ok(Command::Append(Append::try_from(value)?)),
+                    b"getrange" => Ok(Command::GetRange(GetRange::try_from(value)?)),
+                    b"strlen" => Ok(Command::Strlen(Strlen::try_from(value)?)),
+                    b"setrange" => Ok(Command::SetRange(SetRange::try_from(value)?)),
+                    b"incr" => Ok(Command::Incr(Incr::try_from(value)?)),
+                    b"decr" => Ok(Command::Decr(Decr::try_from(value)?)),
+                    b"incrby" => Ok(Command::IncrBy(IncrBy::try_from(value)?)),
+                    b"decrby" => Ok(Command::DecrBy(DecrBy::try_from(value)?)),
+                    b"echo" => Ok(Command::Echo(Echo::try_from(value)?)),
+                    b"hmget" => Ok(Command::HMGet(HMGet::try_from(value)?)),
+                    b"flushall" => Ok(Command::FlushAll(FlushAll::try_from(value)?)),
+                    b"flushdb" => Ok(Command::FlushDb(FlushDb::try_from(value)?)),
+                    b"scan" => Ok(Command::Scan(Scan::try_from(value)?)),
+                    b"keys" => Ok(Command::Keys(Keys::try_from(value)?)),
+                    b"del" | b"unlink" => Ok(Command::Del(Del::try_from(value)?)),
+                    b"exists" => Ok(Command::Exists(Exists::try_from(value)?)),
+                    b"expire" => Ok(Command::Expire(Expire::try_from(value)?)),
+                    b"rename" => Ok(Command::Rename(Rename::try_from(value)?)),
+                    b"ttl" => Ok(Command::Ttl(Ttl::try_from(value)?)),
+                    b"type" => Ok(Command::Type(Type::try_from(value)?)),
+                    b"dbsize" => Ok(Command::DbSize(DbSize::try_from(value)?)),
+                    b"debug" => Ok(Command::Debug(DebugCommand::try_from(value)?)),
+                    b"bitfield" => Ok(Command::BitField(BitField::try_from(value)?)),
+                    b"memory" => Ok(Command::Memory(MemoryCommand::try_from(value)?)),
+                    b"object" => Ok(Command::Object(ObjectCommand::try_from(value)?)),
+                    b"hello" => Ok(Command::Hello(Hello::try_from(value)?)),
+                    b"ping" => Ok(Command::Ping(Ping::try_from(value)?)),
+                    b"client" => Ok(Command::Client(ClientCommand::try_from(value)?)),
+                    b"command" => Ok(Command::CommandMeta(CommandMeta::try_from(value)?)),
+                    _ => Ok(Unrecognized::from(value).into()),
+                }
+            }
             _ => Err(CommandError::InvalidCommand(
                 "command must have a BulkString as the first argument".to_string(),
             )),
@@ -118,9 +677,91 @@ impl TryFrom<RespArray> for Command {
     }
 }
 
+impl Command {
+    /// Commands that never modify the keyspace, so abandoning them half way is harmless
+    pub fn is_read_only(&self) -> bool {
+        matches!(
+            self,
+            Command::Get(_)
+                | Command::MGet(_)
+                | Command::GetRange(_)
+                | Command::Strlen(_)
+                | Command::HGet(_)
+                | Command::HMGet(_)
+                | Command::HGetAll(_)
+                | Command::HLen(_)
+                | Command::HExists(_)
+                | Command::HKeys(_)
+                | Command::HVals(_)
+                | Command::LRange(_)
+                | Command::LLen(_)
+                | Command::LIndex(_)
+                | Command::SMembers(_)
+                | Command::SIsMember(_)
+                | Command::SCard(_)
+                | Command::SInter(_)
+                | Command::SUnion(_)
+                | Command::SDiff(_)
+                | Command::SInterCard(_)
+                | Command::Echo(_)
+                | Command::Ping(_)
+                | Command::Debug(
+                    DebugCommand::Sleep(_) | DebugCommand::Protocol(_) | DebugCommand::Object(_)
+                )
+                | Command::Memory(_)
+                | Command::Object(_)
+                | Command::Scan(_)
+                | Command::Keys(_)
+                | Command::DbSize(_)
+                | Command::Exists(_)
+                | Command::Ttl(_)
+                | Command::Type(_)
+                | Command::CommandMeta(_)
+        )
+    }
+
+    /// Commands that can keep a thread busy for long, the network layer runs them on the
+    /// blocking pool instead of an async worker
+    pub fn is_blocking(&self) -> bool {
+        matches!(
+            self,
+            Command::Debug(DebugCommand::Populate { .. } | DebugCommand::Sleep(_))
+        )
+    }
+}
+
 impl CommandExecutor for Unrecognized {
-    fn execute(self, _backend: &Backend) -> RespFrame {
-        RESP_OK.clone()
+    fn execute(self, backend: &Backend) -> RespFrame {
+        match backend.config.unknown_command_mode {
+            UnknownCommandMode::Ok => RESP_OK.clone(),
+            UnknownCommandMode::Error => {
+                let args: String = self.args.iter().map(|arg| format!("'{}' ", arg)).collect();
+                SimpleError::new(format!(
+                    "ERR unknown command '{}', with args beginning with: {}",
+                    self.name, args
+                ))
+                .into()
+            }
+        }
+    }
+}
+
+impl From<RespArray> for Unrecognized {
+    fn from(value: RespArray) -> Self {
+        let mut args = value
+            .0
+            .unwrap_or_default()
+            .into_iter()
+            .map(|arg| match arg {
+                RespFrame::BulkString(s) => {
+                    String::from_utf8_lossy(s.0.as_deref().unwrap_or_default()).into_owned()
+                }
+                _ => String::new(),
+            });
+        Unrecognized {
+            name: args.next().unwrap_or_default(),
+            args: args.collect(),
+        }
     }
 }
 
@@ -158,8 +799,76 @@ fn validate_command(
     Ok(())
 }
 
+// 多级命令(CLIENT, CONFIG, DEBUG, MEMORY 等)的子命令统一转成小写, 匹配时不区分大小写
+fn subcommand(args: &[RespFrame], idx: usize) -> Option<String> {
+    match args.get(idx) {
+        Some(RespFrame::BulkString(sub)) => sub
+            .0
+            .as_ref()
+            .map(|s| String::from_utf8_lossy(s).to_ascii_lowercase()),
+        _ => None,
+    }
+}
+
+// a key holding another kind of value is a type error, a missing key is not
+fn check_kind(backend: &Backend, key: &str, kind: KeyKind) -> Result<(), CommandError> {
+    match backend.key_kind(key) {
+        Some(k) if k != kind => Err(CommandError::WrongType),
+        _ => Ok(()),
+    }
+}
+
+/// A positive EX/PX style TTL, `amount` is in milliseconds when `millis` is set. Like redis a
+/// TTL whose deadline is out of range is rejected instead of overflowing.
+fn checked_ttl(amount: i64, millis: bool, name: &str) -> Result<Duration, CommandError> {
+    let ms = match millis {
+        true => Some(amount),
+        false => amount.checked_mul(1000),
+    };
+    let ttl = ms
+        .filter(|ms| *ms > 0)
+        .map(|ms| Duration::from_millis(ms as u64))
+        .filter(|ttl| Instant::now().checked_add(*ttl).is_some());
+    ttl.ok_or_else(|| {
+        CommandError::InvalidArgument(format!("invalid expire time in '{}' command", name))
+    })
+}
+
+/// Fail when writing `keys` would grow the keyspace past `maxkeys`. Must be called before taking
+/// an entry lock, counting the keys locks every shard.
+fn check_key_limit(backend: &Backend, keys: &[&str]) -> Result<(), CommandError> {
+    let Some(maxkeys) = backend.config.maxkeys else {
+        return Ok(());
+    };
+    let new_keys = keys
+        .iter()
+        .filter(|key| !backend.exists(key))
+        .collect::<HashSet<_>>()
+        .len();
+    match new_keys {
+        0 => Ok(()),
+        n if backend.dbsize() + n > maxkeys => Err(CommandError::KeyLimit),
+        _ => Ok(()),
+    }
+}
+
+/// The bytes of a string value, integers are coerced to their decimal form like redis does
+fn as_string_bytes(frame: &RespFrame) -> Result<Vec<u8>, CommandError> {
+    match frame {
+        RespFrame::BulkString(s) => Ok(s.0.clone().unwrap_or_default()),
+        RespFrame::SimpleString(s) => Ok(s.0.clone().into_bytes()),
+        RespFrame::Integer(i) => Ok(i.to_string().into_bytes()),
+        _ => Err(CommandError::WrongType),
+    }
+}
+
 fn extract_args(value: RespArray, start: usize) -> Result<Vec<RespFrame>, CommandError> {
-    Ok(value.0.unwrap().into_iter().skip(start).collect())
+    Ok(value
+        .0
+        .unwrap_or_default()
+        .into_iter()
+        .skip(start)
+        .collect())
 }
 
 #[cfg(test)]
@@ -183,4 +892,82 @@ mod tests {
         assert_eq!(ret, RespFrame::Null(RespNull));
         Ok(())
     }
+
+    #[test]
+    fn test_unknown_command_modes() -> anyhow::Result<()> {
+        let input = "*2\r\n$6\r\nfoobar\r\n$3\r\nbaz\r\n";
+
+        let frame = RespArray::decode(&mut BytesMut::from(input))?;
+        let cmd: Command = frame.try_into()?;
+        assert_eq!(
+            cmd.execute(&Backend::new()),
+            SimpleError::new("ERR unknown command 'foobar', with args beginning with: 'baz' ")
+                .into()
+        );
+
+        let backend = Backend::with_config(crate::ServerConfig {
+            unknown_command_mode: UnknownCommandMode::Ok,
+            ..Default::default()
+        });
+        let frame = RespArray::decode(&mut BytesMut::from(input))?;
+        let cmd: Command = frame.try_into()?;
+        assert_eq!(cmd.execute(&backend), RESP_OK.clone());
+        Ok(())
+    }
+
+    #[test]
+    fn test_subcommand_is_case_insensitive() -> anyhow::Result<()> {
+        let args = [
+            RespFrame::BulkString(b"Memory".into()),
+            RespFrame::BulkString(b"StAtS".into()),
+        ];
+        assert_eq!(subcommand(&args, 1), Some("stats".to_string()));
+        assert_eq!(subcommand(&args, 2), None);
+
+        for input in [
+            "*2\r\n$6\r\nMEMORY\r\n$5\r\nstats\r\n",
+            "*2\r\n$6\r\nmemory\r\n$5\r\nSTATS\r\n",
+        ] {
+            let frame = RespArray::decode(&mut BytesMut::from(input))?;
+            let cmd: Command = frame.try_into()?;
+            assert!(matches!(cmd, Command::Memory(MemoryCommand::Stats)));
+        }
+
+        for input in [
+            "*3\r\n$5\r\nDebug\r\n$5\r\nsleep\r\n$1\r\n0\r\n",
+            "*3\r\n$5\r\ndebug\r\n$5\r\nSlEeP\r\n$1\r\n0\r\n",
+        ] {
+            let frame = RespArray::decode(&mut BytesMut::from(input))?;
+            let cmd: Command = frame.try_into()?;
+            assert!(matches!(cmd, Command::Debug(DebugCommand::Sleep(_))));
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_command_error_to_frame() {
+        let frame: RespFrame = CommandError::InvalidCommand("foo".to_string()).into();
+        assert_eq!(frame, SimpleError::new("ERR Invalid command: foo").into());
+
+        let frame: RespFrame = CommandError::InvalidArgument("bar".to_string()).into();
+        assert_eq!(frame, SimpleError::new("ERR Invalid argument: bar").into());
+
+        let frame: RespFrame = CommandError::RespError(RespError::NotComplete).into();
+        assert_eq!(frame, SimpleError::new("ERR Frame not complete").into());
+
+        let err = String::from_utf8(vec![0xff]).unwrap_err();
+        let frame: RespFrame = CommandError::Utf8Error(err).into();
+        assert_eq!(
+            frame,
+            SimpleError::new("ERR Utf8 error: invalid utf-8 sequence of 1 bytes from index 0")
+                .into()
+        );
+
+        let frame: RespFrame = CommandError::WrongType.into();
+        assert_eq!(
+            frame,
+            SimpleError::new("WRONGTYPE Operation against a key holding the wrong kind of value")
+                .into()
+        );
+    }
 }
@@ -1,8 +1,8 @@
 use tracing::warn;
 
-use crate::{cmd::extract_args, RespArray, RespFrame};
+use crate::{cmd::extract_args, KeyKind, RespArray, RespFrame};
 
-use super::{validate_command, CommandError, CommandExecutor, Echo, HMGet};
+use super::{check_kind, validate_command, CommandError, CommandExecutor, Echo, HMGet};
 
 impl CommandExecutor for Echo {
     fn execute(self, _backend: &crate::Backend) -> crate::RespFrame {
@@ -18,7 +18,7 @@ impl TryFrom<RespArray> for Echo {
         let mut args = extract_args(value, 1)?.into_iter();
         match args.next() {
             Some(RespFrame::BulkString(message)) => Ok(Echo {
-                message: String::from_utf8(message.0.unwrap())?,
+                message: String::from_utf8(message.0.unwrap_or_default())?,
             }),
             _ => Err(CommandError::InvalidArgument("Invalid message".to_string())),
         }
@@ -27,15 +27,17 @@ impl TryFrom<RespArray> for Echo {
 
 impl CommandExecutor for HMGet {
     fn execute(self, backend: &crate::Backend) -> RespFrame {
+        if let Err(e) = check_kind(backend, &self.key, KeyKind::Hash) {
+            return e.into();
+        }
         let key = self.key.clone();
         let mut ret = vec![];
         for field in self.fields {
             if let Some(value) = backend.hget(&key, &field) {
                 ret.push(value);
             } else {
-                ret.push(RespFrame::SimpleString(crate::SimpleString(
-                    "(nil)".to_string(),
-                )));
+                // 缺失的 field 用 null bulk string 表示, 客户端才能区分出 nil
+                ret.push(crate::BulkString(None).into());
             }
         }
         RespArray::new(ret).into()
@@ -47,7 +49,7 @@ impl TryFrom<RespArray> for HMGet {
     fn try_from(value: RespArray) -> Result<Self, Self::Error> {
         let mut args = extract_args(value, 1)?.into_iter();
         let key = match args.next() {
-            Some(RespFrame::BulkString(key)) => String::from_utf8(key.0.unwrap())?,
+            Some(RespFrame::BulkString(key)) => String::from_utf8(key.0.unwrap_or_default())?,
             _ => {
                 warn!("Invalid key");
                 return Err(CommandError::InvalidArgument("Invalid key".to_string()));
@@ -55,8 +57,35 @@ impl TryFrom<RespArray> for HMGet {
         };
         let mut fields = vec![];
         while let Some(RespFrame::BulkString(field)) = args.next() {
-            fields.push(String::from_utf8(field.0.unwrap())?);
+            fields.push(String::from_utf8(field.0.unwrap_or_default())?);
         }
         Ok(HMGet { key, fields })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::{Backend, BulkString, RespEncode};
+
+    use super::*;
+
+    #[test]
+    fn test_hmget_missing_field_is_null() {
+        let backend = Backend::new();
+        backend.hset(
+            "hash".to_string(),
+            "a".to_string(),
+            BulkString::new("1").into(),
+        );
+        let hmget = HMGet {
+            key: "hash".to_string(),
+            fields: vec!["a".to_string(), "missing".to_string()],
+        };
+        let reply = hmget.execute(&backend);
+        assert_eq!(
+            reply,
+            RespArray::new([BulkString::new("1").into(), BulkString(None).into()]).into()
+        );
+        assert_eq!(reply.encode(), b"*2\r\n$1\r\n1\r\n$-1\r\n");
+    }
+}
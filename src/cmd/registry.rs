@@ -0,0 +1,439 @@
+use std::collections::BTreeMap;
+
+use lazy_static::lazy_static;
+
+use crate::{BulkString, RespArray, RespFrame, RespNull, SimpleError};
+
+use super::{extract_args, subcommand, CommandError, CommandExecutor, CommandMeta};
+
+lazy_static! {
+    pub static ref BUILTIN_COMMANDS: CommandRegistry = CommandRegistry::builtin();
+}
+
+/// What `COMMAND INFO` reports about a command, arity follows redis: negative means "at least"
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CommandSpec {
+    pub name: &'static str,
+    pub arity: i64,
+    pub flags: &'static [&'static str],
+    pub first_key: i64,
+    pub last_key: i64,
+    pub step: i64,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct CommandRegistry {
+    commands: BTreeMap<&'static str, CommandSpec>,
+}
+
+impl CommandSpec {
+    pub const fn new(name: &'static str, arity: i64, flags: &'static [&'static str]) -> Self {
+        Self {
+            name,
+            arity,
+            flags,
+            first_key: 0,
+            last_key: 0,
+            step: 0,
+        }
+    }
+
+    /// Commands whose only key is the first argument
+    pub const fn keyed(name: &'static str, arity: i64, flags: &'static [&'static str]) -> Self {
+        Self {
+            name,
+            arity,
+            flags,
+            first_key: 1,
+            last_key: 1,
+            step: 1,
+        }
+    }
+
+    /// Access flags of every key this command touches, like the key specs of redis 7
+    pub fn key_flags(&self) -> &'static [&'static str] {
+        match self.name {
+            "del" | "unlink" => &["RM", "delete"],
+            "getdel" => &["RW", "access", "delete"],
+            _ if self.flags.contains(&"write") => &["RW", "update"],
+            _ => &["RO", "access"],
+        }
+    }
+
+    /// Positions of the keys in `args`, which start with the command name
+    fn key_positions(&self, len: usize) -> impl Iterator<Item = usize> {
+        // 负的 last_key 从末尾数, 没有 key 的命令 first_key 是 0
+        let last = match self.last_key {
+            n if n < 0 => len as i64 + n,
+            n => n.min(len as i64 - 1),
+        };
+        let end = match self.first_key {
+            0 => 0,
+            _ => (last + 1).max(0) as usize,
+        };
+        (self.first_key as usize..end).step_by(self.step.max(1) as usize)
+    }
+
+    fn arity_matches(&self, len: usize) -> bool {
+        match self.arity {
+            n if n < 0 => len as i64 >= -n,
+            n => len as i64 == n,
+        }
+    }
+
+    fn info(&self) -> RespFrame {
+        let flags: Vec<RespFrame> = self
+            .flags
+            .iter()
+            .map(|f| BulkString::new(*f).into())
+            .collect();
+        RespArray::new(vec![
+            BulkString::new(self.name).into(),
+            RespFrame::Integer(self.arity),
+            RespArray::new(flags).into(),
+            RespFrame::Integer(self.first_key),
+            RespFrame::Integer(self.last_key),
+            RespFrame::Integer(self.step),
+        ])
+        .into()
+    }
+}
+
+impl CommandRegistry {
+    fn builtin() -> Self {
+        let mut registry = Self::default();
+        for spec in [
+            CommandSpec::keyed("get", 2, &["readonly", "fast"]),
+            CommandSpec {
+                last_key: -1,
+                ..CommandSpec::keyed("mget", -2, &["readonly", "fast"])
+            },
+            CommandSpec::keyed("set", -3, &["write"]),
+            CommandSpec {
+                last_key: -1,
+                step: 2,
+                ..CommandSpec::keyed("mset", -3, &["write"])
+            },
+            CommandSpec::keyed("getset", 3, &["write", "fast"]),
+            CommandSpec::keyed("getdel", 2, &["write", "fast"]),
+            CommandSpec::keyed("getex", -2, &["write", "fast"]),
+            CommandSpec::keyed("setnx", 3, &["write", "fast"]),
+            CommandSpec::keyed("append", 3, &["write"]),
+            CommandSpec::keyed("getrange", 4, &["readonly"]),
+            CommandSpec::keyed("strlen", 2, &["readonly", "fast"]),
+            CommandSpec::keyed("setrange", 4, &["write"]),
+            CommandSpec::keyed("incr", 2, &["write", "fast"]),
+            CommandSpec::keyed("decr", 2, &["write", "fast"]),
+            CommandSpec::keyed("incrby", 3, &["write", "fast"]),
+            CommandSpec::keyed("decrby", 3, &["write", "fast"]),
+            CommandSpec::keyed("hget", 3, &["readonly", "fast"]),
+            CommandSpec::keyed("hmget", -3, &["readonly", "fast"]),
+            CommandSpec::keyed("hset", 4, &["write"]),
+            CommandSpec::keyed("hmset", -4, &["write", "fast"]),
+            CommandSpec::keyed("hgetall", 2, &["readonly"]),
+            CommandSpec::keyed("hlen", 2, &["readonly", "fast"]),
+            CommandSpec::keyed("hdel", -3, &["write", "fast"]),
+            CommandSpec::keyed("hexists", 3, &["readonly", "fast"]),
+            CommandSpec::keyed("hkeys", 2, &["readonly"]),
+            CommandSpec::keyed("hvals", 2, &["readonly"]),
+            CommandSpec::keyed("hincrby", 4, &["write", "fast"]),
+            CommandSpec::keyed("lpush", -3, &["write", "fast"]),
+            CommandSpec::keyed("rpush", -3, &["write", "fast"]),
+            CommandSpec::keyed("lrange", 4, &["readonly"]),
+            CommandSpec::keyed("llen", 2, &["readonly", "fast"]),
+            CommandSpec::keyed("lpop", -2, &["write", "fast"]),
+            CommandSpec::keyed("rpop", -2, &["write", "fast"]),
+            CommandSpec::keyed("lindex", 3, &["readonly"]),
+            CommandSpec::keyed("lset", 4, &["write"]),
+            CommandSpec::keyed("sadd", -3, &["write", "fast"]),
+            CommandSpec::keyed("srem", -3, &["write", "fast"]),
+            CommandSpec::keyed("smembers", 2, &["readonly"]),
+            CommandSpec::keyed("sismember", 3, &["readonly", "fast"]),
+            CommandSpec::keyed("scard", 2, &["readonly", "fast"]),
+            CommandSpec {
+                last_key: -1,
+                ..CommandSpec::keyed("sinter", -2, &["readonly"])
+            },
+            CommandSpec {
+                last_key: -1,
+                ..CommandSpec::keyed("sunion", -2, &["readonly"])
+            },
+            CommandSpec {
+                last_key: -1,
+                ..CommandSpec::keyed("sdiff", -2, &["readonly"])
+            },
+            // the keys follow numkeys, redis reports them with movablekeys
+            CommandSpec::new("sintercard", -3, &["readonly", "movablekeys"]),
+            CommandSpec::keyed("bitfield", -2, &["write"]),
+            CommandSpec::new("echo", 2, &["fast"]),
+            CommandSpec::new("hello", -1, &["fast"]),
+            CommandSpec::new("ping", -1, &["fast"]),
+            CommandSpec::new("client", -2, &["admin"]),
+            CommandSpec::new("flushall", -1, &["write"]),
+            CommandSpec::new("flushdb", -1, &["write"]),
+            CommandSpec::new("scan", -2, &["readonly"]),
+            CommandSpec::new("keys", -2, &["readonly"]),
+            CommandSpec::new("dbsize", 1, &["readonly", "fast"]),
+            CommandSpec::keyed("expire", 3, &["write", "fast"]),
+            CommandSpec {
+                last_key: 2,
+                ..CommandSpec::keyed("rename", -3, &["write"])
+            },
+            CommandSpec::keyed("ttl", 2, &["readonly", "fast"]),
+            CommandSpec::keyed("type", 2, &["readonly", "fast"]),
+            CommandSpec {
+                last_key: -1,
+                ..CommandSpec::keyed("exists", -2, &["readonly", "fast"])
+            },
+            CommandSpec {
+                last_key: -1,
+                ..CommandSpec::keyed("del", -2, &["write"])
+            },
+            CommandSpec {
+                last_key: -1,
+                ..CommandSpec::keyed("unlink", -2, &["write", "fast"])
+            },
+            CommandSpec::new("debug", -2, &["admin"]),
+            CommandSpec::new("memory", -2, &["readonly"]),
+            CommandSpec::new("object", -2, &["readonly"]),
+            CommandSpec::new("command", -1, &["loading", "stale"]),
+        ] {
+            registry.register(spec);
+        }
+        registry
+    }
+
+    pub fn register(&mut self, spec: CommandSpec) {
+        self.commands.insert(spec.name, spec);
+    }
+
+    pub fn get(&self, name: &str) -> Option<&CommandSpec> {
+        self.commands.get(name)
+    }
+
+    pub fn len(&self) -> usize {
+        self.commands.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.commands.is_empty()
+    }
+
+    /// Answer a COMMAND request from this registry
+    pub fn reply(&self, meta: CommandMeta) -> RespFrame {
+        match meta {
+            CommandMeta::Count => RespFrame::Integer(self.len() as i64),
+            CommandMeta::Docs => RespArray::new(vec![]).into(),
+            CommandMeta::Info(names) if names.is_empty() => {
+                let infos: Vec<RespFrame> = self.commands.values().map(|c| c.info()).collect();
+                RespArray::new(infos).into()
+            }
+            CommandMeta::Info(names) => {
+                // unknown commands get a null in their slot, like redis
+                let infos: Vec<RespFrame> = names
+                    .iter()
+                    .map(|name| match self.get(&name.to_ascii_lowercase()) {
+                        Some(spec) => spec.info(),
+                        None => RespFrame::Null(RespNull),
+                    })
+                    .collect();
+                RespArray::new(infos).into()
+            }
+            CommandMeta::GetKeysAndFlags(args) => {
+                let Some(spec) = args
+                    .first()
+                    .and_then(|name| self.get(&name.to_ascii_lowercase()))
+                else {
+                    return SimpleError::new("ERR Invalid command specified").into();
+                };
+                if !spec.arity_matches(args.len()) {
+                    return SimpleError::new(
+                        "ERR Invalid number of arguments specified for command",
+                    )
+                    .into();
+                }
+                let flags: Vec<RespFrame> = spec
+                    .key_flags()
+                    .iter()
+                    .map(|f| BulkString::new(*f).into())
+                    .collect();
+                let keys: Vec<RespFrame> = spec
+                    .key_positions(args.len())
+                    .map(|i| {
+                        RespArray::new(vec![
+                            BulkString::new(args[i].clone()).into(),
+                            RespArray::new(flags.clone()).into(),
+                        ])
+                        .into()
+                    })
+                    .collect();
+                if keys.is_empty() {
+                    return SimpleError::new("ERR The command has no key arguments").into();
+                }
+                RespArray::new(keys).into()
+            }
+        }
+    }
+}
+
+impl CommandExecutor for CommandMeta {
+    fn execute(self, _backend: &crate::Backend) -> RespFrame {
+        BUILTIN_COMMANDS.reply(self)
+    }
+}
+
+impl TryFrom<RespArray> for CommandMeta {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        let args = extract_args(value, 1)?;
+        match (subcommand(&args, 0).as_deref(), args.len()) {
+            (None, _) => Ok(CommandMeta::Info(vec![])),
+            (Some("count"), 1) => Ok(CommandMeta::Count),
+            (Some("docs"), _) => Ok(CommandMeta::Docs),
+            (Some("getkeysandflags"), 2..) => {
+                Ok(CommandMeta::GetKeysAndFlags(string_args(&args[1..])?))
+            }
+            (Some("info"), _) => Ok(CommandMeta::Info(string_args(&args[1..])?)),
+            (Some(sub), _) => Err(CommandError::InvalidCommand(format!(
+                "Unknown COMMAND subcommand or wrong arguments: {}",
+                sub
+            ))),
+        }
+    }
+}
+
+fn string_args(args: &[RespFrame]) -> Result<Vec<String>, CommandError> {
+    args.iter()
+        .map(|arg| match arg {
+            RespFrame::BulkString(name) => {
+                Ok(String::from_utf8(name.0.clone().unwrap_or_default())?)
+            }
+            _ => Err(CommandError::InvalidArgument(
+                "Invalid argument".to_string(),
+            )),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::BytesMut;
+
+    use crate::{Backend, RespDecode};
+
+    use super::*;
+
+    #[test]
+    fn test_command_count_follows_registry() -> anyhow::Result<()> {
+        let mut buf = BytesMut::from("*2\r\n$7\r\ncommand\r\n$5\r\ncount\r\n");
+        let frame = RespArray::decode(&mut buf)?;
+        let meta: CommandMeta = frame.try_into()?;
+        assert_eq!(
+            meta.execute(&Backend::new()),
+            RespFrame::Integer(BUILTIN_COMMANDS.len() as i64)
+        );
+
+        let mut registry = BUILTIN_COMMANDS.clone();
+        registry.register(CommandSpec::keyed("mycommand", 2, &["write"]));
+        assert_eq!(registry.len(), BUILTIN_COMMANDS.len() + 1);
+        assert_eq!(
+            registry.reply(CommandMeta::Count),
+            RespFrame::Integer(registry.len() as i64)
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_command_docs_is_empty() -> anyhow::Result<()> {
+        let mut buf = BytesMut::from("*2\r\n$7\r\ncommand\r\n$4\r\nDOCS\r\n");
+        let frame = RespArray::decode(&mut buf)?;
+        let meta: CommandMeta = frame.try_into()?;
+        assert_eq!(meta.execute(&Backend::new()), RespArray::new(vec![]).into());
+        Ok(())
+    }
+
+    #[test]
+    fn test_command_getkeysandflags() -> anyhow::Result<()> {
+        let mut buf = BytesMut::from(
+            "*5\r\n$7\r\ncommand\r\n$15\r\ngetkeysandflags\r\n$3\r\nset\r\n$3\r\nkey\r\n$5\r\nvalue\r\n",
+        );
+        let frame = RespArray::decode(&mut buf)?;
+        let meta: CommandMeta = frame.try_into()?;
+        let key = |name: &str, flags: &[&str]| -> RespFrame {
+            let flags: Vec<RespFrame> = flags.iter().map(|f| BulkString::new(*f).into()).collect();
+            RespArray::new(vec![
+                BulkString::new(name).into(),
+                RespArray::new(flags).into(),
+            ])
+            .into()
+        };
+        assert_eq!(
+            meta.execute(&Backend::new()),
+            RespArray::new(vec![key("key", &["RW", "update"])]).into()
+        );
+
+        let args = |args: &[&str]| {
+            CommandMeta::GetKeysAndFlags(args.iter().map(|a| a.to_string()).collect())
+        };
+        assert_eq!(
+            BUILTIN_COMMANDS.reply(args(&["set", "key", "value", "EX", "10", "NX"])),
+            RespArray::new(vec![key("key", &["RW", "update"])]).into()
+        );
+        assert_eq!(BUILTIN_COMMANDS.get("set").unwrap().arity, -3);
+        assert_eq!(
+            BUILTIN_COMMANDS.reply(args(&["GET", "key"])),
+            RespArray::new(vec![key("key", &["RO", "access"])]).into()
+        );
+        assert_eq!(
+            BUILTIN_COMMANDS.reply(args(&["mset", "a", "1", "b", "2"])),
+            RespArray::new(vec![
+                key("a", &["RW", "update"]),
+                key("b", &["RW", "update"])
+            ])
+            .into()
+        );
+        assert_eq!(
+            BUILTIN_COMMANDS.reply(args(&["del", "a", "b"])),
+            RespArray::new(vec![
+                key("a", &["RM", "delete"]),
+                key("b", &["RM", "delete"])
+            ])
+            .into()
+        );
+        assert!(matches!(
+            BUILTIN_COMMANDS.reply(args(&["ping"])),
+            RespFrame::Error(_)
+        ));
+        assert!(matches!(
+            BUILTIN_COMMANDS.reply(args(&["get"])),
+            RespFrame::Error(_)
+        ));
+        assert!(matches!(
+            BUILTIN_COMMANDS.reply(args(&["nope", "key"])),
+            RespFrame::Error(_)
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_command_info() -> anyhow::Result<()> {
+        let mut buf = BytesMut::from("*1\r\n$7\r\ncommand\r\n");
+        let frame = RespArray::decode(&mut buf)?;
+        let meta: CommandMeta = frame.try_into()?;
+        let RespFrame::Array(all) = meta.execute(&Backend::new()) else {
+            panic!("COMMAND should reply with an array");
+        };
+        assert_eq!(all.as_ref().unwrap().len(), BUILTIN_COMMANDS.len());
+
+        let reply = BUILTIN_COMMANDS.reply(CommandMeta::Info(vec![
+            "GET".to_string(),
+            "nope".to_string(),
+        ]));
+        let get = BUILTIN_COMMANDS.get("get").unwrap().info();
+        assert_eq!(
+            reply,
+            RespArray::new(vec![get, RespFrame::Null(RespNull)]).into()
+        );
+        Ok(())
+    }
+}
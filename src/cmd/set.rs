@@ -0,0 +1,523 @@
+use std::collections::HashSet;
+
+use crate::{Backend, BulkString, KeyKind, RespArray, RespFrame};
+
+use super::{
+    args::ArgReader, check_key_limit, check_kind, CommandError, CommandExecutor, SAdd, SCard,
+    SDiff, SInter, SInterCard, SIsMember, SMembers, SRem, SUnion,
+};
+
+impl CommandExecutor for SAdd {
+    fn execute(self, backend: &crate::Backend) -> RespFrame {
+        if let Err(e) = check_kind(backend, &self.key, KeyKind::Set) {
+            return e.into();
+        }
+        if let Err(e) = check_key_limit(backend, &[&self.key]) {
+            return e.into();
+        }
+        let mut set = backend.set.entry(self.key.clone()).or_default();
+        backend.index_key(&self.key, KeyKind::Set);
+        let added: Vec<Vec<u8>> = self
+            .members
+            .into_iter()
+            .filter(|member| set.insert(member.clone()))
+            .collect();
+        backend.track_set_write(&self.key, set.len(), &added);
+        RespFrame::Integer(added.len() as i64)
+    }
+}
+
+impl CommandExecutor for SRem {
+    fn execute(self, backend: &crate::Backend) -> RespFrame {
+        if let Err(e) = check_kind(backend, &self.key, KeyKind::Set) {
+            return e.into();
+        }
+        let removed = match backend.set.get_mut(&self.key) {
+            Some(mut set) => self
+                .members
+                .iter()
+                .filter(|member| set.remove(*member))
+                .count(),
+            None => 0,
+        };
+        // 删掉最后一个成员时整个 key 一起删除
+        if backend
+            .set
+            .remove_if(&self.key, |_, set| set.is_empty())
+            .is_some()
+        {
+            backend.expires.remove(&self.key);
+            backend.untrack_set(&self.key);
+            backend.unindex_key(&self.key, KeyKind::Set);
+        }
+        RespFrame::Integer(removed as i64)
+    }
+}
+
+impl CommandExecutor for SMembers {
+    fn execute(self, backend: &crate::Backend) -> RespFrame {
+        if let Err(e) = check_kind(backend, &self.key, KeyKind::Set) {
+            return e.into();
+        }
+        let members: Vec<RespFrame> = match backend.set.get(&self.key) {
+            Some(set) => set
+                .iter()
+                .map(|member| BulkString::new(member.clone()).into())
+                .collect(),
+            None => vec![],
+        };
+        RespArray::new(members).into()
+    }
+}
+
+impl CommandExecutor for SIsMember {
+    fn execute(self, backend: &crate::Backend) -> RespFrame {
+        if let Err(e) = check_kind(backend, &self.key, KeyKind::Set) {
+            return e.into();
+        }
+        let exists = backend
+            .set
+            .get(&self.key)
+            .is_some_and(|set| set.contains(&self.member));
+        RespFrame::Integer(exists as i64)
+    }
+}
+
+impl CommandExecutor for SCard {
+    fn execute(self, backend: &crate::Backend) -> RespFrame {
+        if let Err(e) = check_kind(backend, &self.key, KeyKind::Set) {
+            return e.into();
+        }
+        let len = backend.set.get(&self.key).map_or(0, |set| set.len());
+        RespFrame::Integer(len as i64)
+    }
+}
+
+impl CommandExecutor for SInter {
+    fn execute(self, backend: &crate::Backend) -> RespFrame {
+        combine(backend, &self.keys, |acc, set| {
+            acc.retain(|m| set.contains(m))
+        })
+    }
+}
+
+impl CommandExecutor for SUnion {
+    fn execute(self, backend: &crate::Backend) -> RespFrame {
+        combine(backend, &self.keys, |acc, set| {
+            acc.extend(set.iter().cloned())
+        })
+    }
+}
+
+impl CommandExecutor for SDiff {
+    fn execute(self, backend: &crate::Backend) -> RespFrame {
+        combine(backend, &self.keys, |acc, set| {
+            acc.retain(|m| !set.contains(m))
+        })
+    }
+}
+
+impl CommandExecutor for SInterCard {
+    fn execute(self, backend: &crate::Backend) -> RespFrame {
+        // 先检查所有 key 的类型, 不能因为前面缺了一个 key 就把 WRONGTYPE 吞掉
+        for key in &self.keys {
+            if let Err(e) = check_kind(backend, key, KeyKind::Set) {
+                return e.into();
+            }
+        }
+        // 同时持有多个读锁是安全的, 写入方一次只会锁一个分片
+        let mut sets = Vec::with_capacity(self.keys.len());
+        for key in &self.keys {
+            match backend.set.get(key) {
+                Some(set) => sets.push(set),
+                // 任何一个集合为空, 交集就是空的
+                None => return RespFrame::Integer(0),
+            }
+        }
+        sets.sort_by_key(|set| set.len());
+        let (smallest, others) = sets.split_first().expect("numkeys is at least 1");
+        let contains: Vec<_> = others
+            .iter()
+            .map(|set| move |member: &&Vec<u8>| set.contains(*member))
+            .collect();
+        RespFrame::Integer(intersection_card(smallest.iter(), &contains, self.limit) as i64)
+    }
+}
+
+/// How many of `members` every predicate in `others` accepts, stopping early at `limit` (0 for no
+/// limit). Shared by the *INTERCARD commands: pass the smallest collection's members so the
+/// fewest lookups are made.
+pub(super) fn intersection_card<M>(
+    members: impl IntoIterator<Item = M>,
+    others: &[impl Fn(&M) -> bool],
+    limit: usize,
+) -> usize {
+    let matches = members
+        .into_iter()
+        .filter(|member| others.iter().all(|contains| contains(member)));
+    match limit {
+        0 => matches.count(),
+        limit => matches.take(limit).count(),
+    }
+}
+
+// 从第一个 set 开始依次和后面的 set 合并, 不存在的 key 当作空集合
+fn combine(
+    backend: &Backend,
+    keys: &[String],
+    op: impl Fn(&mut HashSet<Vec<u8>>, &HashSet<Vec<u8>>),
+) -> RespFrame {
+    let mut sets = Vec::with_capacity(keys.len());
+    for key in keys {
+        if let Err(e) = check_kind(backend, key, KeyKind::Set) {
+            return e.into();
+        }
+        sets.push(
+            backend
+                .set
+                .get(key)
+                .map(|set| set.clone())
+                .unwrap_or_default(),
+        );
+    }
+    let mut sets = sets.into_iter();
+    let mut acc = sets.next().unwrap_or_default();
+    for set in sets {
+        op(&mut acc, &set);
+    }
+    let members: Vec<RespFrame> = acc
+        .into_iter()
+        .map(|member| BulkString::new(member).into())
+        .collect();
+    RespArray::new(members).into()
+}
+
+impl TryFrom<RespArray> for SAdd {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        let (key, members) = extract_members(value, "sadd")?;
+        Ok(SAdd { key, members })
+    }
+}
+
+impl TryFrom<RespArray> for SRem {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        let (key, members) = extract_members(value, "srem")?;
+        Ok(SRem { key, members })
+    }
+}
+
+impl TryFrom<RespArray> for SMembers {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        let mut args = ArgReader::new(value, "smembers")?;
+        let key = args.next_string()?;
+        args.finish()?;
+        Ok(SMembers { key })
+    }
+}
+
+impl TryFrom<RespArray> for SIsMember {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        let mut args = ArgReader::new(value, "sismember")?;
+        let (key, member) = (args.next_string()?, args.next_bytes()?);
+        args.finish()?;
+        Ok(SIsMember { key, member })
+    }
+}
+
+impl TryFrom<RespArray> for SCard {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        let mut args = ArgReader::new(value, "scard")?;
+        let key = args.next_string()?;
+        args.finish()?;
+        Ok(SCard { key })
+    }
+}
+
+impl TryFrom<RespArray> for SInter {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        let keys = extract_keys(value, "sinter")?;
+        Ok(SInter { keys })
+    }
+}
+
+impl TryFrom<RespArray> for SUnion {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        let keys = extract_keys(value, "sunion")?;
+        Ok(SUnion { keys })
+    }
+}
+
+impl TryFrom<RespArray> for SDiff {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        let keys = extract_keys(value, "sdiff")?;
+        Ok(SDiff { keys })
+    }
+}
+
+// numkeys key [key ...] [LIMIT limit]
+impl TryFrom<RespArray> for SInterCard {
+    type Error = CommandError;
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        let mut args = ArgReader::new(value, "sintercard")?;
+        let numkeys = args.next_i64()?;
+        if numkeys <= 0 {
+            return Err(CommandError::InvalidArgument(
+                "numkeys should be greater than 0".to_string(),
+            ));
+        }
+        let keys = (0..numkeys)
+            .map(|_| args.next_string())
+            .collect::<Result<Vec<_>, _>>()?;
+        let mut limit = 0;
+        if !args.is_empty() {
+            if !args.next_string()?.eq_ignore_ascii_case("limit") {
+                return Err(CommandError::InvalidArgument("syntax error".to_string()));
+            }
+            limit = usize::try_from(args.next_i64()?).map_err(|_| {
+                CommandError::InvalidArgument("LIMIT can't be negative".to_string())
+            })?;
+        }
+        args.finish()?;
+        Ok(SInterCard { keys, limit })
+    }
+}
+
+// key [key ...]
+fn extract_keys(value: RespArray, name: &'static str) -> Result<Vec<String>, CommandError> {
+    let mut args = ArgReader::new(value, name)?;
+    let mut keys = vec![args.next_string()?];
+    keys.extend(args.rest_strings()?);
+    Ok(keys)
+}
+
+// key member [member ...]
+fn extract_members(
+    value: RespArray,
+    name: &'static str,
+) -> Result<(String, Vec<Vec<u8>>), CommandError> {
+    let mut args = ArgReader::new(value, name)?;
+    let key = args.next_string()?;
+    let mut members = vec![args.next_bytes()?];
+    while !args.is_empty() {
+        members.push(args.next_bytes()?);
+    }
+    Ok((key, members))
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::BytesMut;
+
+    use crate::{Backend, RespDecode};
+
+    use super::*;
+
+    fn sadd(backend: &Backend, members: &[&str]) -> RespFrame {
+        SAdd {
+            key: "set".to_string(),
+            members: members.iter().map(|m| m.as_bytes().to_vec()).collect(),
+        }
+        .execute(backend)
+    }
+
+    fn scard(backend: &Backend) -> RespFrame {
+        SCard {
+            key: "set".to_string(),
+        }
+        .execute(backend)
+    }
+
+    #[test]
+    fn test_sadd_duplicates() -> anyhow::Result<()> {
+        let backend = Backend::new();
+        let mut buf =
+            BytesMut::from("*5\r\n$4\r\nsadd\r\n$3\r\nset\r\n$1\r\na\r\n$1\r\nb\r\n$1\r\na\r\n");
+        let cmd: SAdd = RespArray::decode(&mut buf)?.try_into()?;
+        assert_eq!(cmd.execute(&backend), RespFrame::Integer(2));
+        assert_eq!(sadd(&backend, &["b", "c"]), RespFrame::Integer(1));
+        assert_eq!(scard(&backend), RespFrame::Integer(3));
+        assert_eq!(backend.type_of("set"), "set");
+
+        let members = SMembers {
+            key: "set".to_string(),
+        }
+        .execute(&backend);
+        let RespFrame::Array(members) = members else {
+            panic!("expected an array, got {:?}", members);
+        };
+        let mut members: Vec<RespFrame> = members.0.unwrap_or_default();
+        members.sort_by_key(|m| match m {
+            RespFrame::BulkString(s) => s.0.clone(),
+            _ => None,
+        });
+        let expected: Vec<RespFrame> = ["a", "b", "c"]
+            .iter()
+            .map(|m| BulkString::new(*m).into())
+            .collect();
+        assert_eq!(members, expected);
+        Ok(())
+    }
+
+    #[test]
+    fn test_sismember() -> anyhow::Result<()> {
+        let backend = Backend::new();
+        sadd(&backend, &["a"]);
+        let mut buf = BytesMut::from("*3\r\n$9\r\nsismember\r\n$3\r\nset\r\n$1\r\na\r\n");
+        let cmd: SIsMember = RespArray::decode(&mut buf)?.try_into()?;
+        assert_eq!(cmd.execute(&backend), RespFrame::Integer(1));
+
+        let cmd = SIsMember {
+            key: "set".to_string(),
+            member: b"b".to_vec(),
+        };
+        assert_eq!(cmd.execute(&backend), RespFrame::Integer(0));
+        let cmd = SIsMember {
+            key: "missing".to_string(),
+            member: b"a".to_vec(),
+        };
+        assert_eq!(cmd.execute(&backend), RespFrame::Integer(0));
+        Ok(())
+    }
+
+    #[test]
+    fn test_srem_drops_empty_set() {
+        let backend = Backend::new();
+        sadd(&backend, &["a", "b"]);
+        let srem = SRem {
+            key: "set".to_string(),
+            members: vec![b"a".to_vec(), b"b".to_vec(), b"c".to_vec()],
+        };
+        assert_eq!(srem.execute(&backend), RespFrame::Integer(2));
+        assert_eq!(scard(&backend), RespFrame::Integer(0));
+        assert!(!backend.exists("set"));
+    }
+
+    // 集合没有顺序, 排序之后再比较
+    fn sorted(frame: RespFrame) -> Vec<String> {
+        let RespFrame::Array(members) = frame else {
+            panic!("expected an array, got {:?}", frame);
+        };
+        let mut members: Vec<String> = members
+            .0
+            .unwrap_or_default()
+            .into_iter()
+            .map(|m| match m {
+                RespFrame::BulkString(s) => String::from_utf8(s.0.unwrap_or_default()).unwrap(),
+                m => panic!("expected a bulk string, got {:?}", m),
+            })
+            .collect();
+        members.sort();
+        members
+    }
+
+    fn setup() -> Backend {
+        let backend = Backend::new();
+        for (key, members) in [("s1", ["a", "b", "c"]), ("s2", ["b", "c", "d"])] {
+            SAdd {
+                key: key.to_string(),
+                members: members.iter().map(|m| m.as_bytes().to_vec()).collect(),
+            }
+            .execute(&backend);
+        }
+        backend
+    }
+
+    fn keys(keys: &[&str]) -> Vec<String> {
+        keys.iter().map(|k| k.to_string()).collect()
+    }
+
+    #[test]
+    fn test_sinter() -> anyhow::Result<()> {
+        let backend = setup();
+        let mut buf = BytesMut::from("*3\r\n$6\r\nsinter\r\n$2\r\ns1\r\n$2\r\ns2\r\n");
+        let cmd: SInter = RespArray::decode(&mut buf)?.try_into()?;
+        assert_eq!(sorted(cmd.execute(&backend)), ["b", "c"]);
+
+        let cmd = SInter {
+            keys: keys(&["s1", "missing"]),
+        };
+        assert!(sorted(cmd.execute(&backend)).is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_sunion() {
+        let backend = setup();
+        let cmd = SUnion {
+            keys: keys(&["s1", "missing", "s2"]),
+        };
+        assert_eq!(sorted(cmd.execute(&backend)), ["a", "b", "c", "d"]);
+    }
+
+    #[test]
+    fn test_sdiff() {
+        let backend = setup();
+        let cmd = SDiff {
+            keys: keys(&["s1", "s2", "missing"]),
+        };
+        assert_eq!(sorted(cmd.execute(&backend)), ["a"]);
+        let cmd = SDiff {
+            keys: keys(&["missing", "s1"]),
+        };
+        assert!(sorted(cmd.execute(&backend)).is_empty());
+
+        backend.set("str".to_string(), BulkString::new("x").into());
+        let cmd = SDiff {
+            keys: keys(&["s1", "str"]),
+        };
+        assert_eq!(cmd.execute(&backend), CommandError::WrongType.into());
+    }
+
+    #[test]
+    fn test_sintercard() -> anyhow::Result<()> {
+        let backend = setup();
+        let mut buf =
+            BytesMut::from("*4\r\n$10\r\nsintercard\r\n$1\r\n2\r\n$2\r\ns1\r\n$2\r\ns2\r\n");
+        let cmd: SInterCard = RespArray::decode(&mut buf)?.try_into()?;
+        assert_eq!(cmd.limit, 0);
+        assert_eq!(cmd.execute(&backend), RespFrame::Integer(2));
+
+        let mut buf = BytesMut::from(
+            "*6\r\n$10\r\nsintercard\r\n$1\r\n2\r\n$2\r\ns1\r\n$2\r\ns2\r\n$5\r\nLIMIT\r\n$1\r\n1\r\n",
+        );
+        let cmd: SInterCard = RespArray::decode(&mut buf)?.try_into()?;
+        assert_eq!(cmd.limit, 1);
+        assert_eq!(cmd.execute(&backend), RespFrame::Integer(1));
+
+        let cmd = SInterCard {
+            keys: keys(&["s1", "missing"]),
+            limit: 0,
+        };
+        assert_eq!(cmd.execute(&backend), RespFrame::Integer(0));
+
+        backend.set("str".to_string(), BulkString::new("v").into());
+        let cmd = SInterCard {
+            keys: keys(&["missing", "str"]),
+            limit: 0,
+        };
+        assert_eq!(cmd.execute(&backend), CommandError::WrongType.into());
+
+        let mut buf =
+            BytesMut::from("*4\r\n$10\r\nsintercard\r\n$1\r\n3\r\n$2\r\ns1\r\n$2\r\ns2\r\n");
+        assert!(SInterCard::try_from(RespArray::decode(&mut buf)?).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_intersection_card() {
+        let evens = |n: &u32| n.is_multiple_of(2);
+        let small = |n: &u32| *n < 10;
+        let preds: [&dyn Fn(&u32) -> bool; 2] = [&evens, &small];
+        assert_eq!(intersection_card(0..100u32, &preds, 0), 5);
+        assert_eq!(intersection_card(0..100u32, &preds, 3), 3);
+        assert_eq!(intersection_card(0..100u32, &preds[..0], 7), 7);
+    }
+}
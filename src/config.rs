@@ -0,0 +1,107 @@
+#[cfg(any(unix, feature = "tls"))]
+use std::path::PathBuf;
+use std::time::Duration;
+
+#[derive(Debug, Clone)]
+pub struct ServerConfig {
+    /// addresses to listen on, every one of them must bind for the server to start
+    pub binds: Vec<String>,
+    /// also listen on this unix socket, the file is removed again on shutdown
+    #[cfg(unix)]
+    pub unixsocket: Option<PathBuf>,
+    /// terminate TLS on every TCP listener
+    #[cfg(feature = "tls")]
+    pub tls: Option<TlsConfig>,
+    /// accept a bare `\n` as a line terminator for clients that don't send `\r\n`
+    pub lenient_newlines: bool,
+    /// abort read-only commands running longer than this and reply with an error
+    pub command_timeout: Option<Duration>,
+    /// keep a per-type set of keys so type-filtered listings don't scan the whole keyspace
+    pub type_index: bool,
+    /// close client connections idle for this many seconds, 0 disables it
+    pub timeout: u64,
+    /// how to answer commands this server doesn't know
+    pub unknown_command_mode: UnknownCommandMode,
+    /// hashes with more fields than this are reported as `hashtable` instead of `listpack`
+    pub hash_max_listpack_entries: usize,
+    /// same, for a field or value longer than this many bytes
+    pub hash_max_listpack_value: usize,
+    /// lists with more entries than this are reported as `quicklist` instead of `listpack`, like
+    /// a positive redis list-max-listpack-size
+    pub list_max_listpack_size: usize,
+    /// sets of integers with more members than this are reported as `hashtable` instead of
+    /// `intset`
+    pub set_max_intset_entries: usize,
+    /// other sets with more members than this are reported as `hashtable` instead of `listpack`
+    pub set_max_listpack_entries: usize,
+    /// same, for a member longer than this many bytes
+    pub set_max_listpack_value: usize,
+    /// pipelined replies are buffered and flushed once they exceed this many bytes
+    pub write_flush_threshold: usize,
+    /// the largest string value APPEND/SETRANGE may produce, like redis proto-max-bulk-len
+    pub proto_max_bulk_len: usize,
+    /// drop the unread input instead of closing the connection when it can't be parsed
+    pub resync_on_error: bool,
+    /// refuse to create new keys once the keyspace holds this many, existing keys can still
+    /// be written
+    pub maxkeys: Option<usize>,
+    /// reject commands with more arguments than this, counting the command name
+    pub max_command_args: usize,
+    /// have HKEYS/HVALS/HGETALL list fields sorted by name instead of in storage order
+    pub sorted_hash_fields: bool,
+    /// accept non-standard command options, e.g. `RENAME key newkey EX seconds`
+    pub enable_extensions: bool,
+    /// let INCR and friends go past the i64 range, such counters are replied to as RESP3 big
+    /// numbers
+    pub wide_integers: bool,
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        Self {
+            binds: vec!["0.0.0.0:6379".to_string()],
+            #[cfg(unix)]
+            unixsocket: None,
+            #[cfg(feature = "tls")]
+            tls: None,
+            lenient_newlines: false,
+            command_timeout: None,
+            type_index: false,
+            timeout: 0,
+            unknown_command_mode: UnknownCommandMode::default(),
+            hash_max_listpack_entries: 128,
+            hash_max_listpack_value: 64,
+            list_max_listpack_size: 128,
+            set_max_intset_entries: 512,
+            set_max_listpack_entries: 128,
+            set_max_listpack_value: 64,
+            write_flush_threshold: 16 * 1024,
+            proto_max_bulk_len: 512 * 1024 * 1024,
+            resync_on_error: false,
+            maxkeys: None,
+            max_command_args: 1024 * 1024,
+            sorted_hash_fields: false,
+            enable_extensions: false,
+            wide_integers: false,
+        }
+    }
+}
+
+/// PEM encoded certificate chain and private key
+#[cfg(feature = "tls")]
+#[derive(Debug, Clone)]
+pub struct TlsConfig {
+    pub cert: PathBuf,
+    pub key: PathBuf,
+    /// drop connections that haven't finished the handshake within this long
+    pub handshake_timeout: Duration,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum UnknownCommandMode {
+    /// `-ERR unknown command`, like redis
+    #[default]
+    Error,
+    /// the legacy `+OK` reply
+    Ok,
+}
@@ -1,10 +1,14 @@
 mod backend;
 pub mod cmd;
+mod config;
 mod resp;
 mod respv2;
+#[cfg(feature = "tls")]
+pub mod tls;
 
 pub mod network;
 
 pub use backend::*;
+pub use config::*;
 pub use resp::*;
 pub use respv2::*;
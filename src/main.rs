@@ -1,6 +1,7 @@
+use std::time::Duration;
+
 use anyhow::Result;
-use simple_redis::Backend;
-use tokio::net::TcpListener;
+use simple_redis::{network, Backend};
 use tracing::{info, level_filters::LevelFilter, warn};
 use tracing_subscriber::{
     fmt::Layer, layer::SubscriberExt as _, util::SubscriberInitExt as _, Layer as _,
@@ -11,23 +12,74 @@ async fn main() -> Result<()> {
     let layer = Layer::new().with_filter(LevelFilter::INFO);
     tracing_subscriber::registry().with(layer).init();
 
-    let addr = "0.0.0.0:6379";
-    info!("Simple-Redis_server is Listening on {}", addr);
-    let listener = TcpListener::bind(addr).await?;
-
     let backend = Backend::new();
+    let listeners = network::bind(&backend.config.binds).await?;
+    #[cfg(unix)]
+    let unix = backend
+        .config
+        .unixsocket
+        .as_deref()
+        .map(network::bind_unix)
+        .transpose()?;
+    let serve = async {
+        let tcp = network::serve(listeners, backend.clone());
+        #[cfg(unix)]
+        if let Some(listener) = unix {
+            tokio::try_join!(tcp, network::run_unix(listener, backend.clone()))?;
+            return Ok(());
+        }
+        tcp.await
+    };
+
+    // 收到信号后 serve 被 drop，所有 accept 循环随之停止
+    let ret = tokio::select! {
+        ret = serve => ret,
+        _ = shutdown_signal() => {
+            info!(
+                "Shutting down, {} connections still active",
+                backend.connections.len()
+            );
+            Ok(())
+        }
+    };
+    #[cfg(unix)]
+    if let Some(path) = &backend.config.unixsocket {
+        let _ = std::fs::remove_file(path);
+    }
+
+    // idle connections close right away, busy ones after the command they are running
+    backend.shutdown.cancel();
+    backend.connections.close();
+    if tokio::time::timeout(SHUTDOWN_GRACE, backend.connections.wait())
+        .await
+        .is_err()
+    {
+        warn!(
+            "{} connections did not finish within {:?}",
+            backend.connections.len(),
+            SHUTDOWN_GRACE
+        );
+    }
+    ret
+}
+
+const SHUTDOWN_GRACE: Duration = Duration::from_secs(5);
 
-    loop {
-        let (socket, raddr) = listener.accept().await?;
-        info!("Accepted connection from: {}", raddr);
-        let cloned_backend = backend.clone();
-        tokio::spawn(async move {
-            match simple_redis::network::stream_handler(socket, cloned_backend).await {
-                Ok(_) => {
-                    info!("Connection from {} is handled successfully", raddr);
+/// Resolve on Ctrl-C, or SIGTERM on unix
+async fn shutdown_signal() {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{signal, SignalKind};
+        match signal(SignalKind::terminate()) {
+            Ok(mut term) => {
+                tokio::select! {
+                    _ = tokio::signal::ctrl_c() => {}
+                    _ = term.recv() => {}
                 }
-                Err(e) => warn!("Error: {:?}", e),
+                return;
             }
-        });
+            Err(e) => warn!("failed to install SIGTERM handler: {:?}", e),
+        }
     }
+    let _ = tokio::signal::ctrl_c().await;
 }
@@ -1,16 +1,48 @@
-use futures::SinkExt;
-use tokio::net::TcpStream;
+#[cfg(unix)]
+use std::path::Path;
+use std::{
+    panic::{catch_unwind, AssertUnwindSafe},
+    time::{Duration, Instant},
+};
+
+use anyhow::Context;
+use bytes::Buf;
+use futures::{future::try_join_all, SinkExt};
+#[cfg(unix)]
+use tokio::net::UnixListener;
+use tokio::{
+    io::{AsyncRead, AsyncWrite, AsyncWriteExt},
+    net::TcpListener,
+};
 use tokio_stream::StreamExt;
 use tokio_util::codec::{Decoder, Encoder, Framed};
-use tracing::info;
+use tracing::{debug, error, info, warn};
 
 use crate::{
-    cmd::{Command, CommandExecutor},
-    Backend, RespDecodeV2, RespEncode, RespError, RespFrame,
+    cmd::{Command, CommandError, CommandExecutor, Session},
+    parse_length, with_lenient_newlines, Backend, RespArray, RespDecodeV2, RespEncode, RespError,
+    RespFrame, RespVersion, SimpleError, SimpleString,
 };
 
+// write_frame 攒够这么多字节就写出去一次
+const WRITE_CHUNK: usize = 8 * 1024;
+// 元素多于这个数的回复不经过 codec, 用 write_frame 边编码边写
+const STREAM_MIN_ELEMENTS: usize = 1024;
+
+#[derive(Debug)]
+struct RespFrameCodec {
+    lenient_newlines: bool,
+    resync_on_error: bool,
+    max_command_args: usize,
+    version: RespVersion,
+}
+
 #[derive(Debug)]
-struct RespFrameCodec;
+enum Incoming {
+    Frame(RespFrame),
+    /// a command array longer than `max_command_args`, skipped without being parsed
+    TooManyArgs(usize),
+}
 
 #[derive(Debug)]
 struct RedisRequest {
@@ -23,37 +55,342 @@ struct RedisResponse {
     frame: RespFrame,
 }
 
-pub async fn stream_handler(stream: TcpStream, backend: Backend) -> anyhow::Result<()> {
-    let mut framed = Framed::new(stream, RespFrameCodec);
+/// Bind every address up front so a bad one fails startup before anything is served
+pub async fn bind(binds: &[String]) -> anyhow::Result<Vec<TcpListener>> {
+    let mut listeners = Vec::with_capacity(binds.len());
+    for addr in binds {
+        let listener = TcpListener::bind(addr)
+            .await
+            .with_context(|| format!("failed to bind {}", addr))?;
+        info!(
+            "Simple-Redis_server is Listening on {}",
+            listener.local_addr()?
+        );
+        listeners.push(listener);
+    }
+    Ok(listeners)
+}
+
+/// Bind the unix socket, replacing a file left behind by a previous run
+#[cfg(unix)]
+pub fn bind_unix(path: &Path) -> anyhow::Result<UnixListener> {
+    if path.exists() {
+        std::fs::remove_file(path)
+            .with_context(|| format!("failed to remove stale {}", path.display()))?;
+    }
+    let listener =
+        UnixListener::bind(path).with_context(|| format!("failed to bind {}", path.display()))?;
+    info!("Simple-Redis_server is Listening on {}", path.display());
+    Ok(listener)
+}
+
+/// Run an accept loop per listener, all sharing the same backend. A unix socket is served
+/// alongside with `run_unix`
+pub async fn serve(listeners: Vec<TcpListener>, backend: Backend) -> anyhow::Result<()> {
+    try_join_all(
+        listeners
+            .into_iter()
+            .map(|listener| run(listener, backend.clone())),
+    )
+    .await?;
+    Ok(())
+}
+
+/// Accept connections forever, serving each one on its own task
+pub async fn run(listener: TcpListener, backend: Backend) -> anyhow::Result<()> {
+    #[cfg(feature = "tls")]
+    let acceptor = backend
+        .config
+        .tls
+        .as_ref()
+        .map(|tls| anyhow::Ok((crate::tls::acceptor(tls)?, tls.handshake_timeout)))
+        .transpose()?;
+    loop {
+        let (socket, raddr) = listener.accept().await?;
+        info!("Accepted connection from: {}", raddr);
+        let cloned_backend = backend.clone();
+        #[cfg(feature = "tls")]
+        if let Some((acceptor, limit)) = acceptor.clone() {
+            backend.connections.spawn(async move {
+                // a failed or stalled handshake only costs this connection
+                let stream = match tokio::time::timeout(limit, acceptor.accept(socket)).await {
+                    Ok(Ok(stream)) => stream,
+                    Ok(Err(e)) => return warn!("TLS handshake with {} failed: {:?}", raddr, e),
+                    Err(_) => return warn!("TLS handshake with {} timed out", raddr),
+                };
+                if let Err(e) = stream_handler(stream, raddr.to_string(), cloned_backend).await {
+                    warn!("Error: {:?}", e);
+                }
+            });
+            continue;
+        }
+        backend.connections.spawn(async move {
+            match stream_handler(socket, raddr.to_string(), cloned_backend).await {
+                Ok(_) => {
+                    info!("Connection from {} is handled successfully", raddr);
+                }
+                Err(e) => warn!("Error: {:?}", e),
+            }
+        });
+    }
+}
+
+/// Same as `run`, for a unix socket
+#[cfg(unix)]
+pub async fn run_unix(listener: UnixListener, backend: Backend) -> anyhow::Result<()> {
+    // unix peers are unnamed, so report the socket path like redis does
+    let path = listener.local_addr()?;
+    let addr = format!(
+        "{}:0",
+        path.as_pathname().unwrap_or(Path::new("")).display()
+    );
+    loop {
+        let (socket, _) = listener.accept().await?;
+        info!("Accepted connection on: {}", addr);
+        let (addr, cloned_backend) = (addr.clone(), backend.clone());
+        backend.connections.spawn(async move {
+            if let Err(e) = stream_handler(socket, addr, cloned_backend).await {
+                warn!("Error: {:?}", e);
+            }
+        });
+    }
+}
+
+pub async fn stream_handler<S>(stream: S, addr: String, backend: Backend) -> anyhow::Result<()>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    let client = backend.register_client(addr);
+    let codec = RespFrameCodec {
+        lenient_newlines: backend.config.lenient_newlines,
+        resync_on_error: backend.config.resync_on_error,
+        max_command_args: backend.config.max_command_args,
+        version: RespVersion::default(),
+    };
+    let mut framed = Framed::new(stream, codec);
+    let mut session = Session {
+        id: client.id(),
+        ..Default::default()
+    };
+    let idle = match backend.config.timeout {
+        0 => None,
+        secs => Some(Duration::from_secs(secs)),
+    };
+    let killed = client.killed();
     loop {
-        match framed.next().await {
-            Some(Ok(frame)) => {
+        // the deadline restarts with every read, so it only fires after a full idle period
+        let read = async {
+            match idle {
+                Some(idle) => tokio::time::timeout(idle, framed.next()).await,
+                None => Ok(framed.next().await),
+            }
+        };
+        // only waiting for the next command is cancelled, a command already running finishes
+        let next = tokio::select! {
+            _ = killed.cancelled() => None,
+            next = read => Some(next),
+        };
+        let Some(next) = next else {
+            debug!("Closing connection killed by CLIENT KILL or shutdown");
+            // 前面命令的回复可能还在写缓冲里
+            framed.flush().await?;
+            return Ok(());
+        };
+        let next = match next {
+            Ok(next) => next,
+            Err(_) => {
+                debug!("Closing connection idle for more than {:?}", idle);
+                return Ok(());
+            }
+        };
+        let reply = match next {
+            Some(Ok(Incoming::Frame(frame))) => {
                 info!("Received frame: {:?}", frame);
+                client.record(&command_name(&frame), session.version);
                 let request = RedisRequest {
                     frame,
                     backend: backend.clone(),
                 };
-                let response = request_handler(request).await?;
-                info!("Sending response: {:?}", response.frame);
-                framed.send(response.frame).await?;
+                request_handler(request, &mut session).await?.frame
+            }
+            Some(Ok(Incoming::TooManyArgs(n))) => {
+                warn!("Skipped a command with {} arguments", n);
+                CommandError::InvalidArgument("too many arguments".to_string()).into()
             }
             Some(Err(e)) => return Err(e),
             None => return Ok(()),
+        };
+        // a HELLO reply is already encoded with the version it negotiated
+        framed.codec_mut().version = session.version;
+        info!("Sending response: {:?}", reply);
+        if aggregate_len(&reply) >= STREAM_MIN_ELEMENTS {
+            // earlier replies still buffered in the codec have to go out first
+            framed.flush().await?;
+            write_frame(reply, framed.get_mut(), session.version).await?;
+            continue;
+        }
+        // buffer replies while more pipelined commands are already waiting, but don't let the
+        // buffer grow past the threshold
+        framed.feed(reply).await?;
+        let pending = framed.codec().has_complete_frame(framed.read_buffer());
+        if !pending || framed.write_buffer().len() >= backend.config.write_flush_threshold {
+            framed.flush().await?;
         }
     }
 }
 
-async fn request_handler(request: RedisRequest) -> anyhow::Result<RedisResponse> {
+async fn request_handler(
+    request: RedisRequest,
+    session: &mut Session,
+) -> anyhow::Result<RedisResponse> {
     let (frame, backend) = (request.frame, request.backend);
-    let cmd: Command = frame.try_into()?;
+    let name = command_name(&frame);
+    // 解析也可能 panic, 和执行放在同一个边界里
+    let cmd = match catch_unwind(AssertUnwindSafe(|| Command::try_from(frame))) {
+        Ok(Ok(cmd)) => cmd,
+        Ok(Err(e)) => {
+            warn!("Invalid command: {:?}", e);
+            return Ok(RedisResponse { frame: e.into() });
+        }
+        Err(_) => {
+            return Ok(RedisResponse {
+                frame: internal_error(&name),
+            })
+        }
+    };
     info!("Executing command: {:?}", cmd);
-    let frame = cmd.execute(&backend);
+    let start = Instant::now();
+    let frame = match backend.config.command_timeout {
+        Some(limit) if cmd.is_read_only() => {
+            execute_with_timeout(cmd, backend.clone(), limit, &name).await?
+        }
+        _ if cmd.is_blocking() => execute_blocking(cmd, backend.clone(), &name).await?,
+        // a buggy executor must not take the whole connection down, DashMap doesn't poison
+        _ => match catch_unwind(AssertUnwindSafe(|| cmd.execute_in(&backend, session))) {
+            Ok(frame) => frame,
+            Err(_) => internal_error(&name),
+        },
+    };
+    if let Some(metrics) = &backend.metrics {
+        metrics.on_command(&name, start.elapsed());
+    }
     Ok(RedisResponse { frame })
 }
 
+/// Encode `frame` into `w` a chunk at a time instead of building the whole reply in memory.
+/// Aggregates are walked element by element, so at most about `WRITE_CHUNK` bytes are buffered.
+/// A RESP2 `version` gets the same downgrade the connection codec applies.
+pub async fn write_frame<W>(
+    frame: RespFrame,
+    w: &mut W,
+    version: RespVersion,
+) -> std::io::Result<()>
+where
+    W: AsyncWrite + Unpin,
+{
+    let frame = match version {
+        RespVersion::Resp2 => frame.into_resp2(),
+        RespVersion::Resp3 => frame,
+    };
+    let mut buf = Vec::with_capacity(WRITE_CHUNK);
+    // 待编码的元素, 逆序压栈, 保证按原顺序弹出
+    let mut stack = vec![frame];
+    while let Some(frame) = stack.pop() {
+        match frame {
+            RespFrame::Array(RespArray(Some(frames))) => {
+                buf.extend_from_slice(format!("*{}\r\n", frames.len()).as_bytes());
+                stack.extend(frames.into_iter().rev());
+            }
+            RespFrame::Set(set) => {
+                buf.extend_from_slice(format!("~{}\r\n", set.len()).as_bytes());
+                stack.extend(set.0.into_iter().rev());
+            }
+            RespFrame::Map(map) => {
+                buf.extend_from_slice(format!("%{}\r\n", map.len()).as_bytes());
+                let mut entries = Vec::with_capacity(map.len() * 2);
+                for (key, value) in map.0 {
+                    entries.push(SimpleString::new(key).into());
+                    entries.push(value);
+                }
+                stack.extend(entries.into_iter().rev());
+            }
+            frame => frame.encode_to(&mut buf),
+        }
+        if buf.len() >= WRITE_CHUNK {
+            w.write_all(&buf).await?;
+            buf.clear();
+        }
+    }
+    w.write_all(&buf).await?;
+    w.flush().await
+}
+
+fn aggregate_len(frame: &RespFrame) -> usize {
+    match frame {
+        RespFrame::Array(RespArray(Some(frames))) => frames.len(),
+        RespFrame::Set(set) => set.len(),
+        RespFrame::Map(map) => map.len(),
+        _ => 0,
+    }
+}
+
+fn command_name(frame: &RespFrame) -> String {
+    let RespFrame::Array(args) = frame else {
+        return String::new();
+    };
+    match args.0.as_ref().and_then(|args| args.first()) {
+        Some(RespFrame::BulkString(name)) => {
+            String::from_utf8_lossy(name.0.as_deref().unwrap_or_default()).to_ascii_lowercase()
+        }
+        _ => String::new(),
+    }
+}
+
+fn internal_error(name: &str) -> RespFrame {
+    error!("Command {} panicked", name);
+    SimpleError::new("ERR internal error").into()
+}
+
+// the command keeps running on the blocking pool after the deadline, only its reply is dropped
+async fn execute_with_timeout(
+    cmd: Command,
+    backend: Backend,
+    limit: Duration,
+    name: &str,
+) -> anyhow::Result<RespFrame> {
+    let handle = tokio::task::spawn_blocking(move || cmd.execute(&backend));
+    match tokio::time::timeout(limit, handle).await {
+        Ok(Err(e)) if e.is_panic() => Ok(internal_error(name)),
+        Ok(frame) => Ok(frame?),
+        Err(_) => {
+            warn!("Command execution timed out after {:?}", limit);
+            Ok(SimpleError::new("ERR command execution timed out").into())
+        }
+    }
+}
+
+// keeps the async worker free for the other connections while the command runs
+async fn execute_blocking(cmd: Command, backend: Backend, name: &str) -> anyhow::Result<RespFrame> {
+    match tokio::task::spawn_blocking(move || cmd.execute(&backend)).await {
+        Err(e) if e.is_panic() => Ok(internal_error(name)),
+        frame => Ok(frame?),
+    }
+}
+
+impl RespFrameCodec {
+    fn has_complete_frame(&self, buf: &[u8]) -> bool {
+        with_lenient_newlines(self.lenient_newlines, || RespFrame::expect_length(buf)).is_ok()
+    }
+}
+
 impl Encoder<RespFrame> for RespFrameCodec {
     type Error = anyhow::Error;
     fn encode(&mut self, item: RespFrame, dst: &mut bytes::BytesMut) -> Result<(), Self::Error> {
+        // never let a RESP2 client see a RESP3-only type
+        let item = match self.version {
+            RespVersion::Resp2 => item.into_resp2(),
+            RespVersion::Resp3 => item,
+        };
         let encodecd = item.encode();
         dst.extend_from_slice(&encodecd);
         Ok(())
@@ -61,13 +398,726 @@ impl Encoder<RespFrame> for RespFrameCodec {
 }
 
 impl Decoder for RespFrameCodec {
-    type Item = RespFrame;
+    type Item = Incoming;
     type Error = anyhow::Error;
     fn decode(&mut self, src: &mut bytes::BytesMut) -> Result<Option<Self::Item>, Self::Error> {
-        match RespFrame::decode(src) {
-            Ok(frame) => Ok(Some(frame)),
+        // 参数个数在数组头里就能看出来, 超过上限的命令只跳过它的字节, 不会解析成参数列表
+        let decoded = with_lenient_newlines(self.lenient_newlines, || {
+            let too_many = parse_length(src, "*")
+                .ok()
+                .map(|(_, len)| len)
+                .filter(|len| *len > self.max_command_args);
+            match too_many {
+                Some(len) => RespFrame::expect_length(src).map(|frame_len| {
+                    src.advance(frame_len);
+                    Incoming::TooManyArgs(len)
+                }),
+                None => RespFrame::decode(src).map(Incoming::Frame),
+            }
+        });
+        match decoded {
+            Ok(item) => Ok(Some(item)),
             Err(RespError::NotComplete) => Ok(None),
+            // Framed stops reading after an error, so resyncing has to happen here
+            Err(e) if self.resync_on_error => {
+                warn!("Discarding {} unparsable bytes: {:?}", src.len(), e);
+                src.clear();
+                Ok(None)
+            }
             Err(e) => Err(e.into()),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use bytes::BytesMut;
+    use tokio::{
+        io::{AsyncReadExt, AsyncWriteExt},
+        net::TcpStream,
+    };
+
+    use crate::{BulkString, RespArray, RespMap, ServerConfig};
+
+    use super::*;
+
+    fn command(args: &[&str]) -> RespFrame {
+        let args: Vec<RespFrame> = args.iter().map(|s| BulkString::new(*s).into()).collect();
+        RespArray::new(args).into()
+    }
+
+    // send a request and return whatever the first read brings back
+    async fn roundtrip(conn: &mut TcpStream, request: &[u8]) -> anyhow::Result<Vec<u8>> {
+        conn.write_all(request).await?;
+        let mut buf = vec![0u8; 4096];
+        let n = conn.read(&mut buf).await?;
+        buf.truncate(n);
+        Ok(buf)
+    }
+
+    #[tokio::test]
+    async fn test_command_timeout() -> anyhow::Result<()> {
+        let backend = Backend::with_config(ServerConfig {
+            command_timeout: Some(Duration::from_millis(50)),
+            ..Default::default()
+        });
+
+        let request = RedisRequest {
+            frame: command(&["debug", "sleep", "0.5"]),
+            backend: backend.clone(),
+        };
+        let response = request_handler(request, &mut Session::default()).await?;
+        assert_eq!(
+            response.frame,
+            SimpleError::new("ERR command execution timed out").into()
+        );
+
+        let request = RedisRequest {
+            frame: command(&["echo", "hello"]),
+            backend,
+        };
+        let response = request_handler(request, &mut Session::default()).await?;
+        assert_eq!(response.frame, crate::SimpleString::new("hello").into());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_invalid_command_replies_error() -> anyhow::Result<()> {
+        let request = RedisRequest {
+            frame: command(&["get"]),
+            backend: Backend::new(),
+        };
+        let response = request_handler(request, &mut Session::default()).await?;
+        assert_eq!(
+            response.frame,
+            SimpleError::new("ERR Invalid argument: get command must have exactly 1 argument")
+                .into()
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_resp2_connection_never_receives_maps() -> anyhow::Result<()> {
+        let backend = Backend::new();
+        let mut session = Session::default();
+        let mut codec = RespFrameCodec {
+            lenient_newlines: false,
+            resync_on_error: false,
+            max_command_args: 1024,
+            version: session.version,
+        };
+
+        let mut map = RespMap::new();
+        map.insert("key".to_string(), true.into());
+        let mut buf = BytesMut::new();
+        codec.encode(map.clone().into(), &mut buf)?;
+        assert_eq!(buf.as_ref(), b"*2\r\n$3\r\nkey\r\n:+1\r\n");
+
+        backend.hset("hash".to_string(), "field".to_string(), b"value".into());
+        let request = RedisRequest {
+            frame: command(&["hgetall", "hash"]),
+            backend: backend.clone(),
+        };
+        let response = request_handler(request, &mut session).await?;
+        let mut buf = BytesMut::new();
+        codec.encode(response.frame, &mut buf)?;
+        assert_eq!(buf.as_ref(), b"*2\r\n$5\r\nfield\r\n$5\r\nvalue\r\n");
+
+        let request = RedisRequest {
+            frame: command(&["hello", "3"]),
+            backend,
+        };
+        request_handler(request, &mut session).await?;
+        codec.version = session.version;
+        let mut buf = BytesMut::new();
+        codec.encode(map.into(), &mut buf)?;
+        assert_eq!(buf.as_ref(), b"%1\r\n+key\r\n#t\r\n");
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_idle_connection_is_closed() -> anyhow::Result<()> {
+        let backend = Backend::with_config(ServerConfig {
+            timeout: 1,
+            ..Default::default()
+        });
+        let listener = TcpListener::bind("127.0.0.1:0").await?;
+        let addr = listener.local_addr()?;
+        let server = tokio::spawn(async move {
+            let (stream, _) = listener.accept().await?;
+            let addr = stream.peer_addr()?.to_string();
+            stream_handler(stream, addr, backend).await
+        });
+
+        let mut client = TcpStream::connect(addr).await?;
+        client
+            .write_all(b"*2\r\n$4\r\necho\r\n$5\r\nhello\r\n")
+            .await?;
+        let mut buf = [0u8; 64];
+        let n = client.read(&mut buf).await?;
+        assert_eq!(&buf[..n], b"+hello\r\n");
+
+        // stay idle, the server should hang up on its own
+        let n = tokio::time::timeout(Duration::from_secs(5), client.read(&mut buf)).await??;
+        assert_eq!(n, 0);
+        server.await??;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_debug_sleep_leaves_other_connections_running() -> anyhow::Result<()> {
+        let backend = Backend::new();
+        let (mut sleeper, server) = tokio::io::duplex(4096);
+        tokio::spawn(stream_handler(server, "a:0".to_string(), backend.clone()));
+        let (mut other, server) = tokio::io::duplex(4096);
+        tokio::spawn(stream_handler(server, "b:0".to_string(), backend));
+
+        // 单线程的 runtime, 如果 sleep 占住了 worker, 另一个连接要等它结束才有回复
+        let start = Instant::now();
+        sleeper
+            .write_all(b"*3\r\n$5\r\ndebug\r\n$5\r\nsleep\r\n$1\r\n2\r\n")
+            .await?;
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        other.write_all(b"*1\r\n$4\r\nping\r\n").await?;
+        let mut buf = [0u8; 64];
+        let n = other.read(&mut buf).await?;
+        assert_eq!(&buf[..n], b"+PONG\r\n");
+        assert!(start.elapsed() < Duration::from_secs(1));
+
+        let n = sleeper.read(&mut buf).await?;
+        assert_eq!(&buf[..n], b"+OK\r\n");
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_panicking_command_keeps_connection() -> anyhow::Result<()> {
+        let listener = TcpListener::bind("127.0.0.1:0").await?;
+        let addr = listener.local_addr()?;
+        tokio::spawn(run(listener, Backend::new()));
+
+        let mut client = TcpStream::connect(addr).await?;
+        let mut buf = [0u8; 64];
+        client
+            .write_all(b"*2\r\n$5\r\ndebug\r\n$5\r\npanic\r\n")
+            .await?;
+        let n = client.read(&mut buf).await?;
+        assert_eq!(&buf[..n], b"-ERR internal error\r\n");
+
+        client
+            .write_all(b"*2\r\n$4\r\necho\r\n$5\r\nhello\r\n")
+            .await?;
+        let n = client.read(&mut buf).await?;
+        assert_eq!(&buf[..n], b"+hello\r\n");
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_null_bulk_string_key_keeps_connection() -> anyhow::Result<()> {
+        let (mut client, server) = tokio::io::duplex(4096);
+        tokio::spawn(stream_handler(
+            server,
+            "duplex:0".to_string(),
+            Backend::new(),
+        ));
+
+        let mut buf = [0u8; 64];
+        for request in [
+            b"*2\r\n$3\r\nget\r\n$-1\r\n".as_slice(),
+            b"*3\r\n$4\r\nhget\r\n$-1\r\n$-1\r\n",
+        ] {
+            client.write_all(request).await?;
+            let n = client.read(&mut buf).await?;
+            assert_eq!(&buf[..n], b"$-1\r\n");
+        }
+
+        client
+            .write_all(b"*2\r\n$4\r\necho\r\n$5\r\nhello\r\n")
+            .await?;
+        let n = client.read(&mut buf).await?;
+        assert_eq!(&buf[..n], b"+hello\r\n");
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_resync_on_error_drops_garbage() -> anyhow::Result<()> {
+        let backend = Backend::with_config(ServerConfig {
+            resync_on_error: true,
+            ..Default::default()
+        });
+        let listener = TcpListener::bind("127.0.0.1:0").await?;
+        let addr = listener.local_addr()?;
+        tokio::spawn(run(listener, backend));
+
+        let mut client = TcpStream::connect(addr).await?;
+        // a complete line that doesn't parse as an integer
+        client.write_all(b":abc\r\n").await?;
+        // give the server a chance to read and drop the garbage on its own
+        tokio::time::sleep(Duration::from_millis(50)).await;
+
+        let reply = roundtrip(&mut client, b"*2\r\n$4\r\necho\r\n$5\r\nhello\r\n").await?;
+        assert_eq!(reply, b"+hello\r\n");
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_serve_on_multiple_binds() -> anyhow::Result<()> {
+        let binds = vec!["127.0.0.1:0".to_string(), "127.0.0.1:0".to_string()];
+        let listeners = bind(&binds).await?;
+        let addrs = listeners
+            .iter()
+            .map(|l| l.local_addr())
+            .collect::<Result<Vec<_>, _>>()?;
+        tokio::spawn(serve(listeners, Backend::new()));
+
+        for addr in addrs {
+            let mut client = TcpStream::connect(addr).await?;
+            let reply = roundtrip(&mut client, b"*2\r\n$4\r\necho\r\n$5\r\nhello\r\n").await?;
+            assert_eq!(reply, b"+hello\r\n");
+        }
+
+        let binds = vec!["127.0.0.1:0".to_string(), "not-an-address".to_string()];
+        let err = bind(&binds).await.unwrap_err();
+        assert!(err.to_string().contains("not-an-address"));
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_unix_socket_set_get() -> anyhow::Result<()> {
+        let path = std::env::temp_dir().join(format!("simple-redis-{}.sock", std::process::id()));
+        let listener = bind_unix(&path)?;
+        tokio::spawn(run_unix(listener, Backend::new()));
+
+        let mut client = tokio::net::UnixStream::connect(&path).await?;
+        let request = b"*3\r\n$3\r\nset\r\n$3\r\nkey\r\n$5\r\nvalue\r\n";
+        client.write_all(request).await?;
+        let mut buf = [0u8; 64];
+        let n = client.read(&mut buf).await?;
+        assert_eq!(&buf[..n], b"+OK\r\n");
+
+        client
+            .write_all(b"*2\r\n$3\r\nget\r\n$3\r\nkey\r\n")
+            .await?;
+        let n = client.read(&mut buf).await?;
+        assert_eq!(&buf[..n], b"$5\r\nvalue\r\n");
+
+        std::fs::remove_file(&path)?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_stream_handler_over_duplex() -> anyhow::Result<()> {
+        let (mut client, server) = tokio::io::duplex(4096);
+        let backend = Backend::new();
+        tokio::spawn(stream_handler(
+            server,
+            "duplex:0".to_string(),
+            backend.clone(),
+        ));
+
+        let mut buf = [0u8; 64];
+        client
+            .write_all(b"*3\r\n$3\r\nset\r\n$3\r\nkey\r\n$5\r\nvalue\r\n")
+            .await?;
+        let n = client.read(&mut buf).await?;
+        assert_eq!(&buf[..n], b"+OK\r\n");
+
+        client
+            .write_all(b"*2\r\n$3\r\nget\r\n$3\r\nkey\r\n")
+            .await?;
+        let n = client.read(&mut buf).await?;
+        assert_eq!(&buf[..n], b"$5\r\nvalue\r\n");
+        assert_eq!(backend.client_list()[0].addr, "duplex:0");
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_too_many_arguments() -> anyhow::Result<()> {
+        let backend = Backend::with_config(ServerConfig {
+            max_command_args: 3,
+            ..Default::default()
+        });
+        let (mut client, server) = tokio::io::duplex(4096);
+        tokio::spawn(stream_handler(
+            server,
+            "duplex:0".to_string(),
+            backend.clone(),
+        ));
+
+        let mut buf = [0u8; 64];
+        client
+            .write_all(&command(&["mset", "a", "1", "b", "2"]).encode())
+            .await?;
+        let n = client.read(&mut buf).await?;
+        assert_eq!(&buf[..n], b"-ERR Invalid argument: too many arguments\r\n");
+        assert!(!backend.exists("a"));
+
+        client
+            .write_all(&command(&["set", "a", "1"]).encode())
+            .await?;
+        let n = client.read(&mut buf).await?;
+        assert_eq!(&buf[..n], b"+OK\r\n");
+        Ok(())
+    }
+
+    #[test]
+    fn test_codec_skips_oversized_command() -> anyhow::Result<()> {
+        let mut codec = RespFrameCodec {
+            lenient_newlines: false,
+            resync_on_error: false,
+            max_command_args: 3,
+            version: RespVersion::default(),
+        };
+        let mut buf = BytesMut::from(command(&["mset", "a", "1", "b", "2"]).encode().as_slice());
+        buf.extend_from_slice(&command(&["get", "a"]).encode());
+        assert!(matches!(
+            codec.decode(&mut buf)?,
+            Some(Incoming::TooManyArgs(5))
+        ));
+        assert!(matches!(codec.decode(&mut buf)?, Some(Incoming::Frame(_))));
+        assert!(buf.is_empty());
+
+        // a huge header alone just waits for more input, nothing is allocated for it
+        let mut buf = BytesMut::from("*1000000000\r\n$3\r\nget\r\n");
+        assert!(codec.decode(&mut buf)?.is_none());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_write_frame_streams_large_reply() -> anyhow::Result<()> {
+        let mut frames: Vec<RespFrame> = (0..2000)
+            .map(|i| BulkString::new(format!("value-{}", i)).into())
+            .collect();
+        frames.push(command(&["a", "b"]));
+        frames.push(RespFrame::Integer(2));
+        let frame: RespFrame = RespArray::new(frames).into();
+
+        let received = stream(frame.clone(), RespVersion::Resp3).await?;
+        assert_eq!(received, frame.clone().encode());
+        let mut buf = bytes::BytesMut::from(received.as_slice());
+        assert_eq!(<RespFrame as RespDecodeV2>::decode(&mut buf)?, frame);
+
+        let mut map = RespMap::new();
+        map.insert("values".to_string(), command(&["a", "b"]));
+        map.insert("count".to_string(), RespFrame::Integer(2));
+        let frame: RespFrame = RespArray::new(vec![
+            map.into(),
+            RespArray::new(vec![]).into(),
+            RespArray(None).into(),
+        ])
+        .into();
+        assert_eq!(
+            stream(frame.clone(), RespVersion::Resp3).await?,
+            frame.clone().encode()
+        );
+        // a RESP2 client gets the map as a flat array
+        assert_eq!(
+            stream(frame.clone(), RespVersion::Resp2).await?,
+            frame.into_resp2().encode()
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_large_reply_keeps_pipeline_order() -> anyhow::Result<()> {
+        let backend = Backend::new();
+        let (mut client, server) = tokio::io::duplex(1024);
+        let handler = tokio::spawn(stream_handler(server, "duplex:0".to_string(), backend));
+
+        let mut request = Vec::new();
+        let mut sadd = vec!["sadd".to_string(), "big".to_string()];
+        sadd.extend((0..STREAM_MIN_ELEMENTS).map(|i| format!("m{}", i)));
+        let sadd: Vec<&str> = sadd.iter().map(String::as_str).collect();
+        command(&sadd).encode_to(&mut request);
+        command(&["smembers", "big"]).encode_to(&mut request);
+        command(&["ping"]).encode_to(&mut request);
+        client.write_all(&request).await?;
+        client.shutdown().await?;
+
+        let mut received = Vec::new();
+        client.read_to_end(&mut received).await?;
+        handler.await??;
+        let mut buf = BytesMut::from(received.as_slice());
+        let added = <RespFrame as RespDecodeV2>::decode(&mut buf)?;
+        assert_eq!(added, RespFrame::Integer(STREAM_MIN_ELEMENTS as i64));
+        // the connection never said HELLO 3, so the set arrives as an array
+        match <RespFrame as RespDecodeV2>::decode(&mut buf)? {
+            RespFrame::Array(RespArray(Some(frames))) => {
+                assert_eq!(frames.len(), STREAM_MIN_ELEMENTS)
+            }
+            frame => panic!("expected an array, got {:?}", frame),
+        }
+        assert_eq!(buf.as_ref(), b"+PONG\r\n");
+        Ok(())
+    }
+
+    // 管道比回复小得多, 写端必须边写边等读端
+    async fn stream(frame: RespFrame, version: RespVersion) -> anyhow::Result<Vec<u8>> {
+        let (mut reader, mut writer) = tokio::io::duplex(1024);
+        let write = tokio::spawn(async move { write_frame(frame, &mut writer, version).await });
+        let mut received = Vec::new();
+        reader.read_to_end(&mut received).await?;
+        write.await??;
+        Ok(received)
+    }
+
+    #[derive(Debug, Default)]
+    struct RecordingMetrics {
+        commands: std::sync::Mutex<Vec<String>>,
+        connections: std::sync::Mutex<Vec<i64>>,
+    }
+
+    impl crate::Metrics for RecordingMetrics {
+        fn on_command(&self, name: &str, _dur: Duration) {
+            self.commands.lock().unwrap().push(name.to_string());
+        }
+
+        fn on_connection(&self, delta: i64) {
+            self.connections.lock().unwrap().push(delta);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_metrics_hooks() -> anyhow::Result<()> {
+        let metrics = std::sync::Arc::new(RecordingMetrics::default());
+        let backend = Backend::with_metrics(ServerConfig::default(), metrics.clone());
+        let (mut client, server) = tokio::io::duplex(4096);
+        let handler = tokio::spawn(stream_handler(server, "duplex:0".to_string(), backend));
+
+        let mut buf = [0u8; 64];
+        client.write_all(b"*1\r\n$4\r\nPING\r\n").await?;
+        let n = client.read(&mut buf).await?;
+        assert_eq!(&buf[..n], b"+PONG\r\n");
+        assert_eq!(*metrics.connections.lock().unwrap(), [1]);
+
+        drop(client);
+        handler.await??;
+        assert_eq!(*metrics.commands.lock().unwrap(), ["ping"]);
+        assert_eq!(*metrics.connections.lock().unwrap(), [1, -1]);
+        Ok(())
+    }
+
+    #[cfg(feature = "tls")]
+    #[tokio::test]
+    async fn test_tls_ping() -> anyhow::Result<()> {
+        use std::sync::Arc;
+
+        use tokio_rustls::{
+            rustls::{crypto::ring, pki_types::ServerName, ClientConfig, RootCertStore},
+            TlsConnector,
+        };
+
+        let cert = rcgen::generate_simple_self_signed(vec!["localhost".to_string()])?;
+        let dir = std::env::temp_dir().join(format!("simple-redis-tls-{}", std::process::id()));
+        std::fs::create_dir_all(&dir)?;
+        let tls = crate::TlsConfig {
+            cert: dir.join("cert.pem"),
+            key: dir.join("key.pem"),
+            handshake_timeout: Duration::from_millis(200),
+        };
+        std::fs::write(&tls.cert, cert.cert.pem())?;
+        std::fs::write(&tls.key, cert.key_pair.serialize_pem())?;
+
+        let backend = Backend::with_config(ServerConfig {
+            tls: Some(tls),
+            ..Default::default()
+        });
+        let listener = TcpListener::bind("127.0.0.1:0").await?;
+        let addr = listener.local_addr()?;
+        tokio::spawn(run(listener, backend));
+
+        // a plain text client fails the handshake without taking the listener down
+        let mut plain = TcpStream::connect(addr).await?;
+        plain.write_all(b"*1\r\n$4\r\nping\r\n").await?;
+
+        // a client that never starts the handshake is dropped once the timeout passes
+        let mut silent = TcpStream::connect(addr).await?;
+        let mut buf = [0u8; 64];
+        let n = tokio::time::timeout(Duration::from_secs(5), silent.read(&mut buf)).await??;
+        assert_eq!(n, 0);
+
+        let mut roots = RootCertStore::empty();
+        roots.add(cert.cert.der().clone())?;
+        let config = ClientConfig::builder_with_provider(Arc::new(ring::default_provider()))
+            .with_safe_default_protocol_versions()?
+            .with_root_certificates(roots)
+            .with_no_client_auth();
+        let connector = TlsConnector::from(Arc::new(config));
+        let stream = TcpStream::connect(addr).await?;
+        let mut client = connector
+            .connect(ServerName::try_from("localhost")?, stream)
+            .await?;
+
+        client.write_all(b"*1\r\n$4\r\nping\r\n").await?;
+        let n = client.read(&mut buf).await?;
+        assert_eq!(&buf[..n], b"+PONG\r\n");
+
+        std::fs::remove_dir_all(&dir)?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_client_list_shows_connections() -> anyhow::Result<()> {
+        let listener = TcpListener::bind("127.0.0.1:0").await?;
+        let addr = listener.local_addr()?;
+        tokio::spawn(run(listener, Backend::new()));
+
+        // a reply proves the server has registered the connection
+        let mut first = TcpStream::connect(addr).await?;
+        let mut second = TcpStream::connect(addr).await?;
+        for conn in [&mut first, &mut second] {
+            let reply = roundtrip(conn, b"*2\r\n$4\r\necho\r\n$2\r\nhi\r\n").await?;
+            assert_eq!(reply, b"+hi\r\n");
+        }
+
+        let reply = roundtrip(&mut first, b"*2\r\n$6\r\nclient\r\n$4\r\nlist\r\n").await?;
+        let reply = String::from_utf8_lossy(&reply);
+        for conn in [&first, &second] {
+            let line = format!("addr={} ", conn.local_addr()?);
+            assert_eq!(reply.matches(&line).count(), 1, "{}", reply);
+        }
+        assert!(reply.contains(&format!(
+            "addr={} name= db=0 cmd=client resp=2",
+            first.local_addr()?
+        )));
+        assert!(reply.contains("cmd=echo"));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_client_kill_closes_connection() -> anyhow::Result<()> {
+        let listener = TcpListener::bind("127.0.0.1:0").await?;
+        let addr = listener.local_addr()?;
+        tokio::spawn(run(listener, Backend::new()));
+
+        let mut victim = TcpStream::connect(addr).await?;
+        let mut killer = TcpStream::connect(addr).await?;
+        roundtrip(&mut victim, b"*2\r\n$4\r\necho\r\n$2\r\nhi\r\n").await?;
+
+        let list = roundtrip(&mut killer, b"*2\r\n$6\r\nclient\r\n$4\r\nlist\r\n").await?;
+        let list = String::from_utf8_lossy(&list);
+        let victim_addr = format!("addr={} ", victim.local_addr()?);
+        let line = list.lines().find(|l| l.contains(&victim_addr)).unwrap();
+        let id = line.split_once("id=").unwrap().1.split(' ').next().unwrap();
+
+        let kill = format!(
+            "*4\r\n$6\r\nclient\r\n$4\r\nkill\r\n$2\r\nid\r\n${}\r\n{}\r\n",
+            id.len(),
+            id
+        );
+        assert_eq!(roundtrip(&mut killer, kill.as_bytes()).await?, b":+1\r\n");
+
+        // the victim's handler is gone, so its socket reads EOF
+        let mut buf = [0u8; 16];
+        let n = tokio::time::timeout(Duration::from_secs(5), victim.read(&mut buf)).await??;
+        assert_eq!(n, 0);
+
+        let addr = killer.local_addr()?.to_string();
+        let kill = format!(
+            "*3\r\n$6\r\nclient\r\n$4\r\nkill\r\n${}\r\n{}\r\n",
+            addr.len(),
+            addr
+        );
+        assert_eq!(roundtrip(&mut killer, kill.as_bytes()).await?, b"+OK\r\n");
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_shutdown_closes_connections() -> anyhow::Result<()> {
+        let backend = Backend::new();
+        let listener = TcpListener::bind("127.0.0.1:0").await?;
+        let addr = listener.local_addr()?;
+        let server = tokio::spawn(run(listener, backend.clone()));
+
+        let mut conn = TcpStream::connect(addr).await?;
+        roundtrip(&mut conn, b"*2\r\n$4\r\necho\r\n$2\r\nhi\r\n").await?;
+        assert_eq!(backend.connections.len(), 1);
+
+        server.abort();
+        backend.shutdown.cancel();
+        backend.connections.close();
+        tokio::time::timeout(Duration::from_secs(5), backend.connections.wait()).await?;
+
+        let mut buf = [0u8; 16];
+        let n = tokio::time::timeout(Duration::from_secs(5), conn.read(&mut buf)).await??;
+        assert_eq!(n, 0);
+        assert!(backend.client_list().is_empty());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_client_ids_are_unique() -> anyhow::Result<()> {
+        let listener = TcpListener::bind("127.0.0.1:0").await?;
+        let addr = listener.local_addr()?;
+        tokio::spawn(run(listener, Backend::new()));
+
+        let mut ids = vec![];
+        for _ in 0..2 {
+            let mut conn = TcpStream::connect(addr).await?;
+            let reply = roundtrip(&mut conn, b"*2\r\n$6\r\nclient\r\n$2\r\nid\r\n").await?;
+            let reply = String::from_utf8(reply)?;
+            let id: u64 = reply.trim_start_matches(":+").trim_end().parse()?;
+            // asking twice on the same connection gives the same id
+            let again = roundtrip(&mut conn, b"*2\r\n$6\r\nclient\r\n$2\r\nid\r\n").await?;
+            assert_eq!(again, reply.as_bytes());
+            ids.push(id);
+        }
+        assert!(ids[0] > 0);
+        assert!(ids[1] > ids[0]);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_pipelined_replies_cross_flush_threshold() -> anyhow::Result<()> {
+        let backend = Backend::with_config(ServerConfig {
+            write_flush_threshold: 1024,
+            ..Default::default()
+        });
+        let listener = TcpListener::bind("127.0.0.1:0").await?;
+        let addr = listener.local_addr()?;
+        tokio::spawn(run(listener, backend));
+
+        let (mut reader, mut writer) = TcpStream::connect(addr).await?.into_split();
+        let count = 2000;
+        let mut pipeline = vec![];
+        let mut expected = vec![];
+        for i in 0..count {
+            let msg = format!("message-{:05}", i);
+            pipeline.extend(format!("*2\r\n$4\r\necho\r\n${}\r\n{}\r\n", msg.len(), msg).bytes());
+            expected.extend(format!("+{}\r\n", msg).bytes());
+        }
+        // write from another task so neither side blocks on a full socket buffer
+        let write = tokio::spawn(async move { writer.write_all(&pipeline).await });
+
+        let mut received = vec![0u8; expected.len()];
+        tokio::time::timeout(Duration::from_secs(10), reader.read_exact(&mut received)).await??;
+        write.await??;
+        assert_eq!(received, expected);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_get_miss_encodes_nil_per_version() -> anyhow::Result<()> {
+        let backend = Backend::new();
+        for (version, nil) in [
+            (RespVersion::Resp2, &b"$-1\r\n"[..]),
+            (RespVersion::Resp3, &b"_\r\n"[..]),
+        ] {
+            let mut codec = RespFrameCodec {
+                lenient_newlines: false,
+                resync_on_error: false,
+                max_command_args: 1024,
+                version,
+            };
+            let request = RedisRequest {
+                frame: command(&["get", "missing"]),
+                backend: backend.clone(),
+            };
+            let response = request_handler(request, &mut Session::default()).await?;
+            let mut buf = BytesMut::new();
+            codec.encode(response.frame, &mut buf)?;
+            assert_eq!(buf.as_ref(), nil);
+        }
+        Ok(())
+    }
+}
@@ -2,9 +2,11 @@ use std::ops::Deref;
 
 use bytes::{Buf, BytesMut};
 
-use crate::{calc_total_length, parse_length, RespDecode, RespEncode, RespError, RespFrame};
+use crate::{
+    calc_total_length, line_len, parse_length, RespDecode, RespEncode, RespError, RespFrame,
+};
 
-use super::{BUF_CAP, CRLF_LEN};
+use super::{ARRAY_PREALLOC, BUF_CAP};
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct RespArray(pub(crate) Option<Vec<RespFrame>>);
@@ -16,15 +18,19 @@ pub struct RespArray(pub(crate) Option<Vec<RespFrame>>);
 
 impl RespEncode for RespArray {
     fn encode(self) -> Vec<u8> {
+        let mut buf = Vec::with_capacity(BUF_CAP);
+        self.encode_to(&mut buf);
+        buf
+    }
+
+    fn encode_to(self, buf: &mut Vec<u8>) {
         match self.0 {
-            None => b"*-1\r\n".to_vec(),
+            None => buf.extend_from_slice(b"*-1\r\n"),
             Some(frames) => {
-                let mut buf = Vec::with_capacity(BUF_CAP);
                 buf.extend_from_slice(format!("*{}\r\n", frames.len()).as_bytes());
                 for frame in frames {
-                    buf.extend_from_slice(&frame.encode());
+                    frame.encode_to(buf);
                 }
-                buf
             }
         }
     }
@@ -39,9 +45,9 @@ impl RespDecode for RespArray {
         if buf.len() < total_len {
             return Err(RespError::NotComplete);
         }
-        buf.advance(end + CRLF_LEN);
+        buf.advance(line_len(buf, end));
 
-        let mut frames = Vec::with_capacity(len);
+        let mut frames = Vec::with_capacity(len.min(ARRAY_PREALLOC));
         for _ in 0..len {
             frames.push(RespFrame::decode(buf)?);
         }
@@ -144,4 +150,29 @@ mod tests {
     //     let frame: RespFrame = RespNullArray.into();
     //     assert_eq!(frame.encode(), b"*-1\r\n");
     // }
+
+    #[test]
+    fn test_deep_array_encode_to() {
+        // 100 层嵌套, 每层 100 个元素, 逐个元素拼出期望的字节
+        let mut frame: RespFrame = BulkString::new("leaf").into();
+        let mut expected = b"$4\r\nleaf\r\n".to_vec();
+        for _ in 0..100 {
+            let mut frames = vec![frame];
+            frames.extend((0..99).map(RespFrame::Integer));
+            frame = RespArray::new(frames).into();
+
+            let mut level = b"*100\r\n".to_vec();
+            level.extend_from_slice(&expected);
+            for i in 0..99 {
+                level.extend_from_slice(format!(":+{}\r\n", i).as_bytes());
+            }
+            expected = level;
+        }
+
+        assert_eq!(frame.clone().encode(), expected);
+        let mut buf = b"+OK\r\n".to_vec();
+        frame.encode_to(&mut buf);
+        assert_eq!(&buf[..5], b"+OK\r\n");
+        assert_eq!(&buf[5..], expected);
+    }
 }
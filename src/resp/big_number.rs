@@ -0,0 +1,121 @@
+use std::ops::Deref;
+
+use bytes::BytesMut;
+
+use crate::{extract_simaple_frame_data, line_len, RespDecode, RespEncode, RespError};
+
+/// RESP3 big number, kept as its decimal digits since it may not fit any integer type
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BigNumber(pub(crate) String);
+
+// - big number: "(3492890328409238509324850943850943825024385\r\n"
+impl RespEncode for BigNumber {
+    fn encode(self) -> Vec<u8> {
+        format!("({}\r\n", self.0).into_bytes()
+    }
+}
+
+impl RespDecode for BigNumber {
+    const PREFIX: &'static str = "(";
+    fn decode(buf: &mut BytesMut) -> Result<Self, RespError> {
+        let end = extract_simaple_frame_data(buf, Self::PREFIX)?;
+        let data = buf.split_to(line_len(buf, end));
+        let s = String::from_utf8_lossy(&data[Self::PREFIX.len()..end]);
+        BigNumber::new(s)
+    }
+    fn expect_length(buf: &[u8]) -> Result<usize, RespError> {
+        let end = extract_simaple_frame_data(buf, Self::PREFIX)?;
+        Ok(line_len(buf, end))
+    }
+}
+
+/// An optionally signed, non-empty run of decimal digits
+pub(crate) fn is_big_number(s: &str) -> bool {
+    let digits = s.strip_prefix(['+', '-']).unwrap_or(s);
+    !digits.is_empty() && digits.bytes().all(|b| b.is_ascii_digit())
+}
+
+impl Deref for BigNumber {
+    type Target = String;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl BigNumber {
+    /// Fails unless `s` is an optionally signed run of decimal digits, the only thing RESP3
+    /// allows after `(`
+    pub fn new(s: impl Into<String>) -> Result<Self, RespError> {
+        let s = s.into();
+        if !is_big_number(&s) {
+            return Err(RespError::InvalidFrame(format!(
+                "invalid big number: {}",
+                s
+            )));
+        }
+        Ok(BigNumber(s))
+    }
+}
+
+impl From<i128> for BigNumber {
+    fn from(n: i128) -> Self {
+        BigNumber(n.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::RespFrame;
+
+    use super::*;
+
+    #[test]
+    fn test_big_number_decode() {
+        let mut buf = BytesMut::from("(3492890328409238509324850943850943825024385\r\n");
+        assert_eq!(BigNumber::expect_length(&buf).unwrap(), buf.len());
+        let frame = BigNumber::decode(&mut buf).unwrap();
+        assert_eq!(
+            frame,
+            BigNumber::new("3492890328409238509324850943850943825024385").unwrap()
+        );
+
+        let mut buf = BytesMut::from("(-12\r\n");
+        assert_eq!(
+            BigNumber::decode(&mut buf).unwrap(),
+            BigNumber::new("-12").unwrap()
+        );
+
+        for bad in ["(\r\n", "(-\r\n", "(12a\r\n", "(1.5\r\n"] {
+            let mut buf = BytesMut::from(bad);
+            assert!(BigNumber::decode(&mut buf).is_err(), "{:?}", bad);
+        }
+    }
+
+    #[test]
+    fn test_big_number_round_trip() {
+        for s in [
+            "0",
+            "-1",
+            "+42",
+            "3492890328409238509324850943850943825024385",
+        ] {
+            let frame: RespFrame = BigNumber::new(s).unwrap().into();
+            let encoded = frame.clone().encode();
+            assert_eq!(encoded, format!("({}\r\n", s).into_bytes());
+            let mut buf = BytesMut::from(encoded.as_slice());
+            assert_eq!(RespFrame::decode(&mut buf).unwrap(), frame);
+        }
+    }
+
+    #[test]
+    fn test_big_number_new_validates() {
+        for bad in ["", "-", "12a", "1.5", "1\r\n:2"] {
+            assert!(BigNumber::new(bad).is_err(), "{:?}", bad);
+        }
+        assert_eq!(
+            BigNumber::from(-170141183460469231731687303715884105728i128),
+            BigNumber::new("-170141183460469231731687303715884105728").unwrap()
+        );
+    }
+}
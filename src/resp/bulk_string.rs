@@ -2,9 +2,9 @@ use std::ops::Deref;
 
 use bytes::{Buf, BytesMut};
 
-use crate::{parse_length, RespDecode, RespEncode, RespError};
+use crate::{line_len, parse_length, RespDecode, RespEncode, RespError};
 
-use super::CRLF_LEN;
+use super::{lenient_newlines, CRLF_LEN};
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct BulkString(pub(crate) Option<Vec<u8>>);
@@ -50,17 +50,26 @@ impl RespDecode for BulkString {
     const PREFIX: &'static str = "$";
     fn decode(buf: &mut BytesMut) -> Result<Self, RespError> {
         let (end, len) = parse_length(buf, Self::PREFIX)?;
-        let remained = &buf[end + CRLF_LEN..];
-        if remained.len() < len + CRLF_LEN {
-            return Err(RespError::NotComplete);
-        }
-        buf.advance(end + CRLF_LEN);
-        let data = buf.split_to(len + CRLF_LEN);
+        let start = line_len(buf, end);
+        let total = payload_len(&buf[start..], len).ok_or(RespError::NotComplete)?;
+        buf.advance(start);
+        let data = buf.split_to(total);
         Ok(BulkString::new(data[..len].to_vec()))
     }
     fn expect_length(buf: &[u8]) -> Result<usize, RespError> {
         let (end, len) = parse_length(buf, Self::PREFIX)?;
-        Ok(end + CRLF_LEN + len + CRLF_LEN)
+        let start = line_len(buf, end);
+        let total = payload_len(&buf[start..], len).unwrap_or(len + CRLF_LEN);
+        Ok(start + total)
+    }
+}
+
+// 数据加上结束符的长度, 宽松模式下数据之后可以只有 "\n"
+fn payload_len(remained: &[u8], len: usize) -> Option<usize> {
+    match remained.get(len) {
+        Some(b'\n') if lenient_newlines() => Some(len + 1),
+        Some(_) if remained.len() >= len + CRLF_LEN => Some(len + CRLF_LEN),
+        _ => None,
     }
 }
 
@@ -1,21 +1,27 @@
 use bytes::BytesMut;
 
-use crate::{extract_simaple_frame_data, RespDecode, RespEncode, RespError};
-
-use super::CRLF_LEN;
+use crate::{extract_simaple_frame_data, line_len, RespDecode, RespEncode, RespError};
 
 // double: ",[<+|->]<integral>[.<fractional>][<E|e>[sign]<exponent>]\r\n"
 impl RespEncode for f64 {
     fn encode(self) -> Vec<u8> {
-        let mut buf = Vec::with_capacity(32);
-        let ret = if self.abs() > 1e+8 || self.abs() < 1e-8 {
-            format!(",{:+e}\r\n", self)
+        // 符号单独输出: 正数带 +, 负数只有一个 -
+        let sign = if self < 0.0 { "-" } else { "+" };
+        let abs = self.abs();
+        // rust 的 Display 本身就是能精确 round-trip 的最短表示, 指数形式的阈值和 %.17g 一致
+        let ret = if self == 0.0 {
+            // 0.0 和 -0.0 都按 redis 的做法输出不带符号的 0
+            ",0\r\n".to_string()
+        } else if self.is_nan() {
+            ",nan\r\n".to_string()
+        } else if self.is_infinite() {
+            format!(",{}inf\r\n", if self < 0.0 { "-" } else { "" })
+        } else if !(1e-4..1e17).contains(&abs) {
+            format!(",{}{:e}\r\n", sign, abs)
         } else {
-            let sign = if self < 0.0 { "" } else { "+" };
-            format!(",{}{}\r\n", sign, self)
+            format!(",{}{}\r\n", sign, abs)
         };
-        buf.extend_from_slice(&ret.into_bytes());
-        buf
+        ret.into_bytes()
     }
 }
 
@@ -24,13 +30,13 @@ impl RespDecode for f64 {
     const PREFIX: &'static str = ",";
     fn decode(buf: &mut BytesMut) -> Result<Self, RespError> {
         let end = extract_simaple_frame_data(buf, Self::PREFIX)?;
-        let data = buf.split_to(end + CRLF_LEN);
+        let data = buf.split_to(line_len(buf, end));
         let s = String::from_utf8_lossy(&data[Self::PREFIX.len()..end]);
         Ok(s.parse()?)
     }
     fn expect_length(buf: &[u8]) -> Result<usize, RespError> {
         let end = extract_simaple_frame_data(buf, Self::PREFIX)?;
-        Ok(end + CRLF_LEN)
+        Ok(line_len(buf, end))
     }
 }
 
@@ -64,10 +70,47 @@ mod tests {
         let frame: RespFrame = (-123.456).into();
         assert_eq!(frame.encode(), b",-123.456\r\n");
 
+        let frame: RespFrame = 3.0.into();
+        assert_eq!(frame.encode(), b",+3\r\n");
+
         let frame: RespFrame = 1.23456e+8.into();
-        assert_eq!(frame.encode(), b",+1.23456e8\r\n");
+        assert_eq!(frame.encode(), b",+123456000\r\n");
+
+        let frame: RespFrame = 1.5e+20.into();
+        assert_eq!(frame.encode(), b",+1.5e20\r\n");
 
         let frame: RespFrame = (-1.23456e-9).into();
         assert_eq!(frame.encode(), b",-1.23456e-9\r\n");
+
+        let frame: RespFrame = (-1e-12).into();
+        assert_eq!(frame.encode(), b",-1e-12\r\n");
+
+        let frame: RespFrame = f64::NEG_INFINITY.into();
+        assert_eq!(frame.encode(), b",-inf\r\n");
+    }
+
+    #[test]
+    fn test_double_zero_and_tiny() {
+        let frame: RespFrame = 0.0.into();
+        assert_eq!(frame.encode(), b",0\r\n");
+
+        let frame: RespFrame = (-0.0).into();
+        assert_eq!(frame.encode(), b",0\r\n");
+
+        let frame: RespFrame = 1e-12.into();
+        assert_eq!(frame.encode(), b",+1e-12\r\n");
+
+        let mut buf = BytesMut::from(1e-12.encode().as_slice());
+        assert_eq!(f64::decode(&mut buf).unwrap(), 1e-12);
+    }
+
+    #[test]
+    fn test_double_round_trip() {
+        for value in [3.0, 0.1, 1.0 / 3.0, -2.5e-7, 1e300] {
+            let mut buf = BytesMut::from(value.encode().as_slice());
+            assert_eq!(f64::decode(&mut buf).unwrap(), value);
+        }
+        assert_eq!(0.1.encode(), b",+0.1\r\n");
+        assert_eq!((1.0 / 3.0).encode(), b",+0.3333333333333333\r\n");
     }
 }
@@ -2,8 +2,8 @@ use bytes::BytesMut;
 use enum_dispatch::enum_dispatch;
 
 use crate::{
-    BulkString, RespArray, RespDecode, RespError, RespMap, RespNull, RespSet, SimpleError,
-    SimpleString,
+    BigNumber, BulkString, RespArray, RespDecode, RespError, RespMap, RespNull, RespSet,
+    SimpleError, SimpleString,
 };
 
 #[enum_dispatch(RespEncode)]
@@ -21,6 +21,7 @@ pub enum RespFrame {
     Double(f64),
     Map(RespMap),
     Set(RespSet),
+    BigNumber(BigNumber),
 }
 
 impl RespDecode for RespFrame {
@@ -78,6 +79,10 @@ impl RespDecode for RespFrame {
                 let frame = RespSet::decode(buf)?;
                 Ok(frame.into())
             }
+            Some(b'(') => {
+                let frame = BigNumber::decode(buf)?;
+                Ok(frame.into())
+            }
             None => Err(RespError::NotComplete),
             _ => Err(RespError::InvalidFrameType(format!(
                 "expect_length: unknown frame type: {:?}",
@@ -99,11 +104,77 @@ impl RespDecode for RespFrame {
             Some(b'#') => bool::expect_length(buf),
             Some(b',') => f64::expect_length(buf),
             Some(b'_') => RespNull::expect_length(buf),
+            Some(b'(') => BigNumber::expect_length(buf),
             _ => Err(RespError::NotComplete),
         }
     }
 }
 
+impl RespFrame {
+    /// RESP2 has no map, set, boolean, double or null types, so rewrite them (recursively) into the
+    /// RESP2 replies Redis uses for them: flat arrays, integers and bulk strings
+    pub fn into_resp2(self) -> RespFrame {
+        match self {
+            RespFrame::Map(map) => {
+                let mut frames = Vec::with_capacity(map.len() * 2);
+                for (key, value) in map.0 {
+                    frames.push(BulkString::new(key).into());
+                    frames.push(value.into_resp2());
+                }
+                RespArray::new(frames).into()
+            }
+            RespFrame::Set(set) => RespArray::new(
+                set.0
+                    .into_iter()
+                    .map(RespFrame::into_resp2)
+                    .collect::<Vec<_>>(),
+            )
+            .into(),
+            RespFrame::Array(RespArray(Some(frames))) => RespArray::new(
+                frames
+                    .into_iter()
+                    .map(RespFrame::into_resp2)
+                    .collect::<Vec<_>>(),
+            )
+            .into(),
+            RespFrame::Boolean(b) => RespFrame::Integer(b as i64),
+            RespFrame::Double(d) => BulkString::new(d.to_string()).into(),
+            RespFrame::BigNumber(n) => BulkString::new(n.0).into(),
+            // RESP2 has no null type, nil is spelled as a null bulk string
+            RespFrame::Null(_) => BulkString(None).into(),
+            frame => frame,
+        }
+    }
+
+    /// Rough number of payload bytes held by the frame, excluding the frame itself
+    pub fn memory_estimate(&self) -> usize {
+        match self {
+            RespFrame::SimpleString(s) => s.len(),
+            RespFrame::Error(e) => e.len(),
+            RespFrame::BigNumber(n) => n.len(),
+            RespFrame::BulkString(s) => s.0.as_ref().map_or(0, |s| s.len()),
+            RespFrame::Array(array) => array.0.as_ref().map_or(0, |frames| {
+                frames
+                    .iter()
+                    .map(|f| std::mem::size_of::<RespFrame>() + f.memory_estimate())
+                    .sum()
+            }),
+            RespFrame::Map(map) => map
+                .iter()
+                .map(|(k, v)| k.len() + std::mem::size_of::<RespFrame>() + v.memory_estimate())
+                .sum(),
+            RespFrame::Set(set) => set
+                .iter()
+                .map(|f| std::mem::size_of::<RespFrame>() + f.memory_estimate())
+                .sum(),
+            RespFrame::Integer(_)
+            | RespFrame::Null(_)
+            | RespFrame::Boolean(_)
+            | RespFrame::Double(_) => 0,
+        }
+    }
+}
+
 impl From<&str> for RespFrame {
     fn from(s: &str) -> Self {
         SimpleString(s.to_string()).into()
@@ -1,14 +1,31 @@
+use std::num::{IntErrorKind, ParseIntError};
+
 use bytes::BytesMut;
+use lazy_static::lazy_static;
+
+use crate::{extract_simaple_frame_data, line_len, RespDecode, RespEncode, RespError};
 
-use crate::{extract_simaple_frame_data, RespDecode, RespEncode, RespError};
+const SHARED_MIN: i64 = -1;
+const SHARED_MAX: i64 = 255;
+
+lazy_static! {
+    // 常用的小整数预先编码好, 类似 redis 的 shared integers
+    static ref SHARED_INTEGERS: Vec<Vec<u8>> =
+        (SHARED_MIN..=SHARED_MAX).map(encode_integer).collect();
+}
 
-use super::CRLF_LEN;
+fn encode_integer(value: i64) -> Vec<u8> {
+    let sign = if value < 0 { "" } else { "+" };
+    format!(":{}{}\r\n", sign, value).into_bytes()
+}
 
 // integer: ":[<+|->]<value>\r\n"
 impl RespEncode for i64 {
     fn encode(self) -> Vec<u8> {
-        let sign = if self < 0 { "" } else { "+" };
-        format!(":{}{}\r\n", sign, self).into_bytes()
+        if (SHARED_MIN..=SHARED_MAX).contains(&self) {
+            return SHARED_INTEGERS[(self - SHARED_MIN) as usize].clone();
+        }
+        encode_integer(self)
     }
 }
 
@@ -16,13 +33,21 @@ impl RespDecode for i64 {
     const PREFIX: &'static str = ":";
     fn decode(buf: &mut BytesMut) -> Result<Self, RespError> {
         let end = extract_simaple_frame_data(buf, Self::PREFIX)?;
-        let data = buf.split_to(end + 2);
+        let data = buf.split_to(line_len(buf, end));
         let s = String::from_utf8_lossy(&data[1..end]);
-        Ok(s.parse()?)
+        if s.is_empty() {
+            return Err(RespError::InvalidFrame("empty integer".to_string()));
+        }
+        s.parse().map_err(|e: ParseIntError| match e.kind() {
+            IntErrorKind::PosOverflow | IntErrorKind::NegOverflow => {
+                RespError::InvalidFrame(format!("integer out of range: {}", s))
+            }
+            _ => e.into(),
+        })
     }
     fn expect_length(buf: &[u8]) -> Result<usize, RespError> {
         let end = extract_simaple_frame_data(buf, Self::PREFIX)?;
-        Ok(end + CRLF_LEN)
+        Ok(line_len(buf, end))
     }
 }
 
@@ -44,6 +69,31 @@ mod tests {
         assert_eq!(frame, -123);
     }
 
+    #[test]
+    fn test_integer_decode_edge_cases() {
+        let mut buf = BytesMut::from(":9223372036854775807\r\n");
+        assert_eq!(i64::decode(&mut buf).unwrap(), i64::MAX);
+
+        let mut buf = BytesMut::from(":-9223372036854775808\r\n");
+        assert_eq!(i64::decode(&mut buf).unwrap(), i64::MIN);
+
+        let mut buf = BytesMut::from(":-42\r\n");
+        assert_eq!(i64::expect_length(&buf).unwrap(), 6);
+        assert_eq!(i64::decode(&mut buf).unwrap(), -42);
+
+        let mut buf = BytesMut::from(":9223372036854775808\r\n");
+        assert_eq!(
+            i64::decode(&mut buf).unwrap_err(),
+            RespError::InvalidFrame("integer out of range: 9223372036854775808".to_string())
+        );
+
+        let mut buf = BytesMut::from(":\r\n");
+        assert_eq!(
+            i64::decode(&mut buf).unwrap_err(),
+            RespError::InvalidFrame("empty integer".to_string())
+        );
+    }
+
     #[test]
     fn test_integer() {
         let frame: RespFrame = 123.into();
@@ -52,4 +102,27 @@ mod tests {
         let frame: RespFrame = (-123).into();
         assert_eq!(frame.encode(), b":-123\r\n");
     }
+
+    #[test]
+    fn test_shared_integer_encoding() {
+        for value in [
+            i64::MIN,
+            -1000,
+            SHARED_MIN - 1,
+            SHARED_MIN,
+            0,
+            1,
+            SHARED_MAX - 1,
+            SHARED_MAX,
+            SHARED_MAX + 1,
+            1000,
+            i64::MAX,
+        ] {
+            assert_eq!(value.encode(), encode_integer(value), "value {}", value);
+        }
+        assert_eq!((-1).encode(), b":-1\r\n");
+        assert_eq!(0.encode(), b":+0\r\n");
+        assert_eq!(255.encode(), b":+255\r\n");
+        assert_eq!(256.encode(), b":+256\r\n");
+    }
 }
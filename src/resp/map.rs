@@ -6,10 +6,11 @@ use std::{
 use bytes::{Buf, BytesMut};
 
 use crate::{
-    calc_total_length, parse_length, RespDecode, RespEncode, RespError, RespFrame, SimpleString,
+    calc_total_length, line_len, parse_length, RespDecode, RespEncode, RespError, RespFrame,
+    SimpleString,
 };
 
-use super::{BUF_CAP, CRLF_LEN};
+use super::BUF_CAP;
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct RespMap(pub(crate) HashMap<String, RespFrame>);
@@ -18,12 +19,16 @@ pub struct RespMap(pub(crate) HashMap<String, RespFrame>);
 impl RespEncode for RespMap {
     fn encode(self) -> Vec<u8> {
         let mut buf = Vec::with_capacity(BUF_CAP);
-        buf.extend_from_slice(&format!("%{}\r\n", self.len()).into_bytes());
+        self.encode_to(&mut buf);
+        buf
+    }
+
+    fn encode_to(self, buf: &mut Vec<u8>) {
+        buf.extend_from_slice(format!("%{}\r\n", self.len()).as_bytes());
         for (key, value) in self.0 {
-            buf.extend_from_slice(&SimpleString::new(key).encode());
-            buf.extend_from_slice(&value.encode());
+            SimpleString::new(key).encode_to(buf);
+            value.encode_to(buf);
         }
-        buf
     }
 }
 
@@ -37,13 +42,16 @@ impl RespDecode for RespMap {
         if buf.len() < total_len {
             return Err(RespError::NotComplete);
         }
-        buf.advance(end + CRLF_LEN);
+        buf.advance(line_len(buf, end));
 
         let mut map = RespMap::new();
         for _ in 0..len {
             let key = SimpleString::decode(buf)?;
             let value = RespFrame::decode(buf)?;
-            map.insert(key.0, value);
+            // 重复的 key 说明输入有问题, 不能悄悄用后面的值覆盖
+            if map.insert(key.0, value).is_some() {
+                return Err(RespError::InvalidFrame("duplicate map key".to_string()));
+            }
         }
         Ok(map)
     }
@@ -123,4 +131,13 @@ mod tests {
         assert!(frame_res.contains("+name\r\n$5\r\nAlice\r\n"));
         assert!(frame_res.contains("+age\r\n,-18.21\r\n"));
     }
+
+    #[test]
+    fn test_map_decode_duplicate_key() {
+        let mut buf = BytesMut::from("%2\r\n+a\r\n:1\r\n+a\r\n:2\r\n");
+        assert_eq!(
+            RespMap::decode(&mut buf),
+            Err(RespError::InvalidFrame("duplicate map key".to_string()))
+        );
+    }
 }
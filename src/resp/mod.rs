@@ -1,4 +1,5 @@
 mod array;
+mod big_number;
 mod bool;
 mod bulk_string;
 mod double;
@@ -10,22 +11,49 @@ mod set;
 mod simple_error;
 mod simple_string;
 
+use std::cell::Cell;
+
 use bytes::{Buf, BytesMut};
 use enum_dispatch::enum_dispatch;
 use thiserror::Error;
 
+pub(crate) use self::big_number::is_big_number;
 pub use self::{
-    array::RespArray, bulk_string::BulkString, frame::RespFrame, map::RespMap, null::RespNull,
-    set::RespSet, simple_error::SimpleError, simple_string::SimpleString,
+    array::RespArray, big_number::BigNumber, bulk_string::BulkString, frame::RespFrame,
+    map::RespMap, null::RespNull, set::RespSet, simple_error::SimpleError,
+    simple_string::SimpleString,
 };
 
 const CRLF: &[u8] = b"\r\n";
 const CRLF_LEN: usize = CRLF.len();
 const BUF_CAP: usize = 4096;
+// 数组头里的长度来自客户端, 不可信, 解码时最多预分配这么多个元素
+pub(crate) const ARRAY_PREALLOC: usize = 1024;
+
+thread_local! {
+    static LENIENT_NEWLINES: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Protocol version negotiated by a connection through HELLO, RESP2 until told otherwise
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum RespVersion {
+    #[default]
+    Resp2,
+    Resp3,
+}
 
 #[enum_dispatch]
 pub trait RespEncode {
     fn encode(self) -> Vec<u8>;
+
+    /// Append the encoded frame to `buf`. Aggregates override this so nested frames are written
+    /// straight into one buffer instead of through a temporary `Vec` per element.
+    fn encode_to(self, buf: &mut Vec<u8>)
+    where
+        Self: Sized,
+    {
+        buf.extend_from_slice(&self.encode());
+    }
 }
 
 pub trait RespDecode: Sized {
@@ -63,17 +91,7 @@ pub fn extract_simaple_frame_data(buf: &[u8], prefix: &str) -> Result<usize, Res
             prefix, buf
         )));
     }
-    let mut end = 0;
-    for i in 0..buf.len() - 1 {
-        if buf[i] == b'\r' && buf[i + 1] == b'\n' {
-            end = i;
-            break;
-        }
-    }
-    if end == 0 {
-        return Err(RespError::NotComplete);
-    }
-    Ok(end)
+    find_ctrl(buf, 1).ok_or(RespError::NotComplete)
 }
 
 pub fn extract_fixed_data(
@@ -95,18 +113,56 @@ pub fn extract_fixed_data(
 }
 
 pub fn find_ctrl(buf: &[u8], nth: usize) -> Option<usize> {
+    let lenient = lenient_newlines();
     let mut count = 0;
-    for i in 0..buf.len() - 1 {
-        if buf[i] == b'\r' && buf[i + 1] == b'\n' {
+    let mut i = 0;
+    while i < buf.len() {
+        let found = if buf[i] == b'\r' && buf.get(i + 1) == Some(&b'\n') {
+            true
+        } else {
+            lenient && buf[i] == b'\n'
+        };
+        if found {
             count += 1;
             if count == nth {
                 return Some(i);
             }
+            if buf[i] == b'\r' {
+                i += 1;
+            }
         }
+        i += 1;
     }
     None
 }
 
+// 计算行结束符之后的位置, 宽松模式下行可能只以 "\n" 结尾
+pub fn line_len(buf: &[u8], end: usize) -> usize {
+    if buf[end] == b'\n' {
+        end + 1
+    } else {
+        end + CRLF_LEN
+    }
+}
+
+/// Run `f` with the decoders on the current thread accepting a bare `\n` as a line
+/// terminator. Strict `\r\n` parsing is the default and is restored afterwards.
+pub fn with_lenient_newlines<T>(lenient: bool, f: impl FnOnce() -> T) -> T {
+    struct Restore(bool);
+    impl Drop for Restore {
+        fn drop(&mut self) {
+            LENIENT_NEWLINES.with(|l| l.set(self.0));
+        }
+    }
+
+    let _restore = Restore(LENIENT_NEWLINES.with(|l| l.replace(lenient)));
+    f()
+}
+
+pub(crate) fn lenient_newlines() -> bool {
+    LENIENT_NEWLINES.with(|l| l.get())
+}
+
 // 计算结束位置以及获取长度信息
 pub fn parse_length(buf: &[u8], prefix: &str) -> Result<(usize, usize), RespError> {
     let end = extract_simaple_frame_data(buf, prefix)?;
@@ -121,7 +177,7 @@ pub fn calc_total_length(
     len: usize,
     prefix: &str,
 ) -> Result<usize, RespError> {
-    let mut total = end + CRLF_LEN;
+    let mut total = line_len(buf, end);
     let mut data = &buf[total..];
     match prefix {
         "*" | "~" => {
@@ -2,9 +2,11 @@ use std::ops::Deref;
 
 use bytes::{Buf, BytesMut};
 
-use crate::{calc_total_length, parse_length, RespDecode, RespEncode, RespError, RespFrame};
+use crate::{
+    calc_total_length, line_len, parse_length, RespDecode, RespEncode, RespError, RespFrame,
+};
 
-use super::{BUF_CAP, CRLF_LEN};
+use super::BUF_CAP;
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct RespSet(pub(crate) Vec<RespFrame>);
@@ -12,11 +14,15 @@ pub struct RespSet(pub(crate) Vec<RespFrame>);
 impl RespEncode for RespSet {
     fn encode(self) -> Vec<u8> {
         let mut buf = Vec::with_capacity(BUF_CAP);
-        buf.extend_from_slice(&format!("~{}\r\n", self.len()).into_bytes());
+        self.encode_to(&mut buf);
+        buf
+    }
+
+    fn encode_to(self, buf: &mut Vec<u8>) {
+        buf.extend_from_slice(format!("~{}\r\n", self.len()).as_bytes());
         for frame in self.0 {
-            buf.extend_from_slice(&frame.encode());
+            frame.encode_to(buf);
         }
-        buf
     }
 }
 
@@ -30,7 +36,7 @@ impl RespDecode for RespSet {
         if buf.len() < total_len {
             return Err(RespError::NotComplete);
         }
-        buf.advance(end + CRLF_LEN);
+        buf.advance(line_len(buf, end));
 
         let mut set = Vec::new();
         for _ in 0..len {
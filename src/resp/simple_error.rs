@@ -2,9 +2,7 @@ use std::ops::Deref;
 
 use bytes::BytesMut;
 
-use crate::{extract_simaple_frame_data, RespDecode, RespEncode, RespError};
-
-use super::CRLF_LEN;
+use crate::{extract_simaple_frame_data, line_len, RespDecode, RespEncode, RespError};
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct SimpleError(pub(crate) String);
@@ -21,13 +19,13 @@ impl RespDecode for SimpleError {
     fn decode(buf: &mut BytesMut) -> Result<Self, RespError> {
         let end = extract_simaple_frame_data(buf, Self::PREFIX)?;
 
-        let data = buf.split_to(end + 2);
+        let data = buf.split_to(line_len(buf, end));
         let s = String::from_utf8_lossy(&data[1..end]);
         Ok(SimpleError::new(s.to_string()))
     }
     fn expect_length(buf: &[u8]) -> Result<usize, RespError> {
         let end = extract_simaple_frame_data(buf, Self::PREFIX)?;
-        Ok(end + CRLF_LEN)
+        Ok(line_len(buf, end))
     }
 }
 impl Deref for SimpleError {
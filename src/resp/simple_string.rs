@@ -2,9 +2,7 @@ use std::ops::Deref;
 
 use bytes::BytesMut;
 
-use crate::{extract_simaple_frame_data, RespDecode, RespEncode, RespError};
-
-use super::CRLF_LEN;
+use crate::{extract_simaple_frame_data, line_len, RespDecode, RespEncode, RespError};
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct SimpleString(pub(crate) String);
@@ -20,13 +18,13 @@ impl RespDecode for SimpleString {
     const PREFIX: &'static str = "+";
     fn decode(buf: &mut BytesMut) -> Result<Self, RespError> {
         let end = extract_simaple_frame_data(buf, Self::PREFIX)?;
-        let data = buf.split_to(end + 2);
+        let data = buf.split_to(line_len(buf, end));
         let s = String::from_utf8_lossy(&data[Self::PREFIX.len()..end]);
         Ok(SimpleString::new(s.to_string()))
     }
     fn expect_length(buf: &[u8]) -> Result<usize, RespError> {
         let end = extract_simaple_frame_data(buf, Self::PREFIX)?;
-        Ok(end + CRLF_LEN)
+        Ok(line_len(buf, end))
     }
 }
 
@@ -78,6 +76,17 @@ mod tests {
         assert_eq!(frame, SimpleString::new("hello".to_string()));
     }
 
+    #[test]
+    fn test_simple_string_decode_lenient_newline() {
+        let mut buf = BytesMut::from("+OK\n");
+        let ret = SimpleString::decode(&mut buf);
+        assert_eq!(ret.unwrap_err(), RespError::NotComplete);
+
+        let frame = crate::with_lenient_newlines(true, || SimpleString::decode(&mut buf)).unwrap();
+        assert_eq!(frame, SimpleString::new("OK".to_string()));
+        assert!(buf.is_empty());
+    }
+
     #[test]
     fn test_simple_string() {
         let frame: RespFrame = SimpleString::new("OK".to_string()).into();
@@ -172,19 +172,119 @@ mod tests {
         assert_eq!(frame, RespFrame::Double(3.12));
     }
 
+    #[test]
+    fn respv2_double_round_trip_should_work() {
+        use crate::RespEncode;
+
+        for value in [3.0, 0.1, 1.0 / 3.0, -2.5e-7, 1e300, f64::INFINITY] {
+            let mut buf = BytesMut::from(RespFrame::Double(value).encode().as_slice());
+            let frame = RespFrame::decode(&mut buf).unwrap();
+            assert_eq!(frame, RespFrame::Double(value));
+        }
+    }
+
+    #[test]
+    fn respv2_big_number_length_should_work() {
+        let buf = b"(3492890328409238509324850943850943825024385\r\n";
+        let len = RespFrame::expect_length(buf).unwrap();
+        assert_eq!(len, buf.len());
+    }
+
+    #[test]
+    fn respv2_big_number_should_work() {
+        use crate::{BigNumber, RespEncode};
+
+        for s in ["3492890328409238509324850943850943825024385", "-17", "+0"] {
+            let frame: RespFrame = BigNumber::new(s).unwrap().into();
+            let mut buf = BytesMut::from(frame.clone().encode().as_slice());
+            assert_eq!(RespFrame::decode(&mut buf).unwrap(), frame);
+        }
+
+        let mut buf = BytesMut::from("*2\r\n(12\r\n:1\r\n");
+        let frame = RespFrame::decode(&mut buf).unwrap();
+        assert_eq!(
+            frame,
+            RespFrame::Array(
+                vec![BigNumber::new("12").unwrap().into(), RespFrame::Integer(1)].into()
+            )
+        );
+
+        let mut buf = BytesMut::from("(12x\r\n");
+        assert!(RespFrame::decode(&mut buf).is_err());
+    }
+
+    #[test]
+    fn respv2_lenient_newline_should_work() {
+        let mut buf = BytesMut::from("+OK\n");
+        let frame = crate::with_lenient_newlines(true, || RespFrame::decode(&mut buf)).unwrap();
+        assert_eq!(frame, RespFrame::SimpleString("OK".into()));
+
+        let mut buf = BytesMut::from("*2\n$3\nget\n$5\nhello\r\n");
+        let frame = crate::with_lenient_newlines(true, || RespFrame::decode(&mut buf)).unwrap();
+        assert_eq!(
+            frame,
+            RespFrame::Array(vec![b"get".into(), b"hello".into()].into())
+        );
+    }
+
+    #[test]
+    fn respv2_strict_newline_should_reject_bare_lf() {
+        let buf = b"+OK\n";
+        let ret = RespFrame::expect_length(buf).unwrap_err();
+        assert_eq!(ret, RespError::NotComplete);
+
+        let mut buf = BytesMut::from("+OK\n");
+        let ret = RespFrame::decode(&mut buf).unwrap_err();
+        assert_eq!(ret, RespError::NotComplete);
+    }
+
     #[test]
     fn respv2_map_length_should_work() {
-        let buf = b"%2\r\n+OK\r\n-ERR\r\n";
+        let buf = b"%1\r\n+OK\r\n-ERR\r\n";
         let len = RespFrame::expect_length(buf).unwrap();
         assert_eq!(len, buf.len());
     }
 
     #[test]
     fn respv2_map_should_work() {
-        let mut buf = BytesMut::from("%2\r\n+OK\r\n-ERR\r\n");
+        let mut buf = BytesMut::from("%1\r\n+OK\r\n-ERR\r\n");
         let frame = RespFrame::decode(&mut buf).unwrap();
         let mut map = HashMap::new();
         map.insert("OK".to_string(), RespFrame::Error("ERR".into()));
         assert_eq!(frame, RespFrame::Map(map.into()));
     }
+
+    #[test]
+    fn respv2_map_non_string_keys_should_work() {
+        let buf = b"%1\r\n$3\r\nkey\r\n:5\r\n";
+        assert_eq!(RespFrame::expect_length(buf).unwrap(), buf.len());
+        let mut buf = BytesMut::from(&buf[..]);
+        let frame = RespFrame::decode(&mut buf).unwrap();
+        let mut map = HashMap::new();
+        map.insert("key".to_string(), RespFrame::Integer(5));
+        assert_eq!(frame, RespFrame::Map(map.into()));
+
+        let mut buf = BytesMut::from("%2\r\n:1\r\n+one\r\n+two\r\n:2\r\n");
+        let RespFrame::Map(map) = RespFrame::decode(&mut buf).unwrap() else {
+            panic!("expected a map");
+        };
+        assert_eq!(map.get("1"), Some(&RespFrame::SimpleString("one".into())));
+        assert_eq!(map.get("two"), Some(&RespFrame::Integer(2)));
+
+        let mut buf = BytesMut::from("%1\r\n*0\r\n:1\r\n");
+        assert!(RespFrame::decode(&mut buf).is_err());
+    }
+
+    #[test]
+    fn respv2_map_duplicate_keys_should_fail() {
+        for input in [
+            "%2\r\n+key\r\n:1\r\n+key\r\n:2\r\n",
+            "%2\r\n+1\r\n:1\r\n$1\r\n1\r\n:2\r\n",
+            "%2\r\n$1\r\n1\r\n:1\r\n:1\r\n:2\r\n",
+            "%2\r\n:1\r\n:1\r\n+1\r\n:2\r\n",
+        ] {
+            let mut buf = BytesMut::from(input);
+            assert!(RespFrame::decode(&mut buf).is_err(), "{:?}", input);
+        }
+    }
 }
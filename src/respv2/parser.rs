@@ -2,14 +2,16 @@ use std::num::NonZeroUsize;
 
 use winnow::{
     ascii::{digit1, float},
-    combinator::{alt, dispatch, fail, opt, preceded, terminated},
+    combinator::{alt, dispatch, fail, opt, terminated},
     error::{ContextError, ErrMode, Needed},
-    token::{any, take, take_until},
+    token::{any, literal, take, take_until},
     PResult, Parser,
 };
 
 use crate::{
-    BulkString, RespArray, RespError, RespFrame, RespMap, RespNull, SimpleError, SimpleString,
+    resp::{is_big_number, lenient_newlines, ARRAY_PREALLOC},
+    BigNumber, BulkString, RespArray, RespError, RespFrame, RespMap, RespNull, SimpleError,
+    SimpleString,
 };
 
 const CRLF: &[u8] = b"\r\n";
@@ -29,7 +31,7 @@ pub fn parse_frame_length(input: &[u8]) -> Result<usize, RespError> {
 }
 
 fn parse_frame_len(input: &mut &[u8]) -> PResult<()> {
-    let mut simple_parser = terminated(take_until(0.., CRLF), CRLF).value(());
+    let mut simple_parser = line.value(());
     dispatch! {any;
         b'+' => simple_parser,
         b'-' => simple_parser,
@@ -39,6 +41,7 @@ fn parse_frame_len(input: &mut &[u8]) -> PResult<()> {
         b'_' => simple_parser,
         b'#' => simple_parser,
         b',' => simple_parser,
+        b'(' => simple_parser,
         b'%' => map_len,
         _v => fail::<_, _, _>
     }
@@ -56,6 +59,7 @@ pub fn parse_frame(input: &mut &[u8]) -> PResult<RespFrame> {
         b'#' => boolean.map(RespFrame::Boolean),
         b',' => decimal.map(RespFrame::Double),
         b'%' => map.map(RespFrame::Map),
+        b'(' => big_number.map(RespFrame::BigNumber),
         _v => fail::<_, _, _>
 
     }
@@ -74,10 +78,19 @@ fn error(input: &mut &[u8]) -> PResult<SimpleError> {
 fn integer(input: &mut &[u8]) -> PResult<i64> {
     let sign = opt(alt(('+', '-'))).parse_next(input)?.unwrap_or('+');
     let sign = if sign == '+' { 1 } else { -1 };
-    let v: i64 = terminated(digit1.parse_to(), CRLF).parse_next(input)?;
+    let v: i64 = terminated(digit1.parse_to(), crlf).parse_next(input)?;
     Ok(sign * v)
 }
 
+// - big number: "(3492890328409238509324850943850943825024385\r\n"
+fn big_number(input: &mut &[u8]) -> PResult<BigNumber> {
+    let s = parse_string(input)?;
+    if !is_big_number(&s) {
+        return Err(err_cur("Invalid big number"));
+    }
+    Ok(BigNumber(s))
+}
+
 // - null bulk string: "$-1\r\n"
 // fn null_bulk_string(input: &mut &[u8]) -> PResult<RespNullBulkString> {
 //     "-1\r\n".value(RespNullBulkString).parse_next(input)
@@ -95,7 +108,7 @@ fn bulk_string(input: &mut &[u8]) -> PResult<BulkString> {
     } else if len < 0 {
         return Err(err_cur("Invalid length"));
     }
-    let data = terminated(take(len as usize), CRLF).parse_next(input)?;
+    let data = terminated(take(len as usize), crlf).parse_next(input)?;
     Ok(BulkString::new(data.to_vec()))
 }
 
@@ -106,12 +119,17 @@ fn bulk_string_len(input: &mut &[u8]) -> PResult<()> {
     } else if len < -1 {
         return Err(err_cur("Invalid length"));
     }
-    let len_with_crlf = len as usize + 2;
+    let len = len as usize;
+    let len_with_crlf = if lenient_newlines() && input.get(len) == Some(&b'\n') {
+        len + 1
+    } else {
+        len + 2
+    };
     if input.len() < len_with_crlf {
-        let size = NonZeroUsize::new((len_with_crlf - input.len()) as usize).unwrap();
+        let size = NonZeroUsize::new(len_with_crlf - input.len()).unwrap();
         return Err(ErrMode::Incomplete(Needed::Size(size)));
     }
-    *input = &input[(len + 2) as usize..];
+    *input = &input[len_with_crlf..];
     Ok(())
     /* terminated(take(len as usize), CRLF)
     .value(())
@@ -135,7 +153,7 @@ fn array(input: &mut &[u8]) -> PResult<RespArray> {
         return Err(err_cur("Invalid length"));
     }
 
-    let mut arr = Vec::with_capacity(len as usize);
+    let mut arr = Vec::with_capacity((len as usize).min(ARRAY_PREALLOC));
     for _ in 0..len {
         arr.push(parse_frame(input)?);
     }
@@ -158,43 +176,55 @@ fn array_len(input: &mut &[u8]) -> PResult<()> {
 
 // - boolean: "#<t|f>\r\n"
 fn boolean(input: &mut &[u8]) -> PResult<bool> {
-    let b = terminated(alt(('t', 'f')), CRLF).parse_next(input)?;
+    let b = terminated(alt(('t', 'f')), crlf).parse_next(input)?;
     Ok(b == 't')
 }
 
 // - double: ",[<+|->]<integral>[.<fractional>][<E|e>[sign]<exponent>]\r\n"
 fn decimal(input: &mut &[u8]) -> PResult<f64> {
-    terminated(float, CRLF).parse_next(input)
+    terminated(float, crlf).parse_next(input)
 }
 
 // - map: %2\r\n+key1\r\n$6\r\nvalue1\r\n+key2\r\n$6\r\nvalue2\r\n
+// the length counts key/value pairs, keys may be any frame that has a string form
 fn map(input: &mut &[u8]) -> PResult<RespMap> {
     let len = integer(input)?;
-    if len <= 0 {
+    if len < 0 {
         return Err(err_cur("Invalid length"));
     }
 
-    let len = len / 2;
     let mut map = RespMap::new();
     for _ in 0..len {
-        let key = preceded('+', parse_string).parse_next(input)?;
+        let key = map_key(parse_frame(input)?)?;
         let value = parse_frame(input)?;
-        map.insert(key, value);
+        // 和 v1 的解码器一致, "+1", "$1\r\n1" 和 ":1" 转成字符串后也算同一个 key
+        if map.insert(key, value).is_some() {
+            return Err(err_cur("duplicate map key"));
+        }
     }
     Ok(map)
 }
 
+// RespMap is keyed by String, so keys are stored in their string form
+fn map_key(key: RespFrame) -> PResult<String> {
+    match key {
+        RespFrame::SimpleString(s) => Ok(s.0),
+        RespFrame::BulkString(BulkString(Some(s))) => {
+            String::from_utf8(s).map_err(|_| err_cur("Invalid map key"))
+        }
+        RespFrame::Integer(i) => Ok(i.to_string()),
+        _ => Err(err_cur("Unsupported map key type")),
+    }
+}
+
 fn map_len(input: &mut &[u8]) -> PResult<()> {
     let len = integer(input)?;
-    if len <= 0 {
+    if len < 0 {
         return Err(err_cur("Invalid length"));
     }
 
-    let len = len / 2;
     for _ in 0..len {
-        terminated(take_until(0.., CRLF), CRLF)
-            .value(())
-            .parse_next(input)?;
+        parse_frame_len(input)?;
         parse_frame_len(input)?;
     }
     Ok(())
@@ -202,15 +232,32 @@ fn map_len(input: &mut &[u8]) -> PResult<()> {
 
 // null: "_\r\n"
 fn null(input: &mut &[u8]) -> PResult<RespNull> {
-    "\r\n".value(RespNull).parse_next(input)
+    crlf.value(RespNull).parse_next(input)
 }
 
 fn parse_string(input: &mut &[u8]) -> PResult<String> {
-    terminated(take_until(0.., CRLF), CRLF)
-        .map(|s: &[u8]| String::from_utf8_lossy(s).to_string())
+    line.map(|s: &[u8]| String::from_utf8_lossy(s).to_string())
         .parse_next(input)
 }
 
+// a line terminated by "\r\n", or by a bare "\n" when lenient newlines are enabled
+fn line<'a>(input: &mut &'a [u8]) -> PResult<&'a [u8]> {
+    if lenient_newlines() {
+        let s = terminated(take_until(0.., b'\n'), b'\n').parse_next(input)?;
+        Ok(s.strip_suffix(b"\r").unwrap_or(s))
+    } else {
+        terminated(take_until(0.., CRLF), CRLF).parse_next(input)
+    }
+}
+
+fn crlf<'a>(input: &mut &'a [u8]) -> PResult<&'a [u8]> {
+    if lenient_newlines() {
+        alt((CRLF, b"\n".as_slice())).parse_next(input)
+    } else {
+        literal(CRLF).parse_next(input)
+    }
+}
+
 fn err_cur(_s: impl Into<String>) -> ErrMode<ContextError> {
     let context = ContextError::default();
     ErrMode::Cut(context)
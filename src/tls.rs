@@ -0,0 +1,29 @@
+use std::{fs::File, io::BufReader, path::Path, sync::Arc};
+
+use anyhow::Context;
+use tokio_rustls::{rustls::ServerConfig as RustlsConfig, TlsAcceptor};
+
+use crate::TlsConfig;
+
+/// Build the acceptor wrapping every TCP connection from the PEM files in `config`
+pub fn acceptor(config: &TlsConfig) -> anyhow::Result<TlsAcceptor> {
+    let certs = rustls_pemfile::certs(&mut open(&config.cert)?)
+        .collect::<Result<Vec<_>, _>>()
+        .with_context(|| format!("invalid certificate in {}", config.cert.display()))?;
+    let key = rustls_pemfile::private_key(&mut open(&config.key)?)
+        .with_context(|| format!("invalid private key in {}", config.key.display()))?
+        .with_context(|| format!("no private key in {}", config.key.display()))?;
+
+    let server = RustlsConfig::builder_with_provider(Arc::new(
+        tokio_rustls::rustls::crypto::ring::default_provider(),
+    ))
+    .with_safe_default_protocol_versions()?
+    .with_no_client_auth()
+    .with_single_cert(certs, key)?;
+    Ok(TlsAcceptor::from(Arc::new(server)))
+}
+
+fn open(path: &Path) -> anyhow::Result<BufReader<File>> {
+    let file = File::open(path).with_context(|| format!("failed to open {}", path.display()))?;
+    Ok(BufReader::new(file))
+}
@@ -0,0 +1,18 @@
+mod support;
+
+use anyhow::Result;
+use simple_redis::{Backend, RespFrame, SimpleString};
+use support::TestClient;
+
+#[tokio::test]
+async fn set_then_get_end_to_end() -> Result<()> {
+    let mut client = TestClient::start(Backend::new()).await?;
+    assert_eq!(client.get("key").await?, None);
+
+    client.set("key", "value").await?;
+    assert_eq!(client.get("key").await?, Some(b"value".to_vec()));
+
+    let reply = client.raw(&["echo", "hello"]).await?;
+    assert_eq!(reply, RespFrame::SimpleString(SimpleString::new("hello")));
+    Ok(())
+}
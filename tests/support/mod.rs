@@ -0,0 +1,62 @@
+use anyhow::Result;
+use bytes::BytesMut;
+use simple_redis::{
+    network, Backend, BulkString, RespArray, RespDecodeV2, RespEncode, RespError, RespFrame,
+};
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::{TcpListener, TcpStream},
+};
+
+/// A minimal client talking to a server started on an ephemeral port
+pub struct TestClient {
+    stream: TcpStream,
+    buf: BytesMut,
+}
+
+impl TestClient {
+    /// Start a fresh server with `backend` and connect to it
+    pub async fn start(backend: Backend) -> Result<Self> {
+        let listener = TcpListener::bind("127.0.0.1:0").await?;
+        let addr = listener.local_addr()?;
+        tokio::spawn(network::run(listener, backend));
+        let stream = TcpStream::connect(addr).await?;
+        Ok(Self {
+            stream,
+            buf: BytesMut::new(),
+        })
+    }
+
+    /// Send a command and wait for its reply
+    pub async fn raw(&mut self, cmd: &[&str]) -> Result<RespFrame> {
+        let args: Vec<RespFrame> = cmd.iter().map(|s| BulkString::new(*s).into()).collect();
+        let frame: RespFrame = RespArray::new(args).into();
+        self.stream.write_all(&frame.encode()).await?;
+        loop {
+            match RespFrame::decode(&mut self.buf) {
+                Ok(frame) => return Ok(frame),
+                Err(RespError::NotComplete) => {
+                    if self.stream.read_buf(&mut self.buf).await? == 0 {
+                        anyhow::bail!("connection closed by server");
+                    }
+                }
+                Err(e) => return Err(e.into()),
+            }
+        }
+    }
+
+    pub async fn set(&mut self, key: &str, value: &str) -> Result<()> {
+        match self.raw(&["set", key, value]).await? {
+            RespFrame::SimpleString(s) if s.as_str() == "OK" => Ok(()),
+            frame => anyhow::bail!("unexpected SET reply: {:?}", frame),
+        }
+    }
+
+    pub async fn get(&mut self, key: &str) -> Result<Option<Vec<u8>>> {
+        match self.raw(&["get", key]).await? {
+            RespFrame::BulkString(s) => Ok((*s).clone()),
+            RespFrame::Null(_) => Ok(None),
+            frame => anyhow::bail!("unexpected GET reply: {:?}", frame),
+        }
+    }
+}